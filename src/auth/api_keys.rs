@@ -0,0 +1,349 @@
+use crate::auth::{never, unix_time};
+use crate::models::{McpError, McpResult, TokenInfo};
+use crate::sync::{lock, read, write};
+use anyhow::{Context, Result};
+use argon2::{Argon2, PasswordHash, PasswordVerifier};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::SystemTime;
+use tokio::sync::Semaphore;
+
+/// How many Argon2 verifications may run at once across all requests.
+const MAX_CONCURRENT_ARGON2: usize = 4;
+
+/// Static API keys loaded from a JSON file, for deployments without an
+/// authorization server.
+///
+/// Only hashes of the keys are stored, either as `sha256:<hex digest>` or
+/// as an Argon2 PHC string (`$argon2id$...`). Each key maps to a
+/// [`TokenInfo`] for its owner, with its scopes and optional expiry:
+///
+/// ```json
+/// {
+///   "keys": [
+///     {"owner": "ci", "hash": "sha256:9f86d0...", "scopes": ["time:read"]},
+///     {"owner": "ops", "id": "ops1", "hash": "$argon2id$v=19$...",
+///      "scopes": ["time:admin"], "expires_at": "2027-01-01T00:00:00Z"}
+///   ]
+/// }
+/// ```
+///
+/// Argon2 keys need an `id`, and clients present them as `<id>.<secret>`
+/// with the hash taken over that whole string. The id picks the one entry
+/// to verify, so a wrong key costs at most one Argon2 run.
+///
+/// Clones share their keys, so [`ApiKeyStore::reload`] on any of them
+/// applies everywhere.
+#[derive(Clone)]
+pub struct ApiKeyStore {
+    path: PathBuf,
+    keys: Arc<RwLock<Arc<KeySet>>>,
+    argon2_slots: Arc<Semaphore>,
+}
+
+impl ApiKeyStore {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let keys = KeySet::load(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            keys: Arc::new(RwLock::new(Arc::new(keys))),
+            argon2_slots: Arc::new(Semaphore::new(MAX_CONCURRENT_ARGON2)),
+        })
+    }
+
+    /// Re-reads the key file. On error the current keys stay in place.
+    pub fn reload(&self) -> Result<()> {
+        let keys = KeySet::load(&self.path)?;
+        let count = keys.len();
+        *write(&self.keys) = Arc::new(keys);
+        tracing::info!("Loaded {} API keys from {}", count, self.path.display());
+        Ok(())
+    }
+
+    /// Reloads the key file whenever the process receives SIGHUP.
+    #[cfg(unix)]
+    pub fn reload_on_hangup(&self) {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut hangup = match signal(SignalKind::hangup()) {
+            Ok(hangup) => hangup,
+            Err(e) => {
+                tracing::warn!("Failed to install SIGHUP handler: {}", e);
+                return;
+            }
+        };
+
+        let store = self.clone();
+        tokio::spawn(async move {
+            while hangup.recv().await.is_some() {
+                if let Err(e) = store.reload() {
+                    tracing::error!("Failed to reload API keys: {:#}", e);
+                }
+            }
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.current().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The owner of `key`, or `None` if it isn't one of the configured keys.
+    pub async fn validate(&self, key: &str) -> McpResult<Option<TokenInfo>> {
+        let keys = self.current();
+        let digest: [u8; 32] = Sha256::digest(key.as_bytes()).into();
+
+        let api_key = match keys.find_by_digest(&digest) {
+            Some(api_key) => api_key,
+            None => {
+                let Some(index) = keys.find_by_id(key) else {
+                    return Ok(None);
+                };
+
+                // Argon2 is deliberately slow, so keep it off the runtime and
+                // limit how many threads it can occupy.
+                let _slot = self
+                    .argon2_slots
+                    .acquire()
+                    .await
+                    .map_err(|_| McpError::internal_error("API key verification failed"))?;
+                let key = key.to_string();
+                let set = keys.clone();
+                let verified =
+                    tokio::task::spawn_blocking(move || set.verify_argon2(index, &key, digest))
+                        .await
+                        .map_err(|_| McpError::internal_error("API key verification failed"))?;
+                if !verified {
+                    return Ok(None);
+                }
+                &keys.keys[index]
+            }
+        };
+
+        api_key.token_info().map(Some)
+    }
+
+    fn current(&self) -> Arc<KeySet> {
+        read(&self.keys).clone()
+    }
+}
+
+impl std::fmt::Debug for ApiKeyStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApiKeyStore")
+            .field("path", &self.path)
+            .field("keys", &self.len())
+            .finish()
+    }
+}
+
+/// One version of the key file.
+struct KeySet {
+    keys: Vec<ApiKey>,
+    /// Indexes into `keys` of the SHA-256 hashed keys, by digest.
+    sha256: HashMap<[u8; 32], usize>,
+    /// Indexes into `keys` of the Argon2 hashed keys, by id.
+    argon2: HashMap<String, usize>,
+    /// Argon2 keys already verified, by SHA-256 digest of the presented
+    /// key, so each costs one Argon2 run per reload rather than per request.
+    verified: Mutex<HashMap<[u8; 32], usize>>,
+}
+
+impl KeySet {
+    fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let file: KeyFile = serde_json::from_str(&contents)
+            .with_context(|| format!("Invalid API key file {}", path.display()))?;
+
+        let keys = file
+            .keys
+            .into_iter()
+            .map(ApiKey::try_from)
+            .collect::<Result<Vec<_>>>()
+            .with_context(|| format!("Invalid API key file {}", path.display()))?;
+
+        let sha256 = keys
+            .iter()
+            .enumerate()
+            .filter_map(|(index, key)| match key.hash {
+                KeyHash::Sha256(digest) => Some((digest, index)),
+                KeyHash::Argon2(_) => None,
+            })
+            .collect();
+
+        let mut argon2 = HashMap::new();
+        for (index, key) in keys.iter().enumerate() {
+            if let (KeyHash::Argon2(_), Some(id)) = (&key.hash, &key.id) {
+                if argon2.insert(id.clone(), index).is_some() {
+                    anyhow::bail!("Duplicate API key id {} in {}", id, path.display());
+                }
+            }
+        }
+
+        Ok(Self {
+            keys,
+            sha256,
+            argon2,
+            verified: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    fn find_by_digest(&self, digest: &[u8; 32]) -> Option<&ApiKey> {
+        let index = self
+            .sha256
+            .get(digest)
+            .or(lock(&self.verified).get(digest))
+            .copied()?;
+        self.keys.get(index)
+    }
+
+    /// The index of the Argon2 key whose id `key` starts with.
+    fn find_by_id(&self, key: &str) -> Option<usize> {
+        let (id, _) = key.split_once('.')?;
+        self.argon2.get(id).copied()
+    }
+
+    fn verify_argon2(&self, index: usize, key: &str, digest: [u8; 32]) -> bool {
+        let verified = match &self.keys[index].hash {
+            KeyHash::Argon2(hash) => PasswordHash::new(hash)
+                .map(|hash| Argon2::default().verify_password(key.as_bytes(), &hash))
+                .is_ok_and(|verified| verified.is_ok()),
+            KeyHash::Sha256(_) => false,
+        };
+
+        if verified {
+            lock(&self.verified).insert(digest, index);
+        }
+        verified
+    }
+}
+
+struct ApiKey {
+    owner: String,
+    id: Option<String>,
+    scopes: Vec<String>,
+    expires_at: Option<DateTime<Utc>>,
+    hash: KeyHash,
+}
+
+enum KeyHash {
+    Sha256([u8; 32]),
+    /// A PHC string, checked to parse when loaded.
+    Argon2(String),
+}
+
+impl ApiKey {
+    fn token_info(&self) -> McpResult<TokenInfo> {
+        let expires_at = match self.expires_at {
+            Some(expires_at) => {
+                unix_time(u64::try_from(expires_at.timestamp()).unwrap_or_default())
+            }
+            None => never(),
+        };
+        if expires_at <= SystemTime::now() {
+            return Err(McpError::new(401, "API key expired"));
+        }
+
+        Ok(TokenInfo {
+            user_id: self.owner.clone(),
+            scopes: self.scopes.clone(),
+            expires_at,
+        })
+    }
+}
+
+impl TryFrom<KeyEntry> for ApiKey {
+    type Error = anyhow::Error;
+
+    fn try_from(entry: KeyEntry) -> Result<Self> {
+        let hash = if let Some(hex) = entry.hash.strip_prefix("sha256:") {
+            KeyHash::Sha256(
+                decode_sha256(hex)
+                    .with_context(|| format!("Invalid SHA-256 hash for {}", entry.owner))?,
+            )
+        } else if entry.hash.starts_with("$argon2") {
+            check_argon2(&entry.hash)
+                .map_err(|e| anyhow::anyhow!("Invalid Argon2 hash for {}: {}", entry.owner, e))?;
+            match entry.id.as_deref() {
+                None => anyhow::bail!("Argon2 key for {} needs an id", entry.owner),
+                Some(id) if id.is_empty() || id.contains('.') => {
+                    anyhow::bail!(
+                        "Invalid id for {}; ids are non-empty and contain no '.'",
+                        entry.owner
+                    )
+                }
+                Some(_) => {}
+            }
+            KeyHash::Argon2(entry.hash)
+        } else {
+            anyhow::bail!(
+                "Unsupported hash for {}; expected sha256:<hex> or an Argon2 PHC string",
+                entry.owner
+            );
+        };
+
+        Ok(Self {
+            owner: entry.owner,
+            id: entry.id,
+            scopes: entry.scopes,
+            expires_at: entry.expires_at,
+            hash,
+        })
+    }
+}
+
+/// Checks `phc` is a complete Argon2 hash: a known variant with valid
+/// parameters, a salt and an output.
+fn check_argon2(phc: &str) -> Result<(), argon2::password_hash::Error> {
+    let hash = PasswordHash::new(phc)?;
+    argon2::Algorithm::try_from(hash.algorithm)?;
+    argon2::Params::try_from(&hash)?;
+    if hash.salt.is_none() || hash.hash.is_none() {
+        return Err(argon2::password_hash::Error::PhcStringTrailingData);
+    }
+    Ok(())
+}
+
+fn decode_sha256(hex: &str) -> Result<[u8; 32]> {
+    if hex.len() != 64 || !hex.is_ascii() {
+        anyhow::bail!("expected 64 hex digits");
+    }
+
+    let mut digest = [0u8; 32];
+    for (byte, pair) in digest.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        let pair = std::str::from_utf8(pair)?;
+        *byte = u8::from_str_radix(pair, 16).context("expected 64 hex digits")?;
+    }
+    Ok(digest)
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyFile {
+    keys: Vec<KeyEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyEntry {
+    owner: String,
+    #[serde(default)]
+    id: Option<String>,
+    hash: String,
+    #[serde(default)]
+    scopes: Vec<String>,
+    #[serde(default)]
+    expires_at: Option<DateTime<Utc>>,
+}
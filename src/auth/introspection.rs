@@ -0,0 +1,264 @@
+use crate::auth::unix_time;
+use crate::models::{McpError, McpResult, TokenInfo};
+use crate::sync::lock;
+use anyhow::{Context, Result};
+use oauth2::url::Url;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// How long introspection results are reused unless configured otherwise.
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Upper bound on cached results; expired entries are pruned when it's hit.
+const MAX_CACHE_ENTRIES: usize = 10_000;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a token the authorization server reports no `exp` for is
+/// trusted, so long-lived connections still re-authenticate eventually.
+const UNBOUNDED_TOKEN_LIFETIME: Duration = Duration::from_secs(3600);
+
+/// Validates opaque bearer tokens by asking the authorization server about
+/// them through its RFC 7662 introspection endpoint.
+///
+/// Results, including inactive tokens, are cached for the configured TTL
+/// (never past the token's own expiry), keyed by the token's SHA-256 hash so
+/// tokens themselves aren't held in memory.
+#[derive(Clone)]
+pub struct IntrospectionClient {
+    endpoint: Url,
+    client_id: String,
+    client_secret: Option<String>,
+    issuer: Option<String>,
+    audience: Vec<String>,
+    cache_ttl: Duration,
+    http: reqwest::Client,
+    cache: Arc<Mutex<HashMap<[u8; 32], CachedResult>>>,
+}
+
+#[derive(Clone)]
+struct CachedResult {
+    result: McpResult<TokenInfo>,
+    expires: Instant,
+}
+
+impl IntrospectionClient {
+    /// Introspects tokens at `endpoint`, authenticating as `client_id` with
+    /// HTTP Basic authentication.
+    pub fn new(endpoint: &str, client_id: impl Into<String>) -> Result<Self> {
+        let endpoint = Url::parse(endpoint)
+            .with_context(|| format!("Invalid introspection endpoint {}", endpoint))?;
+        if !matches!(endpoint.scheme(), "http" | "https") {
+            anyhow::bail!("Introspection endpoint must be an http or https URL");
+        }
+
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .context("Failed to build introspection HTTP client")?;
+
+        Ok(Self {
+            endpoint,
+            client_id: client_id.into(),
+            client_secret: None,
+            issuer: None,
+            audience: Vec::new(),
+            cache_ttl: DEFAULT_CACHE_TTL,
+            http,
+            cache: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    pub fn with_client_secret(mut self, secret: impl Into<String>) -> Self {
+        self.client_secret = Some(secret.into());
+        self
+    }
+
+    /// Requires the introspection response's `iss` to equal `issuer`.
+    pub fn with_issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuer = Some(issuer.into());
+        self
+    }
+
+    /// Accepts tokens whose `aud` includes `audience`. May be called more
+    /// than once; once called, tokens without an `aud` are rejected.
+    pub fn with_audience(mut self, audience: impl Into<String>) -> Self {
+        self.audience.push(audience.into());
+        self
+    }
+
+    /// How long to reuse an introspection result. Zero disables caching.
+    pub fn with_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = ttl;
+        self
+    }
+
+    /// Verifies `token` with the authorization server, or from the cache,
+    /// and returns the caller it identifies.
+    pub async fn validate(&self, token: &str) -> McpResult<TokenInfo> {
+        let key: [u8; 32] = Sha256::digest(token.as_bytes()).into();
+        if let Some(result) = self.cached(&key) {
+            return result;
+        }
+
+        let response = self.introspect(token).await?;
+        let result = self.token_info(response);
+        self.store(key, &result);
+        result
+    }
+
+    fn cached(&self, key: &[u8; 32]) -> Option<McpResult<TokenInfo>> {
+        lock(&self.cache)
+            .get(key)
+            .filter(|entry| entry.expires > Instant::now())
+            .map(|entry| entry.result.clone())
+    }
+
+    fn store(&self, key: [u8; 32], result: &McpResult<TokenInfo>) {
+        let mut ttl = self.cache_ttl;
+        if let Ok(info) = result {
+            // A valid token is cached no longer than it lives.
+            let remaining = info
+                .expires_at
+                .duration_since(SystemTime::now())
+                .unwrap_or_default();
+            ttl = ttl.min(remaining);
+        }
+        let now = Instant::now();
+        let Some(expires) = now.checked_add(ttl).filter(|expires| *expires > now) else {
+            return;
+        };
+
+        let mut cache = lock(&self.cache);
+        if cache.len() >= MAX_CACHE_ENTRIES {
+            cache.retain(|_, entry| entry.expires > now);
+            if cache.len() >= MAX_CACHE_ENTRIES {
+                return;
+            }
+        }
+        cache.insert(
+            key,
+            CachedResult {
+                result: result.clone(),
+                expires,
+            },
+        );
+    }
+
+    async fn introspect(&self, token: &str) -> McpResult<IntrospectionResponse> {
+        let response = self
+            .http
+            .post(self.endpoint.clone())
+            .basic_auth(&self.client_id, self.client_secret.as_ref())
+            .header(reqwest::header::ACCEPT, "application/json")
+            .form(&[("token", token), ("token_type_hint", "access_token")])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| {
+                tracing::warn!("Token introspection request failed: {}", e);
+                McpError::new(503, "Token introspection unavailable")
+            })?;
+
+        response.json().await.map_err(|e| {
+            tracing::warn!("Invalid token introspection response: {}", e);
+            McpError::new(503, "Token introspection unavailable")
+        })
+    }
+
+    fn token_info(&self, response: IntrospectionResponse) -> McpResult<TokenInfo> {
+        if !response.active {
+            return Err(McpError::new(401, "Invalid token"));
+        }
+
+        let now = SystemTime::now();
+        if response.nbf.is_some_and(|nbf| unix_time(nbf) > now) {
+            return Err(McpError::new(401, "Token not yet valid"));
+        }
+        if let Some(issuer) = &self.issuer {
+            if response.iss.as_ref() != Some(issuer) {
+                return Err(McpError::new(401, "Invalid token issuer"));
+            }
+        }
+        if !self.audience.is_empty() {
+            let audiences = match response.aud {
+                Some(Audience::One(audience)) => vec![audience],
+                Some(Audience::Many(audiences)) => audiences,
+                None => Vec::new(),
+            };
+            if !audiences.iter().any(|aud| self.audience.contains(aud)) {
+                return Err(McpError::new(401, "Invalid token audience"));
+            }
+        }
+
+        let expires_at = match response.exp {
+            Some(exp) => unix_time(exp),
+            None => now + UNBOUNDED_TOKEN_LIFETIME,
+        };
+        if expires_at <= now {
+            return Err(McpError::new(401, "Token expired"));
+        }
+
+        let user_id = response
+            .sub
+            .or(response.username)
+            .or(response.client_id)
+            .ok_or_else(|| McpError::new(401, "Token is missing required claims"))?;
+
+        Ok(TokenInfo {
+            user_id,
+            scopes: response
+                .scope
+                .unwrap_or_default()
+                .split_whitespace()
+                .map(str::to_string)
+                .collect(),
+            expires_at,
+        })
+    }
+}
+
+impl fmt::Debug for IntrospectionClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IntrospectionClient")
+            .field("endpoint", &self.endpoint.as_str())
+            .field("client_id", &self.client_id)
+            .field("issuer", &self.issuer)
+            .field("audience", &self.audience)
+            .field("cache_ttl", &self.cache_ttl)
+            .finish()
+    }
+}
+
+/// The RFC 7662 §2.2 response fields this server reads.
+#[derive(Deserialize)]
+struct IntrospectionResponse {
+    active: bool,
+    #[serde(default)]
+    scope: Option<String>,
+    #[serde(default)]
+    client_id: Option<String>,
+    #[serde(default)]
+    username: Option<String>,
+    #[serde(default)]
+    sub: Option<String>,
+    #[serde(default)]
+    exp: Option<u64>,
+    #[serde(default)]
+    nbf: Option<u64>,
+    #[serde(default)]
+    aud: Option<Audience>,
+    #[serde(default)]
+    iss: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Audience {
+    One(String),
+    Many(Vec<String>),
+}
@@ -0,0 +1,271 @@
+use crate::auth::unix_time;
+use crate::models::{McpError, McpResult, TokenInfo};
+use anyhow::{Context, Result};
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::jwk::{AlgorithmParameters, EllipticCurve, JwkSet};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Algorithms accepted for bearer tokens. Each verification key is bound to
+/// exactly one of them, so a token can't pick a weaker algorithm than the
+/// key was issued for.
+const SUPPORTED_ALGORITHMS: [Algorithm; 3] = [Algorithm::HS256, Algorithm::RS256, Algorithm::ES256];
+
+/// Validates JWT bearer tokens against a shared secret or a set of public
+/// keys, and maps their claims to a [`TokenInfo`].
+///
+/// `exp` is required and `nbf` is honoured when present. `iss` and `aud`
+/// are only checked once an issuer or audience has been configured, and
+/// `aud` is then required.
+#[derive(Clone)]
+pub struct JwtValidator {
+    keys: Arc<[VerificationKey]>,
+    issuer: Option<String>,
+    audience: Vec<String>,
+}
+
+struct VerificationKey {
+    kid: Option<String>,
+    algorithm: Algorithm,
+    key: DecodingKey,
+}
+
+impl JwtValidator {
+    /// Validates HS256 tokens signed with `secret`.
+    pub fn hs256(secret: &[u8]) -> Self {
+        Self::with_keys(vec![VerificationKey {
+            kid: None,
+            algorithm: Algorithm::HS256,
+            key: DecodingKey::from_secret(secret),
+        }])
+    }
+
+    /// Validates RS256 or ES256 tokens against a PEM-encoded public key.
+    pub fn from_pem(pem: &[u8]) -> Result<Self> {
+        let key = if let Ok(key) = DecodingKey::from_rsa_pem(pem) {
+            VerificationKey {
+                kid: None,
+                algorithm: Algorithm::RS256,
+                key,
+            }
+        } else if let Ok(key) = DecodingKey::from_ec_pem(pem) {
+            VerificationKey {
+                kid: None,
+                algorithm: Algorithm::ES256,
+                key,
+            }
+        } else {
+            anyhow::bail!("Expected an RSA or P-256 public key in PEM format");
+        };
+
+        Ok(Self::with_keys(vec![key]))
+    }
+
+    /// Validates tokens against every key in a JWKS document. Tokens naming
+    /// a `kid` are only checked against the key with that id.
+    pub fn from_jwks(jwks: &str) -> Result<Self> {
+        let set: JwkSet = serde_json::from_str(jwks).context("Invalid JWKS")?;
+
+        let keys = set
+            .keys
+            .iter()
+            .map(|jwk| {
+                let algorithm = match jwk.common.key_algorithm {
+                    Some(algorithm) => Algorithm::from_str(&algorithm.to_string())?,
+                    None => match &jwk.algorithm {
+                        AlgorithmParameters::RSA(_) => Algorithm::RS256,
+                        AlgorithmParameters::EllipticCurve(params)
+                            if params.curve == EllipticCurve::P256 =>
+                        {
+                            Algorithm::ES256
+                        }
+                        AlgorithmParameters::OctetKey(_) => Algorithm::HS256,
+                        _ => anyhow::bail!("Unsupported key type in JWKS"),
+                    },
+                };
+                if !SUPPORTED_ALGORITHMS.contains(&algorithm) {
+                    anyhow::bail!("Unsupported algorithm {:?} in JWKS", algorithm);
+                }
+
+                Ok(VerificationKey {
+                    kid: jwk.common.key_id.clone(),
+                    algorithm,
+                    key: DecodingKey::from_jwk(jwk)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if keys.is_empty() {
+            anyhow::bail!("JWKS contains no keys");
+        }
+
+        Ok(Self::with_keys(keys))
+    }
+
+    /// Loads public keys from a file holding either a JWKS document or a
+    /// single PEM-encoded key.
+    pub fn from_key_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+
+        if contents.trim_start().starts_with('{') {
+            Self::from_jwks(&contents)
+        } else {
+            Self::from_pem(contents.as_bytes())
+        }
+        .with_context(|| format!("Failed to load keys from {}", path.display()))
+    }
+
+    fn with_keys(keys: Vec<VerificationKey>) -> Self {
+        Self {
+            keys: keys.into(),
+            issuer: None,
+            audience: Vec::new(),
+        }
+    }
+
+    /// Requires the `iss` claim to equal `issuer`.
+    pub fn with_issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuer = Some(issuer.into());
+        self
+    }
+
+    /// Accepts tokens whose `aud` includes `audience`. May be called more
+    /// than once to accept several audiences.
+    pub fn with_audience(mut self, audience: impl Into<String>) -> Self {
+        self.audience.push(audience.into());
+        self
+    }
+
+    /// Verifies `token` and returns the caller it identifies.
+    pub fn validate(&self, token: &str) -> McpResult<TokenInfo> {
+        let header = jsonwebtoken::decode_header(token).map_err(token_error)?;
+
+        let mut candidates = self
+            .keys
+            .iter()
+            .filter(|key| key.algorithm == header.alg)
+            .filter(|key| match (&header.kid, &key.kid) {
+                (Some(wanted), Some(kid)) => wanted == kid,
+                _ => true,
+            })
+            .peekable();
+
+        if candidates.peek().is_none() {
+            return Err(McpError::new(401, "Invalid token: no matching key"));
+        }
+
+        let mut last_error = None;
+        for key in candidates {
+            match jsonwebtoken::decode::<Claims>(token, &key.key, &self.validation(key.algorithm)) {
+                Ok(data) => return Ok(data.claims.into_token_info()),
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        Err(token_error(last_error.expect("at least one key was tried")))
+    }
+
+    fn validation(&self, algorithm: Algorithm) -> Validation {
+        let mut validation = Validation::new(algorithm);
+        validation.validate_nbf = true;
+        validation.set_required_spec_claims(&["exp", "sub"]);
+
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        if self.audience.is_empty() {
+            validation.validate_aud = false;
+        } else {
+            // Otherwise a token without `aud` would pass for any audience.
+            validation.set_required_spec_claims(&["exp", "sub", "aud"]);
+            validation.set_audience(&self.audience);
+        }
+
+        validation
+    }
+}
+
+impl fmt::Debug for JwtValidator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let keys: Vec<_> = self
+            .keys
+            .iter()
+            .map(|key| (key.kid.as_deref(), key.algorithm))
+            .collect();
+
+        f.debug_struct("JwtValidator")
+            .field("keys", &keys)
+            .field("issuer", &self.issuer)
+            .field("audience", &self.audience)
+            .finish()
+    }
+}
+
+/// The registered and scope claims this server reads.
+#[derive(Deserialize)]
+struct Claims {
+    sub: String,
+    exp: u64,
+    /// Space-separated scopes, as issued by most OAuth servers.
+    #[serde(default)]
+    scope: Option<String>,
+    /// Scopes as a list (or string), as issued by Azure AD and Okta.
+    #[serde(default)]
+    scp: Option<ScopeClaim>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ScopeClaim {
+    List(Vec<String>),
+    Joined(String),
+}
+
+impl Claims {
+    fn into_token_info(self) -> TokenInfo {
+        let mut scopes: Vec<String> = self
+            .scope
+            .iter()
+            .flat_map(|scope| scope.split_whitespace())
+            .map(str::to_string)
+            .collect();
+
+        let listed = match self.scp {
+            Some(ScopeClaim::List(list)) => list,
+            Some(ScopeClaim::Joined(joined)) => {
+                joined.split_whitespace().map(str::to_string).collect()
+            }
+            None => Vec::new(),
+        };
+        for scope in listed {
+            if !scopes.contains(&scope) {
+                scopes.push(scope);
+            }
+        }
+
+        TokenInfo {
+            user_id: self.sub,
+            scopes,
+            expires_at: unix_time(self.exp),
+        }
+    }
+}
+
+fn token_error(error: jsonwebtoken::errors::Error) -> McpError {
+    let message = match error.kind() {
+        ErrorKind::ExpiredSignature => "Token expired",
+        ErrorKind::ImmatureSignature => "Token not yet valid",
+        ErrorKind::InvalidIssuer => "Invalid token issuer",
+        ErrorKind::InvalidAudience => "Invalid token audience",
+        ErrorKind::InvalidSignature => "Invalid token signature",
+        ErrorKind::MissingRequiredClaim(_) => "Token is missing required claims",
+        _ => "Invalid token",
+    };
+    tracing::debug!("Rejected bearer token: {}", error);
+    McpError::new(401, message)
+}
@@ -0,0 +1,239 @@
+pub mod api_keys;
+pub mod introspection;
+pub mod jwt;
+pub mod policy;
+pub mod resource;
+
+pub use api_keys::ApiKeyStore;
+pub use introspection::IntrospectionClient;
+pub use jwt::JwtValidator;
+pub use policy::{ScopePolicy, ScopeTarget};
+pub use resource::ProtectedResource;
+
+use crate::config::ServerConfig;
+use crate::models::{McpError, McpResult, TokenInfo};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::RwLock;
+
+/// Header carrying an API key as an alternative to `Authorization: Bearer`.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+#[derive(Clone)]
+pub struct AuthManager {
+    enabled: bool,
+    pub tokens: Arc<RwLock<HashMap<String, TokenInfo>>>,
+    jwt: Option<JwtValidator>,
+    introspection: Option<IntrospectionClient>,
+    api_keys: Option<ApiKeyStore>,
+    resource: Option<ProtectedResource>,
+}
+
+impl AuthManager {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            tokens: Arc::new(RwLock::new(HashMap::new())),
+            jwt: None,
+            introspection: None,
+            api_keys: None,
+            resource: None,
+        }
+    }
+
+    /// Auth manager for a transport, using the validators in `config`.
+    /// Tokens must name this server's canonical resource URI, or one of the
+    /// configured audiences, as their audience.
+    pub fn from_config(config: &ServerConfig) -> Self {
+        let resource = config.protected_resource();
+        let mut manager = Self::new(config.auth_enabled);
+        if let Some(jwt) = config.jwt.clone() {
+            manager = manager.with_jwt(jwt.with_audience(resource.resource()));
+        }
+        if let Some(introspection) = config.introspection.clone() {
+            manager = manager.with_introspection(introspection.with_audience(resource.resource()));
+        }
+        if let Some(api_keys) = config.api_keys.clone() {
+            manager = manager.with_api_keys(api_keys);
+        }
+        manager.with_resource(resource)
+    }
+
+    /// Accepts bearer tokens that `validator` verifies, in addition to those
+    /// in `tokens`.
+    pub fn with_jwt(mut self, validator: JwtValidator) -> Self {
+        self.jwt = Some(validator);
+        self
+    }
+
+    /// Accepts opaque bearer tokens the authorization server reports as
+    /// active. When JWT validation is also configured, tokens shaped like a
+    /// JWT are validated locally and everything else is introspected.
+    pub fn with_introspection(mut self, client: IntrospectionClient) -> Self {
+        self.introspection = Some(client);
+        self
+    }
+
+    /// Accepts the keys in `store`, sent as a bearer token or in the
+    /// `X-API-Key` header.
+    pub fn with_api_keys(mut self, store: ApiKeyStore) -> Self {
+        self.api_keys = Some(store);
+        self
+    }
+
+    /// Points clients at `resource`'s metadata in `WWW-Authenticate`
+    /// challenges.
+    pub fn with_resource(mut self, resource: ProtectedResource) -> Self {
+        self.resource = Some(resource);
+        self
+    }
+
+    /// Identifies the caller from its bearer token. Returns `None` when
+    /// authentication is disabled, meaning the caller is unrestricted.
+    pub async fn authenticate(&self, headers: &HeaderMap) -> McpResult<Option<TokenInfo>> {
+        if !self.enabled {
+            return Ok(None);
+        }
+
+        let Some(auth_header) = headers.get("authorization") else {
+            return match headers.get(API_KEY_HEADER) {
+                Some(key) => {
+                    self.authenticate_api_key(key.to_str().unwrap_or_default())
+                        .await
+                }
+                None => Err(McpError::new(401, "Authorization header required")),
+            };
+        };
+        let auth_header = auth_header
+            .to_str()
+            .map_err(|_| McpError::new(401, "Invalid authorization format"))?;
+
+        if !auth_header.starts_with("Bearer ") {
+            return Err(McpError::new(401, "Invalid authorization format"));
+        }
+
+        let token = &auth_header[7..];
+        match self.tokens.read().await.get(token) {
+            Some(token_info) if !token_info.is_expired() => return Ok(Some(token_info.clone())),
+            Some(_) => return Err(McpError::new(401, "Token expired")),
+            None => {}
+        }
+
+        if let Some(api_keys) = &self.api_keys {
+            if self.jwt.is_none() || !looks_like_jwt(token) {
+                if let Some(token_info) = api_keys.validate(token).await? {
+                    return Ok(Some(token_info));
+                }
+            }
+        }
+
+        match (&self.jwt, &self.introspection) {
+            (Some(jwt), None) => jwt.validate(token).map(Some),
+            (Some(jwt), Some(_)) if looks_like_jwt(token) => jwt.validate(token).map(Some),
+            (_, Some(introspection)) => introspection.validate(token).await.map(Some),
+            (None, None) => Err(McpError::new(401, "Invalid token")),
+        }
+    }
+
+    async fn authenticate_api_key(&self, key: &str) -> McpResult<Option<TokenInfo>> {
+        let token_info = match &self.api_keys {
+            Some(api_keys) => api_keys.validate(key).await?,
+            None => None,
+        };
+        token_info
+            .map(Some)
+            .ok_or_else(|| McpError::new(401, "Invalid API key"))
+    }
+
+    /// The RFC 6750 `WWW-Authenticate` challenge for a 401 or 403 `error`,
+    /// or `None` for any other error. With a protected resource configured,
+    /// it also carries the RFC 9728 `resource_metadata` URL.
+    pub fn challenge(&self, error: &McpError) -> Option<String> {
+        let data = error.data.as_ref();
+        let field = |key: &str| data.and_then(|d| d.get(key)).and_then(|v| v.as_str());
+
+        let mut params = match error.code {
+            // A request without credentials gets no error code.
+            401 if error.message == "Authorization header required" => Vec::new(),
+            401 => vec![
+                ("error", "invalid_token".to_string()),
+                ("error_description", error.message.replace('"', "'")),
+            ],
+            403 => vec![
+                (
+                    "error",
+                    field("error").unwrap_or("insufficient_scope").to_string(),
+                ),
+                ("scope", field("scope").unwrap_or_default().to_string()),
+            ],
+            _ => return None,
+        };
+        if let Some(resource) = &self.resource {
+            params.push(("resource_metadata", resource.metadata_url()));
+        }
+
+        let params: Vec<_> = params
+            .iter()
+            .map(|(key, value)| format!("{}=\"{}\"", key, value))
+            .collect();
+        if params.is_empty() {
+            Some("Bearer".to_string())
+        } else {
+            Some(format!("Bearer {}", params.join(", ")))
+        }
+    }
+
+    /// The HTTP response rejecting a request with `error`, carrying its
+    /// `WWW-Authenticate` challenge.
+    pub fn reject(&self, error: McpError) -> Response {
+        let challenge = self.challenge(&error);
+        let mut response = StatusCode::from(error).into_response();
+        if let Some(value) = challenge.and_then(|c| HeaderValue::from_str(&c).ok()) {
+            response
+                .headers_mut()
+                .insert(header::WWW_AUTHENTICATE, value);
+        }
+        response
+    }
+}
+
+/// The time `secs` seconds after the Unix epoch, as in a token's `exp` or
+/// `nbf` claim. Times too far out to represent are treated as [`never`].
+pub(crate) fn unix_time(secs: u64) -> SystemTime {
+    UNIX_EPOCH
+        .checked_add(Duration::from_secs(secs))
+        .unwrap_or_else(never)
+}
+
+/// Expiry for credentials without one, far enough out not to matter.
+pub(crate) fn never() -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(u64::from(u32::MAX))
+}
+
+/// Whether `token` has the three base64url segments of a signed JWT.
+fn looks_like_jwt(token: &str) -> bool {
+    let segments: Vec<_> = token.split('.').collect();
+    segments.len() == 3
+        && segments.iter().all(|segment| {
+            !segment.is_empty()
+                && segment
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+        })
+}
+
+impl From<McpError> for StatusCode {
+    fn from(error: McpError) -> Self {
+        match error.code {
+            401 => StatusCode::UNAUTHORIZED,
+            403 => StatusCode::FORBIDDEN,
+            503 => StatusCode::SERVICE_UNAVAILABLE,
+            400 | -32602 | -32002..=-32000 => StatusCode::BAD_REQUEST,
+            404 | -32601 => StatusCode::NOT_FOUND,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
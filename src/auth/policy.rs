@@ -0,0 +1,156 @@
+use crate::models::{McpError, McpResult, TokenInfo};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// What kind of item a scope rule applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScopeTarget {
+    Tool,
+    Resource,
+    Prompt,
+}
+
+/// Maps tools, resources and prompts to the scopes that grant access to
+/// them. A caller needs any one of an item's scopes.
+///
+/// Rules are keyed by tool or prompt name, or by resource URI. A key ending
+/// in `*` matches every name starting with what precedes it, and `*` alone
+/// matches everything; an exact key wins over patterns, and longer patterns
+/// win over shorter ones. Items no rule matches are open to every
+/// authenticated caller.
+///
+/// ```json
+/// {
+///   "tools": {"*": ["time:read"], "list_timezones": ["tz:list", "time:admin"]},
+///   "resources": {"time://timezones*": ["tz:list"], "time://*": ["time:read"]},
+///   "prompts": {"*": ["time:read"]}
+/// }
+/// ```
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScopePolicy {
+    #[serde(default)]
+    tools: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    resources: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    prompts: BTreeMap<String, Vec<String>>,
+}
+
+impl ScopePolicy {
+    /// A policy without rules, allowing everything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads a policy from a JSON file in the format shown above.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Invalid scope policy in {}", path.display()))
+    }
+
+    /// Adds a rule granting `pattern`'s items to holders of any of `scopes`.
+    pub fn with_rule<S: Into<String>>(
+        mut self,
+        target: ScopeTarget,
+        pattern: impl Into<String>,
+        scopes: impl IntoIterator<Item = S>,
+    ) -> Self {
+        let scopes = scopes.into_iter().map(Into::into).collect();
+        self.rules_mut(target).insert(pattern.into(), scopes);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty() && self.resources.is_empty() && self.prompts.is_empty()
+    }
+
+    /// Every scope named by a rule, sorted and without duplicates.
+    pub fn scopes(&self) -> Vec<String> {
+        let scopes: BTreeSet<&String> = [&self.tools, &self.resources, &self.prompts]
+            .into_iter()
+            .flat_map(|rules| rules.values().flatten())
+            .collect();
+        scopes.into_iter().cloned().collect()
+    }
+
+    /// The scopes that grant access to `name`, or `None` if no rule covers
+    /// it.
+    pub fn required_scopes(&self, target: ScopeTarget, name: &str) -> Option<&[String]> {
+        let rules = self.rules(target);
+        if let Some(scopes) = rules.get(name) {
+            return Some(scopes);
+        }
+
+        rules
+            .iter()
+            .filter_map(|(pattern, scopes)| {
+                let prefix = pattern.strip_suffix('*')?;
+                name.starts_with(prefix).then_some((prefix.len(), scopes))
+            })
+            .max_by_key(|(len, _)| *len)
+            .map(|(_, scopes)| scopes.as_slice())
+    }
+
+    /// Whether `principal` may use `name`. Without a principal, i.e. when
+    /// authentication is disabled, everything is allowed.
+    pub fn allows(&self, principal: Option<&TokenInfo>, target: ScopeTarget, name: &str) -> bool {
+        self.check(principal, target, name).is_ok()
+    }
+
+    /// Like [`ScopePolicy::allows`], but explains a refusal: 401 for an
+    /// expired token, or 403 `insufficient_scope` naming the scopes that
+    /// would grant access.
+    pub fn check(
+        &self,
+        principal: Option<&TokenInfo>,
+        target: ScopeTarget,
+        name: &str,
+    ) -> McpResult<()> {
+        let Some(principal) = principal else {
+            return Ok(());
+        };
+        if principal.is_expired() {
+            return Err(McpError::new(401, "Token expired"));
+        }
+
+        match self.required_scopes(target, name) {
+            Some(required) if !required.iter().any(|s| principal.scopes.contains(s)) => {
+                Err(insufficient_scope(required))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn rules(&self, target: ScopeTarget) -> &BTreeMap<String, Vec<String>> {
+        match target {
+            ScopeTarget::Tool => &self.tools,
+            ScopeTarget::Resource => &self.resources,
+            ScopeTarget::Prompt => &self.prompts,
+        }
+    }
+
+    fn rules_mut(&mut self, target: ScopeTarget) -> &mut BTreeMap<String, Vec<String>> {
+        match target {
+            ScopeTarget::Tool => &mut self.tools,
+            ScopeTarget::Resource => &mut self.resources,
+            ScopeTarget::Prompt => &mut self.prompts,
+        }
+    }
+}
+
+/// The 403 error for a caller lacking all of `required`. Its `data` carries
+/// the RFC 6750 error code and scopes so HTTP transports can build the
+/// `WWW-Authenticate` challenge.
+pub fn insufficient_scope(required: &[String]) -> McpError {
+    let scope = required.join(" ");
+    McpError::new(403, format!("Insufficient scope: requires {}", scope)).with_data(json!({
+        "error": "insufficient_scope",
+        "scope": scope
+    }))
+}
@@ -0,0 +1,110 @@
+use anyhow::{Context, Result};
+use oauth2::url::Url;
+use serde_json::{json, Value};
+
+/// Where RFC 9728 clients look for a protected resource's metadata.
+pub const METADATA_PATH: &str = "/.well-known/oauth-protected-resource";
+
+/// This server as an OAuth 2.1 protected resource: its canonical URI, which
+/// tokens must name as their audience, and the authorization servers that
+/// issue them.
+#[derive(Clone, Debug)]
+pub struct ProtectedResource {
+    resource: Url,
+    authorization_servers: Vec<String>,
+    scopes_supported: Vec<String>,
+}
+
+impl ProtectedResource {
+    /// Describes the resource at `uri`, an absolute `http` or `https` URI
+    /// without a fragment.
+    pub fn new(uri: &str) -> Result<Self> {
+        Ok(Self {
+            resource: canonical_url(uri)?,
+            authorization_servers: Vec::new(),
+            scopes_supported: Vec::new(),
+        })
+    }
+
+    /// Advertises `issuer` as an authorization server clients can get
+    /// tokens from. May be called more than once.
+    pub fn with_authorization_server(mut self, issuer: &str) -> Result<Self> {
+        let issuer = canonical_url(issuer)
+            .with_context(|| format!("Invalid authorization server {}", issuer))?;
+        self.authorization_servers.push(canonical_string(&issuer));
+        Ok(self)
+    }
+
+    /// Advertises the scopes clients may request for this resource.
+    pub fn with_scopes(mut self, scopes: impl IntoIterator<Item = String>) -> Self {
+        self.scopes_supported = scopes.into_iter().collect();
+        self
+    }
+
+    /// The canonical resource URI: lowercase scheme and host, and no
+    /// trailing slash.
+    pub fn resource(&self) -> String {
+        canonical_string(&self.resource)
+    }
+
+    pub fn authorization_servers(&self) -> &[String] {
+        &self.authorization_servers
+    }
+
+    /// The path this resource's metadata is served at: the well-known
+    /// prefix followed by the resource's own path, as RFC 9728 §3.1
+    /// prescribes.
+    pub fn metadata_path(&self) -> String {
+        format!(
+            "{}{}",
+            METADATA_PATH,
+            self.resource.path().trim_end_matches('/')
+        )
+    }
+
+    /// The absolute URL of this resource's metadata, sent to clients in
+    /// `WWW-Authenticate` challenges.
+    pub fn metadata_url(&self) -> String {
+        format!(
+            "{}{}",
+            self.resource.origin().ascii_serialization(),
+            self.metadata_path()
+        )
+    }
+
+    /// The RFC 9728 metadata document.
+    pub fn metadata(&self) -> Value {
+        let mut metadata = json!({
+            "resource": self.resource(),
+            "bearer_methods_supported": ["header"],
+            "resource_name": "Time MCP Server"
+        });
+        if !self.authorization_servers.is_empty() {
+            metadata["authorization_servers"] = json!(self.authorization_servers);
+        }
+        if !self.scopes_supported.is_empty() {
+            metadata["scopes_supported"] = json!(self.scopes_supported);
+        }
+        metadata
+    }
+}
+
+fn canonical_url(uri: &str) -> Result<Url> {
+    let url = Url::parse(uri).with_context(|| format!("Invalid URI {}", uri))?;
+    if !matches!(url.scheme(), "http" | "https") || !url.has_host() {
+        anyhow::bail!("Expected an absolute http or https URI, got {}", uri);
+    }
+    if url.fragment().is_some() {
+        anyhow::bail!("URI must not contain a fragment: {}", uri);
+    }
+    Ok(url)
+}
+
+/// `url` as a string, without the trailing slash `Url` adds to bare hosts.
+fn canonical_string(url: &Url) -> String {
+    let serialized = url.as_str();
+    match url.query() {
+        Some(_) => serialized.to_string(),
+        None => serialized.trim_end_matches('/').to_string(),
+    }
+}
@@ -0,0 +1,116 @@
+use crate::resources::Cadence;
+use chrono_tz::TZ_VARIANTS;
+use std::collections::BTreeSet;
+
+/// Most values a single `completion/complete` response may carry.
+pub const MAX_COMPLETIONS: usize = 100;
+
+/// Format names accepted by `get_current_time` and `format_time`.
+pub const FORMAT_NAMES: &[&str] = &["custom", "human", "iso", "iso8601", "rfc3339", "unix"];
+
+/// Units accepted by `calculate_duration`.
+pub const DURATION_UNITS: &[&str] = &["seconds", "minutes", "hours", "days"];
+
+/// Ranked completions for an argument or template variable, chosen by its
+/// name. Unknown names have no completions.
+pub fn complete(argument: &str, value: &str) -> Vec<String> {
+    match argument {
+        "timezone" | "tz" | "from_timezone" | "to_timezone" => complete_timezone(value),
+        "region" => complete_region(value),
+        "format" => rank(FORMAT_NAMES.iter().copied(), value),
+        "units" => rank(DURATION_UNITS.iter().copied(), value),
+        "cadence" => rank(Cadence::NAMES.iter().copied(), value),
+        _ => Vec::new(),
+    }
+}
+
+/// IANA timezone identifiers matching `value`, best first. Matches on any
+/// path segment, so "new york" and "york" both find `America/New_York`.
+pub fn complete_timezone(value: &str) -> Vec<String> {
+    rank(TZ_VARIANTS.iter().map(|tz| tz.name()), value)
+}
+
+/// Region prefixes usable with `list_timezones`, such as `America`.
+pub fn complete_region(value: &str) -> Vec<String> {
+    let regions: BTreeSet<&str> = TZ_VARIANTS
+        .iter()
+        .filter_map(|tz| tz.name().split_once('/'))
+        .map(|(region, _)| region)
+        .collect();
+
+    rank(regions.into_iter(), value)
+}
+
+fn rank<'a>(candidates: impl Iterator<Item = &'a str>, value: &str) -> Vec<String> {
+    let query = normalize(value);
+
+    let mut scored: Vec<(u8, &str)> = candidates
+        .filter_map(|candidate| score(candidate, &query).map(|score| (score, candidate)))
+        .collect();
+
+    // Among equally good matches prefer the shorter, more canonical name
+    // (`UTC` over `Etc/UTC`), then alphabetical order.
+    scored.sort_by(|a, b| {
+        a.0.cmp(&b.0)
+            .then_with(|| {
+                if query.is_empty() {
+                    std::cmp::Ordering::Equal
+                } else {
+                    a.1.len().cmp(&b.1.len())
+                }
+            })
+            .then_with(|| a.1.cmp(b.1))
+    });
+
+    scored
+        .into_iter()
+        .map(|(_, candidate)| candidate.to_string())
+        .collect()
+}
+
+/// Lower is better; `None` means no match.
+fn score(candidate: &str, query: &str) -> Option<u8> {
+    if query.is_empty() {
+        return Some(0);
+    }
+
+    let name = normalize(candidate);
+    let last_segment = name.rsplit('/').next().unwrap_or(&name);
+
+    if name == query {
+        Some(0)
+    } else if name.starts_with(query) {
+        Some(1)
+    } else if last_segment.starts_with(query) {
+        Some(2)
+    } else if name.split('/').any(|segment| segment.starts_with(query)) {
+        Some(3)
+    } else if name.contains(query) {
+        Some(4)
+    } else if compact(&name).contains(&compact(query)) {
+        Some(5)
+    } else if query.len() >= 3 && is_subsequence(&compact(query), &compact(&name)) {
+        Some(6)
+    } else {
+        None
+    }
+}
+
+/// Lowercases and maps the separators people type in place of `_`.
+fn normalize(value: &str) -> String {
+    value
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c == ' ' || c == '-' { '_' } else { c })
+        .collect()
+}
+
+fn compact(value: &str) -> String {
+    value.chars().filter(|c| c.is_alphanumeric()).collect()
+}
+
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut haystack = haystack.chars();
+    needle.chars().all(|c| haystack.any(|h| h == c))
+}
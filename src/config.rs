@@ -1,9 +1,22 @@
 use anyhow::Result;
+use std::path::PathBuf;
+use std::time::Duration;
 
-pub const DEFAULT_PROTOCOL_VERSION: &str = "2025-06-18";
-pub const FALLBACK_PROTOCOL_VERSION: &str = "1.0";
+use crate::auth::{ApiKeyStore, IntrospectionClient, JwtValidator, ProtectedResource, ScopePolicy};
+use crate::handlers::origin::OriginPolicy;
+use crate::protocol::ProtocolVersion;
+use crate::shutdown::DEFAULT_SHUTDOWN_TIMEOUT;
+
+pub const DEFAULT_PROTOCOL_VERSION: &str = ProtocolVersion::LATEST.as_str();
 pub const SERVER_NAME: &str = "time-mcp-server";
 pub const SERVER_VERSION: &str = "1.0.0";
+pub const DEFAULT_MAX_IN_FLIGHT: usize = 32;
+/// Permissions of the Unix socket file: read/write for the owner only.
+pub const DEFAULT_SOCKET_MODE: u32 = 0o600;
+/// How many Streamable HTTP sessions may be open at once.
+pub const DEFAULT_MAX_SESSIONS: usize = 1024;
+/// How long a Streamable HTTP session may sit unused before it is closed.
+pub const DEFAULT_SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
 
 #[derive(Clone, Debug)]
 pub struct ServerConfig {
@@ -11,12 +24,63 @@ pub struct ServerConfig {
     pub host: String,
     pub port: u16,
     pub auth_enabled: bool,
+    /// Verifies JWT bearer tokens when auth is enabled.
+    pub jwt: Option<JwtValidator>,
+    /// Verifies opaque bearer tokens with the authorization server when auth
+    /// is enabled.
+    pub introspection: Option<IntrospectionClient>,
+    /// Static API keys accepted when auth is enabled.
+    pub api_keys: Option<ApiKeyStore>,
+    /// Scopes required for each tool, resource and prompt.
+    pub scope_policy: ScopePolicy,
+    /// This server's canonical resource URI and authorization servers.
+    /// Defaults to `http://{host}:{port}/mcp` with no authorization servers.
+    pub resource: Option<ProtectedResource>,
+    /// Maximum number of requests handled concurrently per stdio, WebSocket
+    /// or Unix socket connection.
+    pub max_in_flight: usize,
+    /// File permissions applied to the Unix socket after binding it.
+    pub socket_mode: u32,
+    /// How long to wait for in-flight requests after SIGTERM or SIGINT.
+    pub shutdown_timeout: Duration,
+    /// Browser origins the HTTP and WebSocket transports accept requests
+    /// from, besides loopback ones.
+    pub allowed_origins: OriginPolicy,
+    /// Maximum number of open Streamable HTTP sessions.
+    pub max_sessions: usize,
+    /// How long a Streamable HTTP session may go without requests, and
+    /// without an open SSE stream, before it is closed.
+    pub session_idle_timeout: Duration,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            transport: TransportType::Stdio,
+            host: "localhost".to_string(),
+            port: 8080,
+            auth_enabled: false,
+            jwt: None,
+            introspection: None,
+            api_keys: None,
+            scope_policy: ScopePolicy::default(),
+            resource: None,
+            max_in_flight: DEFAULT_MAX_IN_FLIGHT,
+            socket_mode: DEFAULT_SOCKET_MODE,
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+            allowed_origins: OriginPolicy::default(),
+            max_sessions: DEFAULT_MAX_SESSIONS,
+            session_idle_timeout: DEFAULT_SESSION_IDLE_TIMEOUT,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub enum TransportType {
     Stdio,
     Http { host: String, port: u16 },
+    WebSocket { host: String, port: u16 },
+    Unix { socket_path: PathBuf },
 }
 
 impl ServerConfig {
@@ -35,24 +99,245 @@ impl ServerConfig {
             .and_then(|p| p.parse::<u16>().ok())
             .unwrap_or(8080);
 
+        let max_in_flight = matches
+            .try_get_one::<String>("max-in-flight")
+            .ok()
+            .flatten()
+            .map(|v| v.parse::<usize>())
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("Invalid max-in-flight: {}", e))?
+            .unwrap_or(DEFAULT_MAX_IN_FLIGHT);
+
+        if max_in_flight == 0 {
+            return Err(anyhow::anyhow!("max-in-flight must be at least 1"));
+        }
+
+        let socket_mode = matches
+            .try_get_one::<String>("socket-mode")
+            .ok()
+            .flatten()
+            .map(|v| u32::from_str_radix(v, 8))
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("Invalid socket-mode (expected octal, e.g. 660): {}", e))?
+            .unwrap_or(DEFAULT_SOCKET_MODE);
+
+        if socket_mode > 0o777 {
+            return Err(anyhow::anyhow!("socket-mode must be at most 777"));
+        }
+
+        let shutdown_timeout = matches
+            .try_get_one::<String>("shutdown-timeout")
+            .ok()
+            .flatten()
+            .map(|v| v.parse::<u64>())
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("Invalid shutdown-timeout: {}", e))?
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT);
+
+        let allowed_origins = matches
+            .try_get_one::<String>("allowed-origins")
+            .ok()
+            .flatten()
+            .map(|v| OriginPolicy::from_list(v.as_str()))
+            .unwrap_or_default();
+
+        let max_sessions = matches
+            .try_get_one::<String>("max-sessions")
+            .ok()
+            .flatten()
+            .map(|v| v.parse::<usize>())
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("Invalid max-sessions: {}", e))?
+            .unwrap_or(DEFAULT_MAX_SESSIONS);
+
+        if max_sessions == 0 {
+            return Err(anyhow::anyhow!("max-sessions must be at least 1"));
+        }
+
+        let session_idle_timeout = matches
+            .try_get_one::<String>("session-timeout")
+            .ok()
+            .flatten()
+            .map(|v| v.parse::<u64>())
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("Invalid session-timeout: {}", e))?
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_SESSION_IDLE_TIMEOUT);
+
         let transport = match transport_str.as_str() {
             "stdio" => TransportType::Stdio,
             "http" => TransportType::Http {
                 host: host.clone(),
                 port,
             },
+            "ws" => TransportType::WebSocket {
+                host: host.clone(),
+                port,
+            },
+            "unix" => TransportType::Unix {
+                socket_path: matches
+                    .try_get_one::<String>("socket-path")
+                    .ok()
+                    .flatten()
+                    .map(PathBuf::from)
+                    .ok_or_else(|| anyhow::anyhow!("--socket-path is required for unix"))?,
+            },
             _ => return Err(anyhow::anyhow!("Invalid transport type: {}", transport_str)),
         };
 
         let auth_enabled = std::env::var("OAUTH_ENABLED")
             .map(|v| v == "true")
             .unwrap_or(false);
+        let jwt = Self::jwt_from_env()?;
+        let introspection = Self::introspection_from_env()?;
+        let api_keys = std::env::var("API_KEYS_FILE")
+            .ok()
+            .map(|path| ApiKeyStore::from_file(std::path::Path::new(&path)))
+            .transpose()?;
+        let scope_policy = match std::env::var("OAUTH_SCOPE_POLICY") {
+            Ok(path) => ScopePolicy::from_file(std::path::Path::new(&path))?,
+            Err(_) => ScopePolicy::default(),
+        };
+        let resource = Self::resource_from_env(&host, port)?;
 
         Ok(ServerConfig {
             transport,
             host,
             port,
             auth_enabled,
+            jwt,
+            introspection,
+            api_keys,
+            scope_policy,
+            resource,
+            max_in_flight,
+            socket_mode,
+            shutdown_timeout,
+            allowed_origins,
+            max_sessions,
+            session_idle_timeout,
         })
     }
+
+    /// The protected resource this server presents to OAuth clients, with
+    /// the scopes its policy uses.
+    pub fn protected_resource(&self) -> ProtectedResource {
+        let resource = match &self.resource {
+            Some(resource) => resource.clone(),
+            None => ProtectedResource::new(&default_resource_uri(&self.host, self.port))
+                .unwrap_or_else(|_| {
+                    ProtectedResource::new(&default_resource_uri("localhost", self.port))
+                        .expect("localhost forms a valid URI")
+                }),
+        };
+        resource.with_scopes(self.scope_policy.scopes())
+    }
+
+    /// Reads the canonical resource URI from `OAUTH_RESOURCE` and the
+    /// comma-separated `OAUTH_AUTHORIZATION_SERVERS`.
+    fn resource_from_env(host: &str, port: u16) -> Result<Option<ProtectedResource>> {
+        let uri = std::env::var("OAUTH_RESOURCE").ok();
+        let servers = std::env::var("OAUTH_AUTHORIZATION_SERVERS").ok();
+        if uri.is_none() && servers.is_none() {
+            return Ok(None);
+        }
+
+        let uri = uri.unwrap_or_else(|| default_resource_uri(host, port));
+        let resource = ProtectedResource::new(&uri)
+            .map_err(|e| anyhow::anyhow!("Invalid OAUTH_RESOURCE: {}", e))?;
+        let resource = servers
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|server| !server.is_empty())
+            .try_fold(resource, |resource, server| {
+                resource.with_authorization_server(server)
+            })?;
+
+        Ok(Some(resource))
+    }
+
+    /// Builds the JWT validator from `OAUTH_JWT_SECRET` (HS256) or
+    /// `OAUTH_JWT_KEY_FILE` (a PEM public key or JWKS document), checking
+    /// `OAUTH_ISSUER` and the comma-separated `OAUTH_AUDIENCE` if set.
+    fn jwt_from_env() -> Result<Option<JwtValidator>> {
+        let validator = match (
+            std::env::var("OAUTH_JWT_SECRET").ok(),
+            std::env::var("OAUTH_JWT_KEY_FILE").ok(),
+        ) {
+            (Some(_), Some(_)) => {
+                return Err(anyhow::anyhow!(
+                    "Set only one of OAUTH_JWT_SECRET and OAUTH_JWT_KEY_FILE"
+                ))
+            }
+            (Some(secret), None) => JwtValidator::hs256(secret.as_bytes()),
+            (None, Some(path)) => JwtValidator::from_key_file(std::path::Path::new(&path))?,
+            (None, None) => return Ok(None),
+        };
+
+        let validator = match std::env::var("OAUTH_ISSUER") {
+            Ok(issuer) => validator.with_issuer(issuer),
+            Err(_) => validator,
+        };
+        let validator = audiences_from_env()
+            .into_iter()
+            .fold(validator, JwtValidator::with_audience);
+
+        Ok(Some(validator))
+    }
+
+    /// Builds the introspection client from `OAUTH_INTROSPECTION_URL`,
+    /// authenticating with `OAUTH_CLIENT_ID` and `OAUTH_CLIENT_SECRET` and
+    /// caching results for `OAUTH_INTROSPECTION_CACHE_TTL` seconds. Checks
+    /// `OAUTH_ISSUER` and `OAUTH_AUDIENCE` like JWT validation does.
+    fn introspection_from_env() -> Result<Option<IntrospectionClient>> {
+        let Ok(url) = std::env::var("OAUTH_INTROSPECTION_URL") else {
+            return Ok(None);
+        };
+        let client_id = std::env::var("OAUTH_CLIENT_ID").map_err(|_| {
+            anyhow::anyhow!("OAUTH_CLIENT_ID is required with OAUTH_INTROSPECTION_URL")
+        })?;
+
+        let mut client = IntrospectionClient::new(&url, client_id)?;
+        if let Ok(secret) = std::env::var("OAUTH_CLIENT_SECRET") {
+            client = client.with_client_secret(secret);
+        }
+        if let Ok(ttl) = std::env::var("OAUTH_INTROSPECTION_CACHE_TTL") {
+            let ttl = ttl
+                .parse::<u64>()
+                .map_err(|e| anyhow::anyhow!("Invalid OAUTH_INTROSPECTION_CACHE_TTL: {}", e))?;
+            client = client.with_cache_ttl(Duration::from_secs(ttl));
+        }
+        if let Ok(issuer) = std::env::var("OAUTH_ISSUER") {
+            client = client.with_issuer(issuer);
+        }
+
+        Ok(Some(
+            audiences_from_env()
+                .into_iter()
+                .fold(client, IntrospectionClient::with_audience),
+        ))
+    }
+}
+
+/// The comma-separated audiences in `OAUTH_AUDIENCE`.
+fn audiences_from_env() -> Vec<String> {
+    std::env::var("OAUTH_AUDIENCE")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|audience| !audience.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// The resource URI clients reach the HTTP transport's `/mcp` endpoint at
+/// when none is configured.
+fn default_resource_uri(host: &str, port: u16) -> String {
+    if host.contains(':') {
+        format!("http://[{}]:{}/mcp", host, port)
+    } else {
+        format!("http://{}:{}/mcp", host, port)
+    }
 }
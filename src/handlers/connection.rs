@@ -0,0 +1,167 @@
+use crate::handlers::dispatcher::Dispatcher;
+use crate::models::TokenInfo;
+use crate::session::{Session, SessionState};
+use crate::shutdown::Shutdown;
+use anyhow::Result;
+use futures::future::abortable;
+use futures::{Stream, StreamExt};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Semaphore};
+use tokio::task::JoinSet;
+
+/// Capacity of the queue feeding a connection's writer task.
+pub(crate) const OUTBOUND_CAPACITY: usize = 64;
+
+/// How many messages may wait for an in-flight slot, per slot, before the
+/// connection stops reading.
+const QUEUED_PER_SLOT: usize = 4;
+
+/// Serves one client connection: every raw JSON-RPC message from `incoming`
+/// is dispatched against a fresh [`Session`] on behalf of `principal`, and
+/// responses and notifications are queued on `outbound` for the transport to
+/// write.
+///
+/// Each message is handled on its own task, so responses are sent in
+/// completion order rather than arrival order; clients match them by id.
+/// At most `max_in_flight` requests run at once. Requests beyond that wait
+/// for a slot while the connection keeps reading, so cancellations and
+/// shutdown still get through; reading only pauses once a few times
+/// `max_in_flight` messages are waiting.
+///
+/// Returns once `incoming` ends and every in-flight request has been
+/// answered, or once `shutdown` fires and in-flight requests have been given
+/// up to `drain_timeout` to finish.
+pub(crate) async fn serve_connection<S>(
+    dispatcher: Dispatcher,
+    max_in_flight: usize,
+    mut incoming: S,
+    outbound: mpsc::Sender<Value>,
+    principal: Option<TokenInfo>,
+    shutdown: &Shutdown,
+    drain_timeout: Duration,
+) -> Result<()>
+where
+    S: Stream<Item = String> + Unpin,
+{
+    let session = Arc::new(Session::with_outbound(outbound.clone()));
+    let principal = principal.map(Arc::new);
+    let limiter = Arc::new(Semaphore::new(max_in_flight));
+    let max_queued = max_in_flight.saturating_mul(QUEUED_PER_SLOT);
+    let mut in_flight = JoinSet::new();
+
+    loop {
+        let raw = tokio::select! {
+            biased;
+            _ = shutdown.wait() => break,
+            Some(_) = in_flight.join_next(), if in_flight.len() >= max_queued => continue,
+            raw = incoming.next(), if in_flight.len() < max_queued => match raw {
+                Some(raw) => raw,
+                None => break,
+            },
+        };
+
+        if raw.trim().is_empty() {
+            continue;
+        }
+
+        let message = match serde_json::from_str::<Value>(raw.trim()) {
+            Ok(message) => message,
+            Err(e) => {
+                let _ = outbound.send(Dispatcher::parse_error_response(&e)).await;
+                continue;
+            }
+        };
+
+        // Until initialize has been answered, messages are handled one at a
+        // time so nothing overtakes it.
+        if session.state() == SessionState::Uninitialized {
+            if let Some(response) = dispatcher
+                .handle_message(&session, principal.as_deref(), message)
+                .await
+            {
+                let _ = outbound.send(response).await;
+            }
+            continue;
+        }
+
+        // Notifications such as cancellations skip the in-flight limit so
+        // they are never stuck behind the requests they refer to.
+        let limiter =
+            (!message.is_object() || message.get("id").is_some()).then(|| limiter.clone());
+        let request_id = cancellable_id(&message);
+        let handle = {
+            let dispatcher = dispatcher.clone();
+            let session = session.clone();
+            let principal = principal.clone();
+            let outbound = outbound.clone();
+
+            async move {
+                let _permit = match limiter {
+                    Some(limiter) => match limiter.acquire_owned().await {
+                        Ok(permit) => Some(permit),
+                        Err(_) => return,
+                    },
+                    None => None,
+                };
+                if let Some(response) = dispatcher
+                    .handle_message(&session, principal.as_deref(), message)
+                    .await
+                {
+                    let _ = outbound.send(response).await;
+                }
+            }
+        };
+
+        match request_id {
+            // Tracked before the task is spawned, so a cancellation read right
+            // after the request finds it even while it waits for a slot.
+            Some(id) => {
+                let (handle, abort_handle) = abortable(handle);
+                session.track(&id, abort_handle);
+                let session = session.clone();
+                in_flight.spawn(async move {
+                    let _ = handle.await;
+                    session.untrack(&id);
+                });
+            }
+            None => {
+                in_flight.spawn(handle);
+            }
+        }
+
+        while in_flight.try_join_next().is_some() {}
+    }
+
+    if shutdown.is_triggered() {
+        session.shut_down();
+        if !in_flight.is_empty() {
+            tracing::info!("Waiting for {} in-flight requests", in_flight.len());
+        }
+
+        let drain = async { while in_flight.join_next().await.is_some() {} };
+        if tokio::time::timeout(drain_timeout, drain).await.is_err() {
+            tracing::warn!(
+                "Shutdown deadline elapsed, abandoning {} requests",
+                in_flight.len()
+            );
+            in_flight.shutdown().await;
+        }
+    } else {
+        while in_flight.join_next().await.is_some() {}
+        session.shut_down();
+    }
+
+    Ok(())
+}
+
+/// The id of `message` if it is a single request that may be cancelled;
+/// the spec forbids cancelling `initialize`.
+fn cancellable_id(message: &Value) -> Option<Value> {
+    let method = message.get("method")?.as_str()?;
+    match message.get("id")? {
+        id @ (Value::String(_) | Value::Number(_)) if method != "initialize" => Some(id.clone()),
+        _ => None,
+    }
+}
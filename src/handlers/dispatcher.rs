@@ -0,0 +1,443 @@
+use crate::auth::{ScopePolicy, ScopeTarget};
+use crate::handlers::mcp::McpHandlers;
+use crate::models::{McpError, McpResponse, McpResult, TokenInfo};
+use crate::pagination::DEFAULT_PAGE_SIZE;
+use crate::registry::ToolRegistry;
+use crate::resources::TimeResource;
+use crate::session::{Session, SessionState};
+use futures::future::{abortable, join_all};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tracing::Instrument;
+
+/// Transport-agnostic JSON-RPC method router shared by every transport.
+///
+/// Transports hand incoming messages to [`Dispatcher::handle_message`], along
+/// with the [`Session`] of the connection they arrived on and the caller who
+/// sent them, and write back whatever it returns; the REST compatibility routes call
+/// [`Dispatcher::dispatch`] directly and map the `McpError` to a status code.
+#[derive(Clone)]
+pub struct Dispatcher {
+    tools: Arc<ToolRegistry>,
+    page_size: usize,
+    scope_policy: Arc<ScopePolicy>,
+}
+
+impl Default for Dispatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Dispatcher {
+    /// Dispatcher serving the built-in time tools.
+    pub fn new() -> Self {
+        Self::with_tools(ToolRegistry::with_builtin_tools())
+    }
+
+    pub fn with_tools(tools: ToolRegistry) -> Self {
+        Self {
+            tools: Arc::new(tools),
+            page_size: DEFAULT_PAGE_SIZE,
+            scope_policy: Arc::new(ScopePolicy::new()),
+        }
+    }
+
+    /// Sets how many entries `tools/list`, `resources/list`,
+    /// `resources/templates/list` and `prompts/list` return per page.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Restricts tools, resources and prompts to callers holding the scopes
+    /// `policy` requires. Only applies to authenticated callers.
+    pub fn with_scope_policy(mut self, policy: ScopePolicy) -> Self {
+        self.scope_policy = Arc::new(policy);
+        self
+    }
+
+    pub fn tools(&self) -> &ToolRegistry {
+        &self.tools
+    }
+
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    pub fn scope_policy(&self) -> &ScopePolicy {
+        &self.scope_policy
+    }
+
+    /// Parses one line or body of raw input and handles it, answering
+    /// unparseable JSON with a -32700 error as JSON-RPC requires.
+    pub async fn handle_raw(
+        &self,
+        session: &Session,
+        principal: Option<&TokenInfo>,
+        input: &str,
+    ) -> Option<Value> {
+        match serde_json::from_str::<Value>(input) {
+            Ok(message) => self.handle_message(session, principal, message).await,
+            Err(e) => Some(Self::parse_error_response(&e)),
+        }
+    }
+
+    /// Builds the -32700 response for input that is not valid JSON.
+    pub fn parse_error_response(error: &serde_json::Error) -> Value {
+        tracing::warn!("Failed to parse message: {}", error);
+        Self::error_response(
+            Value::Null,
+            McpError::parse_error(format!("Parse error: {}", error)),
+        )
+    }
+
+    /// Handles an incoming JSON-RPC message or batch and returns the response
+    /// to send back, or `None` when nothing needs answering (notifications,
+    /// client responses, or a batch made up only of those).
+    ///
+    /// `principal` is the authenticated caller, or `None` on transports
+    /// without authentication; its token decides what the message may do.
+    pub async fn handle_message(
+        &self,
+        session: &Session,
+        principal: Option<&TokenInfo>,
+        message: Value,
+    ) -> Option<Value> {
+        self.handle_in_session(session, principal, message)
+            .instrument(session.span())
+            .await
+    }
+
+    async fn handle_in_session(
+        &self,
+        session: &Session,
+        principal: Option<&TokenInfo>,
+        message: Value,
+    ) -> Option<Value> {
+        match message {
+            Value::Array(batch) if batch.is_empty() => Some(Self::error_response(
+                Value::Null,
+                McpError::invalid_request("Empty batch"),
+            )),
+            Value::Array(batch) => {
+                let responses: Vec<Value> = join_all(
+                    batch
+                        .into_iter()
+                        .map(|m| self.handle_single(session, principal, m)),
+                )
+                .await
+                .into_iter()
+                .flatten()
+                .collect();
+
+                (!responses.is_empty()).then_some(Value::Array(responses))
+            }
+            message => self.handle_single(session, principal, message).await,
+        }
+    }
+
+    async fn handle_single(
+        &self,
+        session: &Session,
+        principal: Option<&TokenInfo>,
+        message: Value,
+    ) -> Option<Value> {
+        let Some(object) = message.as_object() else {
+            return Some(Self::error_response(
+                Value::Null,
+                McpError::invalid_request("Message must be a JSON object"),
+            ));
+        };
+
+        // Responses to server-initiated requests need no answer.
+        if !object.contains_key("method")
+            && (object.contains_key("result") || object.contains_key("error"))
+        {
+            return None;
+        }
+
+        let id = match object.get("id") {
+            None => None,
+            Some(id @ (Value::String(_) | Value::Number(_))) => Some(id.clone()),
+            Some(_) => {
+                return Some(Self::error_response(
+                    Value::Null,
+                    McpError::invalid_request("Invalid id: must be a string or number"),
+                ))
+            }
+        };
+        let error_id = id.clone().unwrap_or(Value::Null);
+
+        if object.get("jsonrpc").and_then(|v| v.as_str()) != Some("2.0") {
+            return Some(Self::error_response(
+                error_id,
+                McpError::invalid_request("jsonrpc must be \"2.0\""),
+            ));
+        }
+
+        let Some(method) = object.get("method").and_then(|v| v.as_str()) else {
+            return Some(Self::error_response(
+                error_id,
+                McpError::invalid_request("Missing method"),
+            ));
+        };
+
+        let params = object.get("params").cloned();
+        if params
+            .as_ref()
+            .is_some_and(|p| !p.is_object() && !p.is_array())
+        {
+            return Some(Self::error_response(
+                error_id,
+                McpError::invalid_request("params must be an object or array"),
+            ));
+        }
+
+        match id {
+            Some(id) => {
+                self.handle_request(session, principal, id, method, params)
+                    .await
+            }
+            None => {
+                self.handle_notification(session, method, params);
+                None
+            }
+        }
+    }
+
+    /// Runs a request, returning `None` if it was cancelled before finishing.
+    async fn handle_request(
+        &self,
+        session: &Session,
+        principal: Option<&TokenInfo>,
+        id: Value,
+        method: &str,
+        params: Option<Value>,
+    ) -> Option<Value> {
+        if let Err(error) = Self::check_lifecycle(session, method) {
+            tracing::warn!("Rejecting {} request: {}", method, error.message);
+            return Some(Self::error_response(id, error));
+        }
+
+        // The spec forbids cancelling initialize, so it is never tracked.
+        let result = if method == "initialize" {
+            self.dispatch_with_session(session, principal, method, params)
+                .await
+        } else {
+            let (request, abort_handle) =
+                abortable(self.dispatch_with_session(session, principal, method, params));
+            session.track(&id, abort_handle);
+            let result = request.await;
+            session.untrack(&id);
+
+            match result {
+                Ok(result) => result,
+                Err(_) => {
+                    tracing::info!("Request {} cancelled", id);
+                    return None;
+                }
+            }
+        };
+
+        Some(match result {
+            Ok(result) => serde_json::to_value(McpResponse::success(id.clone(), result))
+                .unwrap_or_else(|e| {
+                    Self::error_response(id, McpError::internal_error(e.to_string()))
+                }),
+            Err(error) => Self::error_response(id, error),
+        })
+    }
+
+    /// Rejects requests the session isn't ready for: anything but
+    /// `initialize` and `ping` before initialization, a second `initialize`,
+    /// and anything but `ping` while shutting down.
+    fn check_lifecycle(session: &Session, method: &str) -> McpResult<()> {
+        if method == "ping" {
+            return Ok(());
+        }
+
+        match (session.state(), method) {
+            (SessionState::Uninitialized, "initialize") => Ok(()),
+            (SessionState::Uninitialized, _) => Err(McpError::invalid_request(
+                "Session not initialized: send initialize first",
+            )),
+            (SessionState::Initializing | SessionState::Ready, "initialize") => {
+                Err(McpError::invalid_request("Session is already initialized"))
+            }
+            (SessionState::Initializing | SessionState::Ready, _) => Ok(()),
+            (SessionState::ShuttingDown, _) => {
+                Err(McpError::invalid_request("Session is shutting down"))
+            }
+        }
+    }
+
+    fn handle_notification(&self, session: &Session, method: &str, params: Option<Value>) {
+        match method {
+            "notifications/initialized" => {
+                if session.mark_ready() {
+                    tracing::info!("Client initialized");
+                } else {
+                    tracing::warn!(
+                        "Ignoring notifications/initialized in state {:?}",
+                        session.state()
+                    );
+                }
+            }
+            "notifications/cancelled" => {
+                let params = params.unwrap_or(Value::Null);
+                let Some(request_id) = params.get("requestId") else {
+                    tracing::warn!("Ignoring cancellation without a requestId");
+                    return;
+                };
+                let reason = params
+                    .get("reason")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("no reason given");
+
+                if session.cancel(request_id) {
+                    tracing::info!("Cancelling request {}: {}", request_id, reason);
+                } else {
+                    tracing::debug!("Cancellation for unknown request {}", request_id);
+                }
+            }
+            _ => tracing::debug!("Ignoring notification: {}", method),
+        }
+    }
+
+    /// Builds a JSON-RPC error response envelope.
+    pub fn error_response(id: Value, error: McpError) -> Value {
+        serde_json::to_value(McpResponse::<()>::error(id, error)).unwrap_or_else(|_| json!({}))
+    }
+
+    /// Routes a method call to its handler and returns the bare `result`
+    /// payload, without the JSON-RPC envelope. Runs outside of any client
+    /// session, so nothing can be sent back besides the result, and the
+    /// session lifecycle isn't enforced.
+    pub async fn dispatch(&self, method: &str, params: Option<Value>) -> McpResult<Value> {
+        self.dispatch_with_session(&Session::new(), None, method, params)
+            .await
+    }
+
+    /// Like [`Dispatcher::dispatch`], on behalf of `principal` in a client
+    /// `session`.
+    pub async fn dispatch_with_session(
+        &self,
+        session: &Session,
+        principal: Option<&TokenInfo>,
+        method: &str,
+        params: Option<Value>,
+    ) -> McpResult<Value> {
+        let version = session.protocol_version();
+        self.authorize(principal, method, params.as_ref())?;
+        let allowed = |target| move |name: &str| self.scope_policy.allows(principal, target, name);
+
+        match method {
+            "initialize" => McpHandlers::handle_initialize(session, params).await,
+            "ping" => Ok(json!({})),
+            "logging/setLevel" => McpHandlers::handle_logging_set_level(session, params).await,
+            "tools/list" => {
+                McpHandlers::handle_tools_list(
+                    &self.tools,
+                    version,
+                    params,
+                    self.page_size,
+                    allowed(ScopeTarget::Tool),
+                )
+                .await
+            }
+            "tools/call" => {
+                McpHandlers::handle_tools_call(&self.tools, session, version, params).await
+            }
+            "resources/list" => {
+                McpHandlers::handle_resources_list(
+                    params,
+                    self.page_size,
+                    allowed(ScopeTarget::Resource),
+                )
+                .await
+            }
+            "resources/templates/list" => {
+                McpHandlers::handle_resources_templates_list(
+                    params,
+                    self.page_size,
+                    allowed(ScopeTarget::Resource),
+                )
+                .await
+            }
+            "resources/read" => McpHandlers::handle_resources_read(params).await,
+            "resources/subscribe" => McpHandlers::handle_resources_subscribe(session, params).await,
+            "resources/unsubscribe" => {
+                McpHandlers::handle_resources_unsubscribe(session, params).await
+            }
+            "prompts/list" => {
+                McpHandlers::handle_prompts_list(
+                    params,
+                    self.page_size,
+                    allowed(ScopeTarget::Prompt),
+                )
+                .await
+            }
+            "prompts/get" => McpHandlers::handle_prompts_get(params).await,
+            "completion/complete" => McpHandlers::handle_completion_complete(params).await,
+            _ => Err(McpError::method_not_found("Method not found")),
+        }
+    }
+
+    /// Rejects calls the principal's token no longer covers, and calls on a
+    /// tool, resource or prompt the scope policy doesn't grant it. Malformed
+    /// params are left for the method handler to report.
+    fn authorize(
+        &self,
+        principal: Option<&TokenInfo>,
+        method: &str,
+        params: Option<&Value>,
+    ) -> McpResult<()> {
+        let Some(principal) = principal else {
+            return Ok(());
+        };
+        if method != "ping" && principal.is_expired() {
+            return Err(McpError::new(401, "Token expired"));
+        }
+
+        let param = |value: Option<&Value>, key: &str| {
+            value
+                .and_then(|v| v.get(key))
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+        };
+        let target = match method {
+            "tools/call" => param(params, "name").map(|name| (ScopeTarget::Tool, name)),
+            "resources/read" | "resources/subscribe" => {
+                param(params, "uri").map(|uri| (ScopeTarget::Resource, canonical_uri(uri)))
+            }
+            "prompts/get" => param(params, "name").map(|name| (ScopeTarget::Prompt, name)),
+            "completion/complete" => {
+                let reference = params.and_then(|p| p.get("ref"));
+                match param(reference, "type").as_deref() {
+                    Some("ref/prompt") => {
+                        param(reference, "name").map(|name| (ScopeTarget::Prompt, name))
+                    }
+                    Some("ref/resource") => param(reference, "uri")
+                        .map(|uri| (ScopeTarget::Resource, canonical_uri(uri))),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+
+        match target {
+            Some((target, name)) => self.scope_policy.check(Some(principal), target, &name),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The URI a scope policy is checked against for a resource request, so
+/// legacy aliases and percent-encoded names get the same rules as the
+/// canonical form. URIs that don't parse are checked as given.
+fn canonical_uri(uri: String) -> String {
+    TimeResource::parse(&uri)
+        .map(|resource| resource.uri())
+        .unwrap_or(uri)
+}
@@ -1,42 +1,182 @@
 use crate::{
-    auth::AuthManager,
-    config::{ServerConfig, DEFAULT_PROTOCOL_VERSION, SERVER_NAME, SERVER_VERSION},
-    models::McpRequest,
-    tools::TimeTools,
+    auth::{resource::METADATA_PATH, AuthManager, ProtectedResource},
+    config::{ServerConfig, SERVER_VERSION},
+    handlers::{dispatcher::Dispatcher, origin::OriginPolicy},
+    models::{McpError, McpRequest, McpResponse, TokenInfo},
+    protocol::ProtocolVersion,
+    session::{Session, NOTIFICATION_CHANNEL_CAPACITY},
+    shutdown::Shutdown,
+    sync::lock,
 };
 use anyhow::Result;
 use axum::{
-    extract::State,
-    http::{HeaderMap, StatusCode},
-    response::Json,
+    body::Bytes,
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::{self, Next},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Json, Response,
+    },
     routing::{get, post},
     Router,
 };
 use chrono::Utc;
-use chrono_tz::TZ_VARIANTS;
 use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc, RwLock};
 use tower_http::{cors::CorsLayer, trace::TraceLayer};
 
+pub const SESSION_ID_HEADER: &str = "mcp-session-id";
+pub const PROTOCOL_VERSION_HEADER: &str = "mcp-protocol-version";
+
+const SESSION_CHANNEL_CAPACITY: usize = 64;
+
+/// Longest gap between sweeps for idle sessions.
+const MAX_EXPIRY_INTERVAL: Duration = Duration::from_secs(60);
+
 #[derive(Clone)]
 pub struct HttpHandler {
     auth: AuthManager,
+    dispatcher: Dispatcher,
+    resource: Arc<ProtectedResource>,
+    sessions: Arc<RwLock<HashMap<String, HttpSession>>>,
+    origins: OriginPolicy,
+    max_sessions: usize,
+    session_idle_timeout: Duration,
+    shutdown: Shutdown,
+    shutdown_timeout: Duration,
+}
+
+/// An `Mcp-Session-Id` and the state that goes with it.
+#[derive(Clone)]
+struct HttpSession {
+    session: Arc<Session>,
+    events: broadcast::Sender<Value>,
+    last_active: Arc<Mutex<Instant>>,
+    /// The `user_id` of the caller that initialized the session, if
+    /// authentication is enabled. Nobody else may use it.
+    owner: Option<String>,
+}
+
+impl HttpSession {
+    /// Creates a session for `principal` whose notifications are fanned out
+    /// to every SSE stream open on it. Notifications sent while no stream is
+    /// open are dropped.
+    fn new(principal: Option<&TokenInfo>) -> Self {
+        let (outbound, mut receiver) = mpsc::channel(NOTIFICATION_CHANNEL_CAPACITY);
+        let (events, _) = broadcast::channel(SESSION_CHANNEL_CAPACITY);
+
+        let forward = events.clone();
+        tokio::spawn(async move {
+            while let Some(notification) = receiver.recv().await {
+                let _ = forward.send(notification);
+            }
+        });
+
+        Self {
+            session: Arc::new(Session::with_outbound(outbound)),
+            events,
+            last_active: Arc::new(Mutex::new(Instant::now())),
+            owner: principal.map(|p| p.user_id.clone()),
+        }
+    }
+
+    fn is_owned_by(&self, principal: Option<&TokenInfo>) -> bool {
+        self.owner.as_deref() == principal.map(|p| p.user_id.as_str())
+    }
+
+    fn touch(&self) {
+        *lock(&self.last_active) = Instant::now();
+    }
+
+    /// Whether the session has gone `timeout` without a request, with no
+    /// request still running and no SSE stream open.
+    fn is_idle(&self, timeout: Duration) -> bool {
+        lock(&self.last_active).elapsed() >= timeout
+            && self.session.in_flight() == 0
+            && self.events.receiver_count() == 0
+    }
 }
 
 impl HttpHandler {
     pub fn new(config: ServerConfig) -> Self {
         Self {
-            auth: AuthManager::new(config.auth_enabled),
+            auth: AuthManager::from_config(&config),
+            dispatcher: Dispatcher::new().with_scope_policy(config.scope_policy.clone()),
+            resource: Arc::new(config.protected_resource()),
+            sessions: Arc::new(RwLock::new(HashMap::new())),
+            origins: config.allowed_origins.clone(),
+            max_sessions: config.max_sessions.max(1),
+            session_idle_timeout: config.session_idle_timeout,
+            shutdown: Shutdown::default(),
+            shutdown_timeout: config.shutdown_timeout,
         }
     }
 
+    /// Serves `dispatcher` instead of the built-in one. The configured scope
+    /// policy carries over unless `dispatcher` brings its own.
+    pub fn with_dispatcher(mut self, dispatcher: Dispatcher) -> Self {
+        self.dispatcher = if dispatcher.scope_policy().is_empty() {
+            dispatcher.with_scope_policy(self.dispatcher.scope_policy().clone())
+        } else {
+            dispatcher
+        };
+        self
+    }
+
+    /// Stops accepting connections once `shutdown` fires, closes every
+    /// session's SSE stream once its queued notifications are sent, and
+    /// gives in-flight requests `shutdown_timeout` to finish.
+    pub fn with_shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    pub fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
     pub async fn run(self, host: &str, port: u16) -> Result<()> {
-        let app = Router::new()
-            .route("/", get(Self::health_check))
+        let shutdown = self.shutdown.clone();
+        let shutdown_timeout = self.shutdown_timeout;
+        let sessions = self.sessions.clone();
+        let idle_timeout = self.session_idle_timeout;
+        let metadata_path = self.resource.metadata_path();
+
+        let mcp = Router::new()
+            .route(
+                "/mcp",
+                post(Self::handle_mcp_post)
+                    .get(Self::handle_mcp_get)
+                    .delete(Self::handle_mcp_delete),
+            )
             .route("/mcp/capabilities", get(Self::get_capabilities))
+            .route("/mcp/tools/list", get(Self::list_tools))
             .route("/mcp/tools/call", post(Self::call_tool))
+            .route("/mcp/resources/list", get(Self::list_resources))
             .route("/mcp/resources/read", post(Self::read_resource))
+            .route("/mcp/prompts/list", get(Self::list_prompts))
             .route("/mcp/prompts/get", post(Self::get_prompt))
+            .route_layer(middleware::from_fn_with_state(
+                self.clone(),
+                Self::check_origin,
+            ));
+
+        let mut app = Router::new()
+            .route("/", get(Self::health_check))
+            .route(METADATA_PATH, get(Self::resource_metadata))
             .route("/health", get(Self::health_check))
+            .merge(mcp);
+        // Clients derive the metadata URL from the resource's path, so serve
+        // it there too unless that path isn't a plain route.
+        if metadata_path != METADATA_PATH && !metadata_path.contains([':', '*', '{']) {
+            app = app.route(&metadata_path, get(Self::resource_metadata));
+        }
+        let app = app
             .layer(TraceLayer::new_for_http())
             .layer(CorsLayer::permissive())
             .with_state(self);
@@ -45,11 +185,84 @@ impl HttpHandler {
         let listener = tokio::net::TcpListener::bind(&addr).await?;
 
         tracing::info!("HTTP server listening on {}", addr);
-        axum::serve(listener, app).await?;
 
+        let expiry = tokio::spawn(Self::expire_sessions(sessions.clone(), idle_timeout));
+
+        let signal = shutdown.clone();
+        let server = axum::serve(listener, app).with_graceful_shutdown(async move {
+            signal.wait().await;
+            Self::close_sessions(&sessions).await;
+        });
+
+        let result = shutdown.with_deadline(shutdown_timeout, server).await;
+        expiry.abort();
+        match result {
+            Some(result) => result?,
+            None => tracing::warn!("Shutdown deadline elapsed, closing remaining connections"),
+        }
+
+        tracing::info!("HTTP server stopped");
         Ok(())
     }
 
+    /// Ends every session. Each SSE stream closes once the session's last
+    /// in-flight request has finished and its notifications are delivered.
+    async fn close_sessions(sessions: &RwLock<HashMap<String, HttpSession>>) {
+        let closed: Vec<_> = sessions.write().await.drain().collect();
+        for (_, http_session) in &closed {
+            http_session.session.shut_down();
+        }
+        if !closed.is_empty() {
+            tracing::info!("Closed {} sessions", closed.len());
+        }
+    }
+
+    /// Periodically ends sessions that have been idle for `idle_timeout`.
+    async fn expire_sessions(
+        sessions: Arc<RwLock<HashMap<String, HttpSession>>>,
+        idle_timeout: Duration,
+    ) {
+        let period = (idle_timeout / 2).clamp(Duration::from_millis(10), MAX_EXPIRY_INTERVAL);
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            Self::remove_idle(&mut *sessions.write().await, idle_timeout);
+        }
+    }
+
+    /// Ends and removes every session idle for `idle_timeout`.
+    fn remove_idle(sessions: &mut HashMap<String, HttpSession>, idle_timeout: Duration) {
+        sessions.retain(|session_id, http_session| {
+            if !http_session.is_idle(idle_timeout) {
+                return true;
+            }
+            http_session.session.shut_down();
+            tracing::info!("Session {} expired", session_id);
+            false
+        });
+    }
+
+    /// Refuses requests to the MCP endpoints from browser origins the
+    /// policy doesn't trust, so web pages can't drive a local server.
+    async fn check_origin(
+        State(handler): State<HttpHandler>,
+        request: Request,
+        next: Next,
+    ) -> Response {
+        if handler.origins.allows_request(request.headers()) {
+            return next.run(request).await;
+        }
+
+        tracing::warn!(
+            "Rejected request from origin {:?}",
+            request.headers().get(header::ORIGIN)
+        );
+        Self::error_response(
+            StatusCode::FORBIDDEN,
+            McpError::invalid_request("Origin not allowed"),
+        )
+    }
+
     async fn health_check() -> Json<Value> {
         Json(json!({
             "status": "healthy",
@@ -58,182 +271,417 @@ impl HttpHandler {
         }))
     }
 
-    async fn get_capabilities(
+    /// RFC 9728 protected resource metadata, telling clients which
+    /// authorization servers issue tokens for this server.
+    async fn resource_metadata(State(handler): State<HttpHandler>) -> Json<Value> {
+        Json(handler.resource.metadata())
+    }
+
+    /// Streamable HTTP transport: accepts a single JSON-RPC message and
+    /// answers requests with a JSON response. Notifications and client
+    /// responses are acknowledged with `202 Accepted`.
+    async fn handle_mcp_post(
         State(handler): State<HttpHandler>,
         headers: HeaderMap,
-    ) -> Result<Json<Value>, StatusCode> {
-        handler
-            .auth
-            .authenticate(&headers)
+        body: Bytes,
+    ) -> Response {
+        let principal = match handler.auth.authenticate(&headers).await {
+            Ok(principal) => principal,
+            Err(e) => return handler.auth.reject(e),
+        };
+
+        if let Err((status, error)) = Self::check_protocol_version(&headers) {
+            return Self::error_response(status, error);
+        }
+
+        let message: Value = match serde_json::from_slice(&body) {
+            Ok(message) => message,
+            Err(e) => {
+                return Self::error_response(
+                    StatusCode::BAD_REQUEST,
+                    McpError::parse_error(format!("Parse error: {}", e)),
+                )
+            }
+        };
+
+        let is_initialize = message.get("method").and_then(|v| v.as_str()) == Some("initialize");
+
+        let http_session = if is_initialize {
+            HttpSession::new(principal.as_ref())
+        } else {
+            match handler.require_session(&headers, principal.as_ref()).await {
+                Ok(http_session) => http_session,
+                Err((status, error)) => return Self::error_response(status, error),
+            }
+        };
+
+        if !is_initialize {
+            if let Err((status, error)) =
+                Self::check_negotiated_version(&headers, &http_session.session)
+            {
+                return Self::error_response(status, error);
+            }
+        }
+
+        // If every request in the body is cancelled the client is still
+        // waiting on this POST, so it gets an error rather than the 202
+        // reserved for notifications and responses.
+        let cancelled = Self::cancelled_response(&message);
+        let response = match handler
+            .dispatcher
+            .handle_message(&http_session.session, principal.as_ref(), message)
             .await
-            .map_err(StatusCode::from)?;
-
-        Ok(Json(json!({
-            "protocolVersion": DEFAULT_PROTOCOL_VERSION,
-            "capabilities": {
-                "tools": {
-                    "listChanged": false
-                },
-                "resources": {
-                    "subscribe": false,
-                    "listChanged": false
-                },
-                "prompts": {
-                    "listChanged": false
+            .or(cancelled)
+        {
+            Some(response) => response,
+            None => return StatusCode::ACCEPTED.into_response(),
+        };
+
+        let mut http_response = Json(&response).into_response();
+        if let Some(error) = Self::auth_error(&response) {
+            let rejection = handler.auth.reject(error);
+            *http_response.status_mut() = rejection.status();
+            http_response
+                .headers_mut()
+                .extend(rejection.headers().clone());
+        }
+
+        if is_initialize && response.get("result").is_some() {
+            let session_id = match handler.create_session(http_session).await {
+                Ok(session_id) => session_id,
+                Err((status, error)) => return Self::error_response(status, error),
+            };
+            if let Ok(value) = HeaderValue::from_str(&session_id) {
+                http_response.headers_mut().insert(SESSION_ID_HEADER, value);
+            }
+        }
+
+        http_response
+    }
+
+    /// Opens the server-to-client SSE stream for an established session.
+    async fn handle_mcp_get(State(handler): State<HttpHandler>, headers: HeaderMap) -> Response {
+        let principal = match handler.auth.authenticate(&headers).await {
+            Ok(principal) => principal,
+            Err(e) => return handler.auth.reject(e),
+        };
+
+        if let Err((status, error)) = Self::check_protocol_version(&headers) {
+            return Self::error_response(status, error);
+        }
+
+        let accepts_sse = headers
+            .get(header::ACCEPT)
+            .and_then(|h| h.to_str().ok())
+            .is_some_and(|accept| accept.contains("text/event-stream"));
+
+        if !accepts_sse {
+            return StatusCode::METHOD_NOT_ALLOWED.into_response();
+        }
+
+        let sender = match handler.require_session(&headers, principal.as_ref()).await {
+            Ok(http_session) => http_session.events,
+            Err((status, error)) => return Self::error_response(status, error),
+        };
+
+        let stream = futures::stream::unfold(sender.subscribe(), |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(message) => {
+                        let event = Event::default().event("message").json_data(message);
+                        return Some((event, receiver));
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!("SSE stream lagged, dropped {} messages", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
                 }
-            },
-            "serverInfo": {
-                "name": SERVER_NAME,
-                "version": SERVER_VERSION
             }
-        })))
+        });
+
+        Sse::new(stream)
+            .keep_alive(KeepAlive::default())
+            .into_response()
+    }
+
+    /// Terminates a session at the client's request.
+    async fn handle_mcp_delete(State(handler): State<HttpHandler>, headers: HeaderMap) -> Response {
+        let principal = match handler.auth.authenticate(&headers).await {
+            Ok(principal) => principal,
+            Err(e) => return handler.auth.reject(e),
+        };
+
+        let Some(session_id) = Self::session_id(&headers) else {
+            return Self::error_response(
+                StatusCode::BAD_REQUEST,
+                McpError::invalid_request("Missing Mcp-Session-Id header"),
+            );
+        };
+
+        let mut sessions = handler.sessions.write().await;
+        let owned = sessions
+            .get(session_id)
+            .is_some_and(|http_session| http_session.is_owned_by(principal.as_ref()));
+        if !owned {
+            return StatusCode::NOT_FOUND.into_response();
+        }
+
+        match sessions.remove(session_id) {
+            Some(http_session) => {
+                http_session.session.shut_down();
+                tracing::info!("Session {} terminated by client", session_id);
+                StatusCode::OK.into_response()
+            }
+            None => StatusCode::NOT_FOUND.into_response(),
+        }
+    }
+
+    /// Registers a newly initialized session, making room by expiring idle
+    /// ones if the session limit has been reached.
+    async fn create_session(
+        &self,
+        http_session: HttpSession,
+    ) -> Result<String, (StatusCode, McpError)> {
+        let mut sessions = self.sessions.write().await;
+        if sessions.len() >= self.max_sessions {
+            Self::remove_idle(&mut sessions, self.session_idle_timeout);
+        }
+        if sessions.len() >= self.max_sessions {
+            tracing::warn!("Refusing new session: {} sessions open", sessions.len());
+            return Err((
+                StatusCode::SERVICE_UNAVAILABLE,
+                McpError::internal_error("Too many open sessions"),
+            ));
+        }
+
+        let session_id = uuid::Uuid::new_v4().to_string();
+        sessions.insert(session_id.clone(), http_session);
+        tracing::info!("Created session {}", session_id);
+        Ok(session_id)
+    }
+
+    /// Looks up the session named by the `Mcp-Session-Id` header. Sessions
+    /// belonging to someone other than `principal` are reported as not
+    /// found.
+    async fn require_session(
+        &self,
+        headers: &HeaderMap,
+        principal: Option<&TokenInfo>,
+    ) -> Result<HttpSession, (StatusCode, McpError)> {
+        let session_id = Self::session_id(headers).ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                McpError::invalid_request("Missing Mcp-Session-Id header"),
+            )
+        })?;
+
+        let not_found = || {
+            (
+                StatusCode::NOT_FOUND,
+                McpError::invalid_request("Session not found"),
+            )
+        };
+
+        let http_session = self
+            .sessions
+            .read()
+            .await
+            .get(session_id)
+            .filter(|http_session| http_session.is_owned_by(principal))
+            .cloned()
+            .ok_or_else(not_found)?;
+
+        // The sweep may not have caught up with a session that just expired.
+        if http_session.is_idle(self.session_idle_timeout) {
+            if let Some(expired) = self.sessions.write().await.remove(session_id) {
+                expired.session.shut_down();
+                tracing::info!("Session {} expired", session_id);
+            }
+            return Err(not_found());
+        }
+
+        http_session.touch();
+        Ok(http_session)
+    }
+
+    fn session_id(headers: &HeaderMap) -> Option<&str> {
+        headers.get(SESSION_ID_HEADER).and_then(|h| h.to_str().ok())
+    }
+
+    fn check_protocol_version(headers: &HeaderMap) -> Result<(), (StatusCode, McpError)> {
+        let Some(version) = headers.get(PROTOCOL_VERSION_HEADER) else {
+            return Ok(());
+        };
+
+        match version.to_str() {
+            Ok(version) if version.parse::<ProtocolVersion>().is_ok() => Ok(()),
+            _ => Err((
+                StatusCode::BAD_REQUEST,
+                McpError::invalid_request("Unsupported MCP-Protocol-Version"),
+            )),
+        }
+    }
+
+    /// Rejects requests whose `MCP-Protocol-Version` header differs from the
+    /// revision negotiated when the session was initialized.
+    fn check_negotiated_version(
+        headers: &HeaderMap,
+        session: &Session,
+    ) -> Result<(), (StatusCode, McpError)> {
+        let Some(version) = headers.get(PROTOCOL_VERSION_HEADER) else {
+            return Ok(());
+        };
+
+        if version.to_str().ok() == Some(session.protocol_version().as_str()) {
+            Ok(())
+        } else {
+            Err((
+                StatusCode::BAD_REQUEST,
+                McpError::invalid_request(format!(
+                    "MCP-Protocol-Version does not match negotiated version {}",
+                    session.protocol_version()
+                )),
+            ))
+        }
+    }
+
+    fn error_response(status: StatusCode, error: McpError) -> Response {
+        (status, Json(McpResponse::<()>::error(Value::Null, error))).into_response()
+    }
+
+    async fn get_capabilities(
+        State(handler): State<HttpHandler>,
+        headers: HeaderMap,
+    ) -> Result<Json<Value>, Response> {
+        handler.rest_dispatch(&headers, "initialize", None).await
+    }
+
+    async fn list_tools(
+        State(handler): State<HttpHandler>,
+        headers: HeaderMap,
+    ) -> Result<Json<Value>, Response> {
+        handler.rest_dispatch(&headers, "tools/list", None).await
     }
 
     async fn call_tool(
         State(handler): State<HttpHandler>,
         headers: HeaderMap,
         Json(request): Json<McpRequest>,
-    ) -> Result<Json<Value>, StatusCode> {
+    ) -> Result<Json<Value>, Response> {
+        let params = json!({
+            "name": request.name.ok_or_else(|| StatusCode::BAD_REQUEST.into_response())?,
+            "arguments": request.arguments.unwrap_or(Value::Null)
+        });
+
         handler
-            .auth
-            .authenticate(&headers)
+            .rest_dispatch(&headers, "tools/call", Some(params))
             .await
-            .map_err(StatusCode::from)?;
-
-        let tool_name = request.name.ok_or(StatusCode::BAD_REQUEST)?;
-        let arguments = request.arguments.unwrap_or(Value::Null);
-
-        let result = Self::execute_tool(&tool_name, arguments).await;
+    }
 
-        match result {
-            Ok(content) => Ok(Json(json!({
-                "content": [{
-                    "type": "text",
-                    "text": content
-                }]
-            }))),
-            Err(e) => {
-                tracing::error!("Tool execution error: {}", e);
-                Err(StatusCode::INTERNAL_SERVER_ERROR)
-            }
-        }
+    async fn list_resources(
+        State(handler): State<HttpHandler>,
+        headers: HeaderMap,
+    ) -> Result<Json<Value>, Response> {
+        handler
+            .rest_dispatch(&headers, "resources/list", None)
+            .await
     }
 
     async fn read_resource(
         State(handler): State<HttpHandler>,
         headers: HeaderMap,
         Json(request): Json<McpRequest>,
-    ) -> Result<Json<Value>, StatusCode> {
+    ) -> Result<Json<Value>, Response> {
+        let params = json!({
+            "uri": request.uri.ok_or_else(|| StatusCode::BAD_REQUEST.into_response())?
+        });
+
         handler
-            .auth
-            .authenticate(&headers)
+            .rest_dispatch(&headers, "resources/read", Some(params))
             .await
-            .map_err(StatusCode::from)?;
-
-        let uri = request.uri.ok_or(StatusCode::BAD_REQUEST)?;
-
-        let content = match uri.as_str() {
-            "timezone_database" => {
-                let timezones: Vec<String> =
-                    TZ_VARIANTS.iter().map(|tz| tz.name().to_string()).collect();
-                json!({
-                    "timezones": timezones,
-                    "total_count": timezones.len()
-                })
-                .to_string()
-            }
-            "time_formats" => Self::get_time_formats_resource().to_string(),
-            _ => return Err(StatusCode::NOT_FOUND),
-        };
+    }
 
-        Ok(Json(json!({
-            "contents": [{
-                "uri": uri,
-                "mimeType": "application/json",
-                "text": content
-            }]
-        })))
+    async fn list_prompts(
+        State(handler): State<HttpHandler>,
+        headers: HeaderMap,
+    ) -> Result<Json<Value>, Response> {
+        handler.rest_dispatch(&headers, "prompts/list", None).await
     }
 
     async fn get_prompt(
         State(handler): State<HttpHandler>,
         headers: HeaderMap,
         Json(request): Json<McpRequest>,
-    ) -> Result<Json<Value>, StatusCode> {
+    ) -> Result<Json<Value>, Response> {
+        let params = json!({
+            "name": request.name.ok_or_else(|| StatusCode::BAD_REQUEST.into_response())?,
+            "arguments": request.arguments.unwrap_or(Value::Null)
+        });
+
         handler
+            .rest_dispatch(&headers, "prompts/get", Some(params))
+            .await
+    }
+
+    /// Authenticates a REST compatibility call and forwards it to the shared
+    /// dispatcher, mapping protocol errors onto HTTP status codes.
+    async fn rest_dispatch(
+        &self,
+        headers: &HeaderMap,
+        method: &str,
+        params: Option<Value>,
+    ) -> Result<Json<Value>, Response> {
+        let principal = self
             .auth
-            .authenticate(&headers)
+            .authenticate(headers)
             .await
-            .map_err(StatusCode::from)?;
-
-        let prompt_name = request.name.ok_or(StatusCode::BAD_REQUEST)?;
-
-        match prompt_name.as_str() {
-            "time_query_assistant" => {
-                let user_query = request
-                    .arguments
-                    .as_ref()
-                    .and_then(|args| args.get("user_query"))
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("general time query");
-
-                let current_time = Utc::now();
-
-                Ok(Json(json!({
-                    "description": "Assistant for time-related queries",
-                    "messages": [{
-                        "role": "system",
-                        "content": {
-                            "type": "text",
-                            "text": format!(
-                                "You are a time query assistant. Help the user with their time-related question: '{}'. Current UTC time: {}. You have access to comprehensive timezone conversion and time formatting tools.",
-                                user_query,
-                                current_time.to_rfc3339()
-                            )
-                        }
-                    }]
-                })))
-            }
-            _ => Err(StatusCode::NOT_FOUND),
-        }
-    }
-
-    async fn execute_tool(name: &str, arguments: Value) -> anyhow::Result<String> {
-        match name {
-            "get_current_time" => TimeTools::get_current_time(arguments).await,
-            "convert_timezone" => TimeTools::convert_timezone(arguments).await,
-            "calculate_duration" => TimeTools::calculate_duration(arguments).await,
-            "format_time" => TimeTools::format_time(arguments).await,
-            "get_timezone_info" => TimeTools::get_timezone_info(arguments).await,
-            "list_timezones" => TimeTools::list_timezones(arguments).await,
-            _ => Err(anyhow::anyhow!("Tool not found: {}", name)),
-        }
-    }
-
-    fn get_time_formats_resource() -> Value {
-        json!({
-            "iso8601_examples": [
-                "2025-08-17T18:30:00Z",
-                "2025-08-17T18:30:00.123Z",
-                "2025-08-17T14:30:00-04:00"
-            ],
-            "rfc3339_examples": [
-                "2025-08-17T18:30:00Z",
-                "2025-08-17T18:30:00.123456Z"
-            ],
-            "custom_format_strings": {
-                "examples": [
-                    "%Y-%m-%d %H:%M:%S",
-                    "%B %d, %Y at %I:%M %p",
-                    "%A, %b %d, %Y"
-                ],
-                "documentation": "Uses strftime format codes. Common codes: %Y=year, %m=month, %d=day, %H=hour(24h), %I=hour(12h), %M=minute, %S=second, %Z=timezone"
-            },
-            "locale_specific_formats": {
-                "us": "%m/%d/%Y %I:%M %p",
-                "eu": "%d/%m/%Y %H:%M",
-                "iso": "%Y-%m-%d %H:%M:%S"
+            .map_err(|e| self.auth.reject(e))?;
+
+        self.dispatcher
+            .dispatch_with_session(&Session::new(), principal.as_ref(), method, params)
+            .await
+            .map(Json)
+            .map_err(|e| {
+                tracing::error!("{} failed: {}", method, e.message);
+                match e.code {
+                    401 | 403 => self.auth.reject(e),
+                    _ => StatusCode::from(e).into_response(),
+                }
+            })
+    }
+
+    /// Errors answering the requests in `message` as cancelled, or `None`
+    /// if it holds only notifications and client responses.
+    fn cancelled_response(message: &Value) -> Option<Value> {
+        let cancelled = |message: &Value| {
+            message.get("method")?;
+            let id = message.get("id")?.clone();
+            Some(Dispatcher::error_response(
+                id,
+                McpError::request_cancelled("Request cancelled"),
+            ))
+        };
+        match message {
+            Value::Array(batch) => {
+                let responses: Vec<Value> = batch.iter().filter_map(cancelled).collect();
+                (!responses.is_empty()).then_some(Value::Array(responses))
             }
-        })
+            message => cancelled(message),
+        }
+    }
+
+    /// The authentication or authorization failure in a single JSON-RPC
+    /// response, which is also reported through the HTTP status.
+    fn auth_error(response: &Value) -> Option<McpError> {
+        let error = response.get("error")?;
+        let code = error.get("code")?.as_i64()?;
+        if code != 401 && code != 403 {
+            return None;
+        }
+
+        let message = error.get("message").and_then(|m| m.as_str()).unwrap_or("");
+        let mut mcp_error = McpError::new(code as i32, message);
+        mcp_error.data = error.get("data").cloned();
+        Some(mcp_error)
     }
 }
@@ -1,416 +1,454 @@
 use crate::{
-    config::{DEFAULT_PROTOCOL_VERSION, FALLBACK_PROTOCOL_VERSION, SERVER_NAME, SERVER_VERSION},
-    models::{McpError, McpResponse},
-    tools::TimeTools,
+    completion::{self, MAX_COMPLETIONS},
+    config::{SERVER_NAME, SERVER_VERSION},
+    logging::LogLevel,
+    models::{ClientInfo, McpError, McpResult},
+    pagination::{self, Page},
+    protocol::ProtocolVersion,
+    registry::{ProgressReporter, ToolContext, ToolRegistry},
+    resources::{self, Cadence, TimeResource},
+    session::Session,
+    tools::TimeError,
 };
 use chrono::Utc;
-use chrono_tz::TZ_VARIANTS;
+use chrono_tz::Tz;
 use serde_json::{json, Value};
 
 pub struct McpHandlers;
 
 impl McpHandlers {
-    pub async fn handle_initialize(id: Value, params: Option<Value>) -> Value {
-        let client_version = params
+    /// Negotiates the protocol revision, records it on the session and
+    /// advertises the capabilities available in that revision.
+    pub async fn handle_initialize(session: &Session, params: Option<Value>) -> McpResult<Value> {
+        let requested = params
             .as_ref()
             .and_then(|p| p.get("protocolVersion"))
-            .and_then(|v| v.as_str())
-            .unwrap_or(DEFAULT_PROTOCOL_VERSION);
+            .and_then(|v| v.as_str());
+
+        let protocol_version = ProtocolVersion::negotiate(requested);
+        if let Some(requested) = requested.filter(|r| *r != protocol_version.as_str()) {
+            tracing::info!(
+                "Client requested unsupported protocol version {}, offering {}",
+                requested,
+                protocol_version
+            );
+        }
 
-        let protocol_version = if client_version == FALLBACK_PROTOCOL_VERSION {
-            FALLBACK_PROTOCOL_VERSION
-        } else {
-            DEFAULT_PROTOCOL_VERSION
+        let client_info = match params.as_ref().and_then(|p| p.get("clientInfo")) {
+            Some(client_info) => Some(
+                serde_json::from_value::<ClientInfo>(client_info.clone())
+                    .map_err(|e| McpError::invalid_params(format!("Invalid clientInfo: {}", e)))?,
+            ),
+            None => None,
         };
+        let client_capabilities = params
+            .as_ref()
+            .and_then(|p| p.get("capabilities"))
+            .cloned()
+            .unwrap_or_else(|| json!({}));
 
-        let response = McpResponse::success(
-            id,
-            json!({
-                "protocolVersion": protocol_version,
-                "capabilities": {
-                    "tools": {
-                        "listChanged": false
-                    },
-                    "resources": {
-                        "subscribe": false,
-                        "listChanged": false
-                    },
-                    "prompts": {
-                        "listChanged": false
-                    }
+        if let Some(client_info) = &client_info {
+            tracing::info!(
+                "Initializing session for {} {}",
+                client_info.name,
+                client_info.version
+            );
+        }
+        session.initialize(protocol_version, client_info, client_capabilities)?;
+
+        let mut result = json!({
+            "protocolVersion": protocol_version.as_str(),
+            "capabilities": {
+                "logging": {},
+                "tools": {
+                    "listChanged": false
+                },
+                "resources": {
+                    "subscribe": true,
+                    "listChanged": false
                 },
-                "serverInfo": {
-                    "name": SERVER_NAME,
-                    "version": SERVER_VERSION
+                "prompts": {
+                    "listChanged": false
                 }
-            }),
-        );
+            },
+            "serverInfo": {
+                "name": SERVER_NAME,
+                "version": SERVER_VERSION
+            }
+        });
+
+        if protocol_version.supports_completions_capability() {
+            result["capabilities"]["completions"] = json!({});
+        }
 
-        serde_json::to_value(response).unwrap_or_else(|_| json!({}))
+        Ok(result)
     }
 
-    pub async fn handle_tools_list(id: Value) -> Value {
-        let response = McpResponse::success(
-            id,
-            json!({
-                "tools": Self::get_tool_definitions()
-            }),
-        );
+    pub async fn handle_logging_set_level(
+        session: &Session,
+        params: Option<Value>,
+    ) -> McpResult<Value> {
+        let level = params
+            .as_ref()
+            .and_then(|p| p.get("level"))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| McpError::invalid_params("Missing level"))?
+            .parse::<LogLevel>()
+            .map_err(McpError::invalid_params)?;
+
+        session.set_log_level(level);
+        tracing::info!("Client log level set to {}", level);
 
-        serde_json::to_value(response).unwrap_or_else(|_| json!({}))
+        Ok(json!({}))
     }
 
-    pub async fn handle_tools_call(id: Value, params: Option<Value>) -> Value {
-        let params = match params {
-            Some(p) => p,
-            None => {
-                let error_response =
-                    McpResponse::<()>::error(id, McpError::invalid_params("Missing params"));
-                return serde_json::to_value(error_response).unwrap_or_else(|_| json!({}));
+    pub async fn handle_tools_list(
+        registry: &ToolRegistry,
+        version: ProtocolVersion,
+        params: Option<Value>,
+        page_size: usize,
+        allowed: impl Fn(&str) -> bool,
+    ) -> McpResult<Value> {
+        let mut definitions = Self::visible(registry.definitions(), "name", allowed);
+        if !version.supports_structured_output() {
+            for definition in &mut definitions {
+                if let Some(definition) = definition.as_object_mut() {
+                    definition.remove("outputSchema");
+                }
             }
+        }
+
+        let page = pagination::paginate_request(definitions, &params, page_size)?;
+        Ok(Self::list_result("tools", page))
+    }
+
+    pub async fn handle_tools_call(
+        registry: &ToolRegistry,
+        session: &Session,
+        version: ProtocolVersion,
+        params: Option<Value>,
+    ) -> McpResult<Value> {
+        let params = params.ok_or_else(|| McpError::invalid_params("Missing params"))?;
+
+        let name = params
+            .get("name")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| McpError::invalid_params("Missing tool name"))?;
+
+        let arguments = params.get("arguments").cloned().unwrap_or(Value::Null);
+
+        let tool = registry
+            .get(name)
+            .ok_or_else(|| McpError::invalid_params(format!("Unknown tool: {}", name)))?;
+
+        let progress_token = params
+            .get("_meta")
+            .and_then(|meta| meta.get("progressToken"))
+            .filter(|token| token.is_string() || token.is_i64() || token.is_u64());
+
+        let context = ToolContext {
+            progress: match progress_token {
+                Some(token) => ProgressReporter::new(token.clone(), session.notifier().clone()),
+                None => ProgressReporter::default(),
+            },
+            client: session.client_info(),
         };
 
-        let name = match params.get("name").and_then(|v| v.as_str()) {
-            Some(n) => n,
-            None => {
-                let error_response =
-                    McpResponse::<()>::error(id, McpError::invalid_params("Missing tool name"));
-                return serde_json::to_value(error_response).unwrap_or_else(|_| json!({}));
-            }
+        let output = match tool.execute_with_context(arguments, &context).await {
+            Ok(output) => output,
+            Err(error) => return Ok(Self::tool_error_result(&error, version)),
         };
 
-        let arguments = params.get("arguments").cloned().unwrap_or(Value::Null);
+        let text = match &output {
+            Value::String(text) => text.clone(),
+            other => other.to_string(),
+        };
 
-        let result = Self::execute_tool(name, arguments).await;
+        let mut result = json!({
+            "content": [{
+                "type": "text",
+                "text": text
+            }]
+        });
 
-        match result {
-            Ok(content) => {
-                let response = McpResponse::success(
-                    id,
-                    json!({
-                        "content": [{
-                            "type": "text",
-                            "text": content
-                        }]
-                    }),
-                );
-                serde_json::to_value(response).unwrap_or_else(|_| json!({}))
-            }
-            Err(e) => {
-                let error_response =
-                    McpResponse::<()>::error(id, McpError::internal_error(e.to_string()));
-                serde_json::to_value(error_response).unwrap_or_else(|_| json!({}))
-            }
+        if output.is_object() && version.supports_structured_output() {
+            result["structuredContent"] = output;
         }
+
+        Ok(result)
     }
 
-    pub async fn handle_resources_list(id: Value) -> Value {
-        let response = McpResponse::success(
-            id,
-            json!({
-                "resources": [
-                    {
-                        "uri": "timezone_database",
-                        "name": "Timezone Database",
-                        "description": "Complete IANA timezone database",
-                        "mimeType": "application/json"
-                    },
-                    {
-                        "uri": "time_formats",
-                        "name": "Time Formats",
-                        "description": "Documentation of supported time formats",
-                        "mimeType": "application/json"
-                    }
-                ]
-            }),
-        );
+    /// Reports a failed tool execution as an `isError` result the model can
+    /// read, rather than as a JSON-RPC protocol error.
+    fn tool_error_result(error: &anyhow::Error, version: ProtocolVersion) -> Value {
+        let code = error
+            .downcast_ref::<TimeError>()
+            .map(TimeError::code)
+            .unwrap_or(-32603);
+        let message = error.to_string();
+
+        let mut result = json!({
+            "content": [{
+                "type": "text",
+                "text": message
+            }],
+            "isError": true
+        });
+
+        if version.supports_structured_output() {
+            result["structuredContent"] = json!({
+                "error": {
+                    "code": code,
+                    "message": message
+                }
+            });
+        }
+
+        result
+    }
+
+    pub async fn handle_resources_list(
+        params: Option<Value>,
+        page_size: usize,
+        allowed: impl Fn(&str) -> bool,
+    ) -> McpResult<Value> {
+        let resources = Self::visible(resources::resource_definitions(), "uri", allowed);
+        let page = pagination::paginate_request(resources, &params, page_size)?;
+        Ok(Self::list_result("resources", page))
+    }
+
+    pub async fn handle_resources_templates_list(
+        params: Option<Value>,
+        page_size: usize,
+        allowed: impl Fn(&str) -> bool,
+    ) -> McpResult<Value> {
+        let templates = Self::visible(resources::template_definitions(), "uriTemplate", allowed);
+        let page = pagination::paginate_request(templates, &params, page_size)?;
+        Ok(Self::list_result("resourceTemplates", page))
+    }
 
-        serde_json::to_value(response).unwrap_or_else(|_| json!({}))
+    /// Keeps the list items whose `key` field passes `allowed`.
+    fn visible(items: Vec<Value>, key: &str, allowed: impl Fn(&str) -> bool) -> Vec<Value> {
+        items
+            .into_iter()
+            .filter(|item| item.get(key).and_then(|v| v.as_str()).is_some_and(&allowed))
+            .collect()
     }
 
-    pub async fn handle_resources_read(id: Value, params: Option<Value>) -> Value {
-        let uri = match params
+    /// Wraps one page of a list method's items under `key`, adding
+    /// `nextCursor` when there are more pages.
+    fn list_result(key: &str, page: Page<Value>) -> Value {
+        let mut result = json!({ key: page.items });
+        if let Some(cursor) = page.next_cursor {
+            result["nextCursor"] = json!(cursor);
+        }
+        result
+    }
+
+    pub async fn handle_resources_read(params: Option<Value>) -> McpResult<Value> {
+        let uri = params
             .as_ref()
             .and_then(|p| p.get("uri"))
             .and_then(|v| v.as_str())
-        {
-            Some(u) => u,
-            None => {
-                let error_response =
-                    McpResponse::<()>::error(id, McpError::invalid_params("Missing URI"));
-                return serde_json::to_value(error_response).unwrap_or_else(|_| json!({}));
-            }
-        };
+            .ok_or_else(|| McpError::invalid_params("Missing URI"))?;
 
-        let content = match uri {
-            "timezone_database" => {
-                let timezones: Vec<String> =
-                    TZ_VARIANTS.iter().map(|tz| tz.name().to_string()).collect();
-                json!({
-                    "timezones": timezones,
-                    "total_count": timezones.len()
-                })
-                .to_string()
-            }
-            "time_formats" => Self::get_time_formats_resource().to_string(),
-            _ => {
-                let error_response =
-                    McpResponse::<()>::error(id, McpError::invalid_params("Unknown resource"));
-                return serde_json::to_value(error_response).unwrap_or_else(|_| json!({}));
-            }
+        let content = TimeResource::parse(uri)?.read();
+
+        Ok(json!({
+            "contents": [{
+                "uri": uri,
+                "mimeType": "application/json",
+                "text": content.to_string()
+            }]
+        }))
+    }
+
+    /// Subscribes the session to `uri`. `time://now/{tz}` resources are
+    /// updated on their cadence and `time://zone/{tz}` on offset transitions;
+    /// other resources never change, so subscribing to them is a no-op.
+    pub async fn handle_resources_subscribe(
+        session: &Session,
+        params: Option<Value>,
+    ) -> McpResult<Value> {
+        let uri = Self::resource_uri(&params)?;
+
+        let schedule = match TimeResource::parse(uri)? {
+            TimeResource::Now(tz, cadence) => Some((tz, cadence)),
+            TimeResource::Zone(tz) => Some((tz, Cadence::Transition)),
+            _ => None,
         };
 
-        let response = McpResponse::success(
-            id,
-            json!({
-                "contents": [{
-                    "uri": uri,
-                    "mimeType": "application/json",
-                    "text": content
-                }]
-            }),
-        );
-
-        serde_json::to_value(response).unwrap_or_else(|_| json!({}))
+        if let Some((tz, cadence)) = schedule {
+            let notifier = session.notifier().clone();
+            let updated = json!({"uri": uri});
+
+            let updates = tokio::spawn(async move {
+                let mut last = Utc::now();
+                while let Some(next) = cadence.next_tick(tz, last) {
+                    let delay = (next - Utc::now()).to_std().unwrap_or_default();
+                    tokio::time::sleep(delay).await;
+                    notifier.notify("notifications/resources/updated", updated.clone());
+                    last = next;
+                }
+            });
+
+            session.subscribe(uri, updates.abort_handle());
+            tracing::info!("Subscribed to {}", uri);
+        }
+
+        Ok(json!({}))
     }
 
-    pub async fn handle_prompts_list(id: Value) -> Value {
-        let response = McpResponse::success(
-            id,
-            json!({
-                "prompts": [{
-                    "name": "time_query_assistant",
-                    "description": "Template for helping users with time-related queries",
-                    "arguments": [{
-                        "name": "user_query",
-                        "description": "The user's time-related question",
-                        "required": true
-                    }]
-                }]
-            }),
-        );
+    pub async fn handle_resources_unsubscribe(
+        session: &Session,
+        params: Option<Value>,
+    ) -> McpResult<Value> {
+        let uri = Self::resource_uri(&params)?;
+
+        if session.unsubscribe(uri) {
+            tracing::info!("Unsubscribed from {}", uri);
+        }
+
+        Ok(json!({}))
+    }
+
+    fn resource_uri(params: &Option<Value>) -> McpResult<&str> {
+        params
+            .as_ref()
+            .and_then(|p| p.get("uri"))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| McpError::invalid_params("Missing URI"))
+    }
+
+    pub async fn handle_prompts_list(
+        params: Option<Value>,
+        page_size: usize,
+        allowed: impl Fn(&str) -> bool,
+    ) -> McpResult<Value> {
+        let prompts = vec![json!({
+            "name": "time_query_assistant",
+            "description": "Template for helping users with time-related queries",
+            "arguments": [
+                {
+                    "name": "user_query",
+                    "description": "The user's time-related question",
+                    "required": true
+                },
+                {
+                    "name": "timezone",
+                    "description": "The user's local timezone (IANA identifier)",
+                    "required": false
+                }
+            ]
+        })];
 
-        serde_json::to_value(response).unwrap_or_else(|_| json!({}))
+        let prompts = Self::visible(prompts, "name", allowed);
+        let page = pagination::paginate_request(prompts, &params, page_size)?;
+        Ok(Self::list_result("prompts", page))
     }
 
-    pub async fn handle_prompts_get(id: Value, params: Option<Value>) -> Value {
-        let name = match params
+    pub async fn handle_prompts_get(params: Option<Value>) -> McpResult<Value> {
+        let name = params
             .as_ref()
             .and_then(|p| p.get("name"))
             .and_then(|v| v.as_str())
-        {
-            Some(n) => n,
-            None => {
-                let error_response =
-                    McpResponse::<()>::error(id, McpError::invalid_params("Missing prompt name"));
-                return serde_json::to_value(error_response).unwrap_or_else(|_| json!({}));
-            }
-        };
+            .ok_or_else(|| McpError::invalid_params("Missing prompt name"))?;
 
         match name {
             "time_query_assistant" => {
-                let user_query = params
-                    .as_ref()
-                    .and_then(|p| p.get("arguments"))
+                let arguments = params.as_ref().and_then(|p| p.get("arguments"));
+                let user_query = arguments
                     .and_then(|args| args.get("user_query"))
                     .and_then(|v| v.as_str())
                     .unwrap_or("general time query");
 
                 let current_time = Utc::now();
 
-                let response = McpResponse::success(
-                    id,
-                    json!({
-                        "description": "Assistant for time-related queries",
-                        "messages": [{
-                            "role": "system",
-                            "content": {
-                                "type": "text",
-                                "text": format!(
-                                    "You are a time query assistant. Help the user with their time-related question: '{}'. Current UTC time: {}. You have access to comprehensive timezone conversion and time formatting tools.",
-                                    user_query,
-                                    current_time.to_rfc3339()
-                                )
-                            }
-                        }]
-                    }),
-                );
-
-                serde_json::to_value(response).unwrap_or_else(|_| json!({}))
-            }
-            _ => {
-                let error_response =
-                    McpResponse::<()>::error(id, McpError::invalid_params("Unknown prompt"));
-                serde_json::to_value(error_response).unwrap_or_else(|_| json!({}))
+                let local_context = match arguments.and_then(|args| args.get("timezone")) {
+                    Some(timezone) => {
+                        let timezone = timezone
+                            .as_str()
+                            .and_then(|tz| tz.parse::<Tz>().ok())
+                            .ok_or_else(|| McpError::invalid_params("Invalid timezone"))?;
+                        format!(
+                            " The user's timezone is {}, where it is currently {}.",
+                            timezone,
+                            current_time.with_timezone(&timezone).to_rfc3339()
+                        )
+                    }
+                    None => String::new(),
+                };
+
+                Ok(json!({
+                    "description": "Assistant for time-related queries",
+                    "messages": [{
+                        "role": "system",
+                        "content": {
+                            "type": "text",
+                            "text": format!(
+                                "You are a time query assistant. Help the user with their time-related question: '{}'. Current UTC time: {}.{} You have access to comprehensive timezone conversion and time formatting tools.",
+                                user_query,
+                                current_time.to_rfc3339(),
+                                local_context
+                            )
+                        }
+                    }]
+                }))
             }
+            _ => Err(McpError::invalid_params("Unknown prompt")),
         }
     }
 
-    async fn execute_tool(name: &str, arguments: Value) -> anyhow::Result<String> {
-        match name {
-            "get_current_time" => TimeTools::get_current_time(arguments).await,
-            "convert_timezone" => TimeTools::convert_timezone(arguments).await,
-            "calculate_duration" => TimeTools::calculate_duration(arguments).await,
-            "format_time" => TimeTools::format_time(arguments).await,
-            "get_timezone_info" => TimeTools::get_timezone_info(arguments).await,
-            "list_timezones" => TimeTools::list_timezones(arguments).await,
-            _ => Err(anyhow::anyhow!("Tool not found: {}", name)),
-        }
-    }
+    /// Completes a prompt argument or resource template variable. Values are
+    /// ranked best first and capped at [`MAX_COMPLETIONS`].
+    pub async fn handle_completion_complete(params: Option<Value>) -> McpResult<Value> {
+        let params = params.ok_or_else(|| McpError::invalid_params("Missing params"))?;
 
-    fn get_tool_definitions() -> Value {
-        json!([
-            {
-                "name": "get_current_time",
-                "description": "Get the current time in various formats and timezones",
-                "inputSchema": {
-                    "type": "object",
-                    "properties": {
-                        "timezone": {
-                            "type": "string",
-                            "description": "Target timezone (default: UTC)",
-                            "default": "UTC"
-                        },
-                        "format": {
-                            "type": "string",
-                            "enum": ["iso", "unix", "human", "custom"],
-                            "description": "Output format",
-                            "default": "iso"
-                        },
-                        "custom_format": {
-                            "type": "string",
-                            "description": "Custom strftime format string"
-                        }
-                    }
-                }
-            },
-            {
-                "name": "convert_timezone",
-                "description": "Convert time between different timezones",
-                "inputSchema": {
-                    "type": "object",
-                    "properties": {
-                        "timestamp": {
-                            "type": "string",
-                            "description": "Input timestamp (ISO 8601 or Unix)"
-                        },
-                        "from_timezone": {
-                            "type": "string",
-                            "description": "Source timezone"
-                        },
-                        "to_timezone": {
-                            "type": "string",
-                            "description": "Target timezone"
-                        }
-                    },
-                    "required": ["timestamp", "from_timezone", "to_timezone"]
-                }
-            },
-            {
-                "name": "calculate_duration",
-                "description": "Calculate time differences between two timestamps",
-                "inputSchema": {
-                    "type": "object",
-                    "properties": {
-                        "start_time": {
-                            "type": "string",
-                            "description": "Start timestamp"
-                        },
-                        "end_time": {
-                            "type": "string",
-                            "description": "End timestamp"
-                        },
-                        "units": {
-                            "type": "string",
-                            "enum": ["seconds", "minutes", "hours", "days"],
-                            "description": "Output units",
-                            "default": "seconds"
-                        }
-                    },
-                    "required": ["start_time", "end_time"]
-                }
-            },
-            {
-                "name": "format_time",
-                "description": "Format timestamps according to various standards",
-                "inputSchema": {
-                    "type": "object",
-                    "properties": {
-                        "timestamp": {
-                            "type": "string",
-                            "description": "Input timestamp"
-                        },
-                        "format": {
-                            "type": "string",
-                            "enum": ["iso8601", "rfc3339", "unix", "custom"],
-                            "description": "Format type"
-                        },
-                        "custom_format": {
-                            "type": "string",
-                            "description": "Custom format string"
-                        },
-                        "timezone": {
-                            "type": "string",
-                            "description": "Target timezone"
-                        }
-                    },
-                    "required": ["timestamp", "format"]
-                }
-            },
-            {
-                "name": "get_timezone_info",
-                "description": "Retrieve timezone information and current offset",
-                "inputSchema": {
-                    "type": "object",
-                    "properties": {
-                        "timezone": {
-                            "type": "string",
-                            "description": "Timezone identifier"
-                        }
-                    },
-                    "required": ["timezone"]
+        let reference = params
+            .get("ref")
+            .ok_or_else(|| McpError::invalid_params("Missing ref"))?;
+
+        match reference.get("type").and_then(|v| v.as_str()) {
+            Some("ref/prompt") => {
+                let name = reference
+                    .get("name")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| McpError::invalid_params("Missing prompt name"))?;
+                if name != "time_query_assistant" {
+                    return Err(McpError::invalid_params("Unknown prompt"));
                 }
-            },
-            {
-                "name": "list_timezones",
-                "description": "List available timezone identifiers",
-                "inputSchema": {
-                    "type": "object",
-                    "properties": {
-                        "region": {
-                            "type": "string",
-                            "description": "Filter by region (e.g., 'America', 'Europe')"
-                        }
-                    }
+            }
+            Some("ref/resource") => {
+                let uri = reference
+                    .get("uri")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| McpError::invalid_params("Missing resource uri"))?;
+                if !resources::is_known_uri_or_template(uri) {
+                    return Err(McpError::invalid_params("Unknown resource"));
                 }
             }
-        ])
-    }
+            _ => return Err(McpError::invalid_params("Invalid ref type")),
+        }
 
-    fn get_time_formats_resource() -> Value {
-        json!({
-            "iso8601_examples": [
-                "2025-08-17T18:30:00Z",
-                "2025-08-17T18:30:00.123Z",
-                "2025-08-17T14:30:00-04:00"
-            ],
-            "rfc3339_examples": [
-                "2025-08-17T18:30:00Z",
-                "2025-08-17T18:30:00.123456Z"
-            ],
-            "custom_format_strings": {
-                "examples": [
-                    "%Y-%m-%d %H:%M:%S",
-                    "%B %d, %Y at %I:%M %p",
-                    "%A, %b %d, %Y"
-                ],
-                "documentation": "Uses strftime format codes. Common codes: %Y=year, %m=month, %d=day, %H=hour(24h), %I=hour(12h), %M=minute, %S=second, %Z=timezone"
-            },
-            "locale_specific_formats": {
-                "us": "%m/%d/%Y %I:%M %p",
-                "eu": "%d/%m/%Y %H:%M",
-                "iso": "%Y-%m-%d %H:%M:%S"
+        let argument = params
+            .get("argument")
+            .ok_or_else(|| McpError::invalid_params("Missing argument"))?;
+        let name = argument
+            .get("name")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| McpError::invalid_params("Missing argument name"))?;
+        let value = argument.get("value").and_then(|v| v.as_str()).unwrap_or("");
+
+        let mut values = completion::complete(name, value);
+        let total = values.len();
+        values.truncate(MAX_COMPLETIONS);
+
+        Ok(json!({
+            "completion": {
+                "values": values,
+                "total": total,
+                "hasMore": total > MAX_COMPLETIONS
             }
-        })
+        }))
     }
 }
@@ -1,3 +1,9 @@
+pub(crate) mod connection;
+pub mod dispatcher;
 pub mod http;
 pub mod mcp;
+pub mod origin;
 pub mod stdio;
+#[cfg(unix)]
+pub mod unix;
+pub mod ws;
@@ -0,0 +1,84 @@
+use axum::http::{header, HeaderMap};
+use oauth2::url::{Host, Url};
+
+/// Which browser origins may reach the HTTP and WebSocket transports.
+///
+/// Requests without an `Origin` header come from non-browser clients and are
+/// always allowed. Loopback origins (`localhost`, `127.0.0.1`, `[::1]`) are
+/// allowed on any port; anything else must be listed, or the policy must
+/// allow `*`. This keeps arbitrary web pages from driving a local server
+/// through the user's browser.
+#[derive(Clone, Debug, Default)]
+pub struct OriginPolicy {
+    allowed: Vec<String>,
+    allow_any: bool,
+}
+
+impl OriginPolicy {
+    /// Policy allowing only loopback origins.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also allows `origin`, e.g. `https://app.example.com`, or every origin
+    /// if it is `*`.
+    pub fn with_origin(mut self, origin: &str) -> Self {
+        match origin.trim() {
+            "*" => self.allow_any = true,
+            origin => self.allowed.push(canonical(origin)),
+        }
+        self
+    }
+
+    /// Policy allowing loopback origins plus the comma-separated `origins`.
+    pub fn from_list(origins: &str) -> Self {
+        origins
+            .split(',')
+            .map(str::trim)
+            .filter(|origin| !origin.is_empty())
+            .fold(Self::new(), Self::with_origin)
+    }
+
+    /// Whether a request carrying `origin` (if any) may be served.
+    pub fn allows(&self, origin: Option<&str>) -> bool {
+        let Some(origin) = origin else {
+            return true;
+        };
+        self.allow_any || is_loopback(origin) || self.allowed.contains(&canonical(origin))
+    }
+
+    /// Like [`OriginPolicy::allows`], for the `Origin` header in `headers`.
+    /// An `Origin` that isn't valid UTF-8 is never allowed.
+    pub fn allows_request(&self, headers: &HeaderMap) -> bool {
+        match headers.get(header::ORIGIN) {
+            None => true,
+            Some(origin) => origin.to_str().is_ok_and(|o| self.allows(Some(o))),
+        }
+    }
+}
+
+/// `origin` serialized the way browsers send it: lowercase scheme and host,
+/// no default port and no trailing slash. Unparseable values are kept as
+/// they are, so they only ever match themselves.
+fn canonical(origin: &str) -> String {
+    match Url::parse(origin) {
+        Ok(url) if url.has_host() => url.origin().ascii_serialization(),
+        _ => origin.to_string(),
+    }
+}
+
+fn is_loopback(origin: &str) -> bool {
+    let Ok(url) = Url::parse(origin) else {
+        return false;
+    };
+    if !matches!(url.scheme(), "http" | "https") {
+        return false;
+    }
+
+    match url.host() {
+        Some(Host::Domain(domain)) => domain.eq_ignore_ascii_case("localhost"),
+        Some(Host::Ipv4(ip)) => ip.is_loopback(),
+        Some(Host::Ipv6(ip)) => ip.is_loopback(),
+        None => false,
+    }
+}
@@ -1,83 +1,123 @@
-use crate::handlers::mcp::McpHandlers;
-use crate::models::{McpError, McpResponse};
+use crate::config::DEFAULT_MAX_IN_FLIGHT;
+use crate::handlers::connection::{serve_connection, OUTBOUND_CAPACITY};
+use crate::handlers::dispatcher::Dispatcher;
+use crate::shutdown::{Shutdown, DEFAULT_SHUTDOWN_TIMEOUT};
 use anyhow::Result;
+use futures::stream;
 use serde_json::Value;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
 
-pub struct StdioHandler;
+pub struct StdioHandler {
+    dispatcher: Dispatcher,
+    max_in_flight: usize,
+    shutdown: Shutdown,
+    shutdown_timeout: Duration,
+}
+
+impl Default for StdioHandler {
+    fn default() -> Self {
+        Self {
+            dispatcher: Dispatcher::default(),
+            max_in_flight: DEFAULT_MAX_IN_FLIGHT,
+            shutdown: Shutdown::default(),
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+        }
+    }
+}
 
 impl StdioHandler {
-    pub async fn run() -> Result<()> {
-        let stdin = tokio::io::stdin();
-        let mut stdout = tokio::io::stdout();
-        let mut reader = BufReader::new(stdin);
-        let mut line = String::new();
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-        loop {
-            line.clear();
-            match reader.read_line(&mut line).await {
-                Ok(0) => {
-                    tracing::info!("EOF reached, shutting down");
-                    break;
-                }
-                Ok(_) => {
-                    if line.trim().is_empty() {
-                        continue;
-                    }
+    pub fn with_dispatcher(mut self, dispatcher: Dispatcher) -> Self {
+        self.dispatcher = dispatcher;
+        self
+    }
+
+    /// Caps how many requests are handled concurrently. Further requests
+    /// wait for a slot; notifications are handled right away.
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight.max(1);
+        self
+    }
+
+    /// Stops reading input once `shutdown` fires, then gives in-flight
+    /// requests the shutdown timeout to finish before closing the output.
+    pub fn with_shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    pub fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
 
-                    match serde_json::from_str::<Value>(&line) {
-                        Ok(message) => {
-                            if let Some(method) = message.get("method").and_then(|v| v.as_str()) {
-                                match method {
-                                    "initialized" => {
-                                        tracing::info!("Client initialized");
-                                    }
-                                    _ => {
-                                        if message.get("id").is_some() {
-                                            let response = Self::handle_request(message).await;
-                                            let response_json = serde_json::to_string(&response)?;
-                                            stdout.write_all(response_json.as_bytes()).await?;
-                                            stdout.write_all(b"\n").await?;
-                                            stdout.flush().await?;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            tracing::error!("Failed to parse request: {} - Input: {}", e, line.trim());
-                        }
-                    }
+    pub async fn run(self) -> Result<()> {
+        self.serve(tokio::io::stdin(), tokio::io::stdout()).await?;
+        tracing::info!("STDIO handler shutting down");
+        Ok(())
+    }
+
+    /// Serves newline-delimited JSON-RPC over `reader` and `writer` until
+    /// `reader` reaches EOF or shutdown is triggered. Besides stdio, this
+    /// serves each client of the Unix socket transport.
+    ///
+    /// Each message is handled on its own task, so responses are written in
+    /// completion order rather than arrival order; clients match them by id.
+    /// All output goes through a single writer task so lines never interleave.
+    pub async fn serve<R, W>(self, reader: R, writer: W) -> Result<()>
+    where
+        R: AsyncRead + Unpin,
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        let (outbound, receiver) = mpsc::channel(OUTBOUND_CAPACITY);
+        let writer_task = tokio::spawn(Self::write_messages(writer, receiver));
+
+        let lines = BufReader::new(reader).lines();
+        let incoming = Box::pin(stream::unfold(lines, |mut lines| async move {
+            match lines.next_line().await {
+                Ok(Some(line)) => Some((line, lines)),
+                Ok(None) => {
+                    tracing::info!("EOF reached, shutting down");
+                    None
                 }
                 Err(e) => {
-                    tracing::error!("Error reading from stdin: {}", e);
-                    break;
+                    tracing::error!("Error reading input: {}", e);
+                    None
                 }
             }
-        }
+        }));
+
+        serve_connection(
+            self.dispatcher,
+            self.max_in_flight,
+            incoming,
+            outbound,
+            None,
+            &self.shutdown,
+            self.shutdown_timeout,
+        )
+        .await?;
+        writer_task.await??;
 
-        tracing::info!("STDIO handler shutting down");
         Ok(())
     }
 
-    async fn handle_request(request: Value) -> Value {
-        let method = request.get("method").and_then(|v| v.as_str()).unwrap_or("");
-        let id = request.get("id").cloned().unwrap_or(serde_json::json!(0));
-        let params = request.get("params").cloned();
-
-        match method {
-            "initialize" => McpHandlers::handle_initialize(id, params).await,
-            "tools/list" => McpHandlers::handle_tools_list(id).await,
-            "tools/call" => McpHandlers::handle_tools_call(id, params).await,
-            "resources/list" => McpHandlers::handle_resources_list(id).await,
-            "resources/read" => McpHandlers::handle_resources_read(id, params).await,
-            "prompts/list" => McpHandlers::handle_prompts_list(id).await,
-            "prompts/get" => McpHandlers::handle_prompts_get(id, params).await,
-            _ => {
-                let error_response =
-                    McpResponse::<()>::error(id, McpError::method_not_found("Method not found"));
-                serde_json::to_value(error_response).unwrap_or_else(|_| serde_json::json!({}))
-            }
+    async fn write_messages<W>(mut writer: W, mut receiver: mpsc::Receiver<Value>) -> Result<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        while let Some(message) = receiver.recv().await {
+            let message_json = serde_json::to_string(&message)?;
+            writer.write_all(message_json.as_bytes()).await?;
+            writer.write_all(b"\n").await?;
+            writer.flush().await?;
         }
+
+        Ok(())
     }
 }
@@ -0,0 +1,224 @@
+use crate::config::{ServerConfig, DEFAULT_MAX_IN_FLIGHT, DEFAULT_SOCKET_MODE};
+use crate::handlers::{dispatcher::Dispatcher, stdio::StdioHandler};
+use crate::shutdown::{self, Drain, DrainGuard, Shutdown, DEFAULT_SHUTDOWN_TIMEOUT};
+use anyhow::{Context, Result};
+use std::future::Future;
+use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::net::{UnixListener, UnixStream};
+
+/// Unix domain socket transport: serves newline-delimited JSON-RPC, framed
+/// exactly as on stdio, to any number of concurrent local clients. Each
+/// connection gets its own session.
+pub struct UnixHandler {
+    dispatcher: Dispatcher,
+    max_in_flight: usize,
+    socket_mode: u32,
+    shutdown: Shutdown,
+    shutdown_timeout: Duration,
+}
+
+impl Default for UnixHandler {
+    fn default() -> Self {
+        Self {
+            dispatcher: Dispatcher::default(),
+            max_in_flight: DEFAULT_MAX_IN_FLIGHT,
+            socket_mode: DEFAULT_SOCKET_MODE,
+            shutdown: Shutdown::default(),
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+        }
+    }
+}
+
+impl UnixHandler {
+    pub fn new(config: ServerConfig) -> Self {
+        Self::default()
+            .with_max_in_flight(config.max_in_flight)
+            .with_socket_mode(config.socket_mode)
+            .with_shutdown_timeout(config.shutdown_timeout)
+    }
+
+    pub fn with_dispatcher(mut self, dispatcher: Dispatcher) -> Self {
+        self.dispatcher = dispatcher;
+        self
+    }
+
+    /// Caps how many messages are handled concurrently per connection.
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight.max(1);
+        self
+    }
+
+    /// Permissions for the socket file, e.g. `0o660` to let a group connect.
+    pub fn with_socket_mode(mut self, socket_mode: u32) -> Self {
+        self.socket_mode = socket_mode;
+        self
+    }
+
+    /// The shutdown that [`UnixHandler::run`] serves until.
+    pub fn with_shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// How long connected clients get to finish in-flight requests once the
+    /// server stops.
+    pub fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    /// Serves on `socket_path` until the configured shutdown fires.
+    pub async fn run(self, socket_path: &Path) -> Result<()> {
+        let shutdown = self.shutdown.clone();
+        self.run_until(socket_path, async move { shutdown.wait().await })
+            .await
+    }
+
+    /// Serves on `socket_path` until `shutdown` completes, then stops reading
+    /// from connected clients, waits up to the shutdown timeout for their
+    /// in-flight requests and removes the socket file. A stale socket left
+    /// behind by a crashed server is replaced, but a live one is never
+    /// stolen.
+    pub async fn run_until<F>(self, socket_path: &Path, shutdown: F) -> Result<()>
+    where
+        F: Future<Output = ()>,
+    {
+        remove_stale_socket(socket_path).await?;
+
+        let listener = bind_with_mode(socket_path, self.socket_mode)?;
+        let _cleanup = SocketCleanup(socket_path.to_path_buf());
+
+        tracing::info!(
+            "Unix socket server listening on {} (mode {:o})",
+            socket_path.display(),
+            self.socket_mode
+        );
+
+        let (trigger, clients_shutdown) = shutdown::channel();
+        let clients = Drain::new();
+
+        tokio::pin!(shutdown);
+        loop {
+            tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => {
+                        self.spawn_client(stream, clients_shutdown.clone(), clients.guard())
+                    }
+                    Err(e) => tracing::warn!("Failed to accept connection: {}", e),
+                },
+                _ = &mut shutdown => break,
+            }
+        }
+
+        tracing::info!("Unix socket server shutting down");
+        drop(listener);
+        trigger.trigger();
+
+        if tokio::time::timeout(self.shutdown_timeout, clients.wait())
+            .await
+            .is_err()
+        {
+            tracing::warn!("Shutdown deadline elapsed, closing remaining connections");
+        }
+
+        Ok(())
+    }
+
+    fn spawn_client(&self, stream: UnixStream, shutdown: Shutdown, guard: DrainGuard) {
+        let handler = StdioHandler::new()
+            .with_dispatcher(self.dispatcher.clone())
+            .with_max_in_flight(self.max_in_flight)
+            .with_shutdown(shutdown)
+            .with_shutdown_timeout(self.shutdown_timeout);
+
+        tokio::spawn(async move {
+            let _guard = guard;
+            tracing::info!("Unix socket client connected");
+            let (reader, writer) = stream.into_split();
+            if let Err(e) = handler.serve(reader, writer).await {
+                tracing::warn!("Unix socket connection failed: {}", e);
+            }
+            tracing::info!("Unix socket client disconnected");
+        });
+    }
+}
+
+/// Removes a socket file left behind by a server that is no longer
+/// listening. Refuses to touch anything that isn't a socket, or a socket
+/// another server is still accepting on.
+async fn remove_stale_socket(socket_path: &Path) -> Result<()> {
+    let Ok(metadata) = std::fs::symlink_metadata(socket_path) else {
+        return Ok(());
+    };
+
+    if !metadata.file_type().is_socket() {
+        anyhow::bail!("{} exists and is not a socket", socket_path.display());
+    }
+    if UnixStream::connect(socket_path).await.is_ok() {
+        anyhow::bail!("{} is already in use", socket_path.display());
+    }
+
+    tracing::info!("Removing stale socket {}", socket_path.display());
+    std::fs::remove_file(socket_path)?;
+    Ok(())
+}
+
+/// Binds a socket at `socket_path` that already has permissions `mode` when
+/// it appears there. It is created in a private directory beside the target
+/// and hard-linked into place once its mode is set, so it is never
+/// reachable with the umask's looser permissions, and an existing file at
+/// `socket_path` is never replaced.
+fn bind_with_mode(socket_path: &Path, mode: u32) -> Result<UnixListener> {
+    let file_name = socket_path
+        .file_name()
+        .with_context(|| format!("{} is not a file path", socket_path.display()))?;
+    let parent = socket_path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+
+    let staging = parent.join(format!(
+        ".{}.{}",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+    std::fs::DirBuilder::new()
+        .mode(0o700)
+        .create(&staging)
+        .with_context(|| format!("Failed to create {}", staging.display()))?;
+    let _staging = StagingCleanup(staging.clone());
+
+    let staged = staging.join("socket");
+    let listener = UnixListener::bind(&staged)
+        .with_context(|| format!("Failed to bind {}", socket_path.display()))?;
+    std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(mode))
+        .with_context(|| format!("Failed to set permissions on {}", socket_path.display()))?;
+    std::fs::hard_link(&staged, socket_path)
+        .with_context(|| format!("Failed to bind {}", socket_path.display()))?;
+
+    Ok(listener)
+}
+
+/// Deletes the private directory a socket was created in.
+struct StagingCleanup(PathBuf);
+
+impl Drop for StagingCleanup {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.0) {
+            tracing::warn!("Failed to remove {}: {}", self.0.display(), e);
+        }
+    }
+}
+
+/// Deletes the socket file when the server stops, however it stops.
+struct SocketCleanup(PathBuf);
+
+impl Drop for SocketCleanup {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.0) {
+            tracing::warn!("Failed to remove socket {}: {}", self.0.display(), e);
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use crate::{
+    auth::AuthManager,
+    config::{ServerConfig, SERVER_VERSION},
+    handlers::{
+        connection::{serve_connection, OUTBOUND_CAPACITY},
+        dispatcher::Dispatcher,
+        origin::OriginPolicy,
+    },
+    models::TokenInfo,
+    shutdown::{Drain, DrainGuard, Shutdown},
+};
+use anyhow::Result;
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::get,
+    Router,
+};
+use chrono::Utc;
+use futures::{stream, SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tower_http::trace::TraceLayer;
+
+/// WebSocket transport: each connection to `/mcp` is a long-lived session
+/// carrying one JSON-RPC message (or batch) per text frame in both
+/// directions, including server-initiated notifications.
+#[derive(Clone)]
+pub struct WsHandler {
+    auth: AuthManager,
+    dispatcher: Dispatcher,
+    origins: OriginPolicy,
+    max_in_flight: usize,
+    shutdown: Shutdown,
+    shutdown_timeout: Duration,
+    /// Held by every open socket so `run` can wait for them on shutdown.
+    connections: Option<DrainGuard>,
+}
+
+impl WsHandler {
+    pub fn new(config: ServerConfig) -> Self {
+        Self {
+            auth: AuthManager::from_config(&config),
+            dispatcher: Dispatcher::new().with_scope_policy(config.scope_policy.clone()),
+            origins: config.allowed_origins.clone(),
+            max_in_flight: config.max_in_flight,
+            shutdown: Shutdown::default(),
+            shutdown_timeout: config.shutdown_timeout,
+            connections: None,
+        }
+    }
+
+    /// Serves `dispatcher` instead of the built-in one. The configured scope
+    /// policy carries over unless `dispatcher` brings its own.
+    pub fn with_dispatcher(mut self, dispatcher: Dispatcher) -> Self {
+        self.dispatcher = if dispatcher.scope_policy().is_empty() {
+            dispatcher.with_scope_policy(self.dispatcher.scope_policy().clone())
+        } else {
+            dispatcher
+        };
+        self
+    }
+
+    /// Stops accepting connections and messages once `shutdown` fires, then
+    /// gives open sockets `shutdown_timeout` to answer in-flight requests.
+    pub fn with_shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    pub fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    pub async fn run(mut self, host: &str, port: u16) -> Result<()> {
+        let shutdown = self.shutdown.clone();
+        let shutdown_timeout = self.shutdown_timeout;
+        let drain = Drain::new();
+        self.connections = Some(drain.guard());
+
+        let app = Router::new()
+            .route("/mcp", get(Self::handle_upgrade))
+            .route("/health", get(Self::health_check))
+            .layer(TraceLayer::new_for_http())
+            .with_state(self);
+
+        let addr = format!("{}:{}", host, port);
+        let listener = tokio::net::TcpListener::bind(&addr).await?;
+
+        tracing::info!("WebSocket server listening on ws://{}/mcp", addr);
+
+        let signal = shutdown.clone();
+        let server = async move {
+            axum::serve(listener, app)
+                .with_graceful_shutdown(async move { signal.wait().await })
+                .await?;
+            // Upgraded sockets outlive the HTTP connections axum tracks.
+            drain.wait().await;
+            Ok::<_, anyhow::Error>(())
+        };
+
+        match shutdown.with_deadline(shutdown_timeout, server).await {
+            Some(result) => result?,
+            None => tracing::warn!("Shutdown deadline elapsed, closing remaining WebSockets"),
+        }
+
+        tracing::info!("WebSocket server stopped");
+        Ok(())
+    }
+
+    async fn health_check() -> Json<Value> {
+        Json(json!({
+            "status": "healthy",
+            "timestamp": Utc::now().to_rfc3339(),
+            "version": SERVER_VERSION
+        }))
+    }
+
+    /// Checks the upgrade request's origin and authenticates it, then hands
+    /// the socket to [`WsHandler::serve_socket`]. CORS doesn't cover
+    /// WebSockets, so without the origin check any web page could connect.
+    async fn handle_upgrade(
+        State(handler): State<WsHandler>,
+        headers: HeaderMap,
+        upgrade: WebSocketUpgrade,
+    ) -> Response {
+        if !handler.origins.allows_request(&headers) {
+            tracing::warn!(
+                "Rejected WebSocket upgrade from origin {:?}",
+                headers.get(header::ORIGIN)
+            );
+            return (StatusCode::FORBIDDEN, "Origin not allowed").into_response();
+        }
+
+        let principal = match handler.auth.authenticate(&headers).await {
+            Ok(principal) => principal,
+            Err(e) => return handler.auth.reject(e),
+        };
+
+        let guard = handler.connections.clone();
+        upgrade.on_upgrade(move |socket| async move {
+            let _guard = guard;
+            if let Err(e) = handler.serve_socket(socket, principal).await {
+                tracing::warn!("WebSocket connection failed: {}", e);
+            }
+        })
+    }
+
+    /// Serves one upgraded connection on behalf of `principal`, whose scopes
+    /// apply for the lifetime of the socket.
+    async fn serve_socket(self, socket: WebSocket, principal: Option<TokenInfo>) -> Result<()> {
+        tracing::info!("WebSocket client connected");
+        let (mut sink, frames) = socket.split();
+        let (outbound, mut receiver) = mpsc::channel::<Value>(OUTBOUND_CAPACITY);
+
+        let writer_task = tokio::spawn(async move {
+            while let Some(message) = receiver.recv().await {
+                sink.send(Message::Text(message.to_string())).await?;
+            }
+            // The client may already be gone, in which case there is nobody
+            // left to tell.
+            let _ = sink.close().await;
+            Ok::<_, axum::Error>(())
+        });
+
+        // Pings are answered by axum itself; only data frames carry messages.
+        let incoming = Box::pin(stream::unfold(frames, |mut frames| async move {
+            loop {
+                match frames.next().await {
+                    Some(Ok(Message::Text(text))) => return Some((text, frames)),
+                    Some(Ok(Message::Binary(bytes))) => {
+                        return Some((String::from_utf8_lossy(&bytes).into_owned(), frames))
+                    }
+                    Some(Ok(Message::Ping(_) | Message::Pong(_))) => {}
+                    Some(Ok(Message::Close(_))) | None => return None,
+                    Some(Err(e)) => {
+                        tracing::warn!("Error reading from WebSocket: {}", e);
+                        return None;
+                    }
+                }
+            }
+        }));
+
+        serve_connection(
+            self.dispatcher,
+            self.max_in_flight,
+            incoming,
+            outbound,
+            principal,
+            &self.shutdown,
+            self.shutdown_timeout,
+        )
+        .await?;
+        writer_task.await??;
+
+        tracing::info!("WebSocket client disconnected");
+        Ok(())
+    }
+}
@@ -1,5 +1,14 @@
 pub mod auth;
+pub mod completion;
 pub mod config;
 pub mod handlers;
+pub mod logging;
 pub mod models;
+pub mod pagination;
+pub mod protocol;
+pub mod registry;
+pub mod resources;
+pub mod session;
+pub mod shutdown;
+mod sync;
 pub mod tools;
@@ -0,0 +1,259 @@
+use crate::session::Notifier;
+use crate::sync::lock;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, Weak};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Every session that has asked for log messages via `logging/setLevel`.
+/// Entries disappear when their session is dropped.
+static SUBSCRIPTIONS: Mutex<Vec<Weak<LogSubscription>>> = Mutex::new(Vec::new());
+
+/// Span field carrying the id of the session an event belongs to, as set by
+/// [`Session::span`](crate::session::Session::span).
+const SESSION_FIELD: &str = "mcp_session";
+
+/// MCP log severities, from least to most severe (RFC 5424 levels).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Notice,
+    Warning,
+    Error,
+    Critical,
+    Alert,
+    Emergency,
+}
+
+impl LogLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Notice => "notice",
+            LogLevel::Warning => "warning",
+            LogLevel::Error => "error",
+            LogLevel::Critical => "critical",
+            LogLevel::Alert => "alert",
+            LogLevel::Emergency => "emergency",
+        }
+    }
+}
+
+impl From<&Level> for LogLevel {
+    fn from(level: &Level) -> Self {
+        match *level {
+            Level::ERROR => LogLevel::Error,
+            Level::WARN => LogLevel::Warning,
+            Level::INFO => LogLevel::Info,
+            _ => LogLevel::Debug,
+        }
+    }
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "debug" => Ok(LogLevel::Debug),
+            "info" => Ok(LogLevel::Info),
+            "notice" => Ok(LogLevel::Notice),
+            "warning" => Ok(LogLevel::Warning),
+            "error" => Ok(LogLevel::Error),
+            "critical" => Ok(LogLevel::Critical),
+            "alert" => Ok(LogLevel::Alert),
+            "emergency" => Ok(LogLevel::Emergency),
+            _ => Err(format!("Invalid log level: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A session's request to receive its log messages at or above `level`.
+pub struct LogSubscription {
+    session: u64,
+    level: Mutex<LogLevel>,
+    notifier: Notifier,
+}
+
+impl LogSubscription {
+    /// Registers a subscription for the session with id `session`. Messages
+    /// are delivered for as long as the returned handle is alive.
+    pub fn register(session: u64, level: LogLevel, notifier: Notifier) -> Arc<Self> {
+        let subscription = Arc::new(Self {
+            session,
+            level: Mutex::new(level),
+            notifier,
+        });
+
+        let mut subscriptions = lock(&SUBSCRIPTIONS);
+        subscriptions.retain(|s| s.strong_count() > 0);
+        subscriptions.push(Arc::downgrade(&subscription));
+
+        subscription
+    }
+
+    pub fn level(&self) -> LogLevel {
+        *lock(&self.level)
+    }
+
+    pub fn set_level(&self, level: LogLevel) {
+        *lock(&self.level) = level;
+    }
+}
+
+/// `tracing_subscriber` layer that forwards events to subscribed sessions as
+/// `notifications/message`.
+///
+/// Each event goes only to the session whose [span](crate::session::Session::span)
+/// it was raised in, so clients never see each other's requests. Events
+/// raised outside of any session, such as authentication failures, are not
+/// forwarded at all.
+///
+/// Install it next to the usual formatting layer:
+///
+/// ```no_run
+/// use time_mcp_server::logging::McpLogLayer;
+/// use tracing_subscriber::prelude::*;
+///
+/// tracing_subscriber::registry()
+///     .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+///     .with(McpLogLayer::new())
+///     .init();
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct McpLogLayer;
+
+impl McpLogLayer {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<S> Layer<S> for McpLogLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut visitor = SessionVisitor::default();
+        attrs.record(&mut visitor);
+
+        if let (Some(session), Some(span)) = (visitor.0, ctx.span(id)) {
+            span.extensions_mut().insert(SessionScope(session));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let Some(session) = ctx.event_scope(event).and_then(|mut scope| {
+            scope.find_map(|span| span.extensions().get::<SessionScope>().map(|s| s.0))
+        }) else {
+            return;
+        };
+
+        let metadata = event.metadata();
+        let level = LogLevel::from(metadata.level());
+
+        // Collect first so the lock isn't held while notifying.
+        let recipients: Vec<Arc<LogSubscription>> = lock(&SUBSCRIPTIONS)
+            .iter()
+            .filter_map(Weak::upgrade)
+            .filter(|s| s.session == session && level >= s.level())
+            .collect();
+
+        if recipients.is_empty() {
+            return;
+        }
+
+        let mut visitor = JsonVisitor::default();
+        event.record(&mut visitor);
+
+        let params = json!({
+            "level": level,
+            "logger": metadata.target(),
+            "data": visitor.into_data()
+        });
+
+        for recipient in recipients {
+            // Never log from here: it would re-enter this layer.
+            recipient
+                .notifier
+                .try_notify("notifications/message", params.clone());
+        }
+    }
+}
+
+/// Marks a span, and everything raised inside it, as belonging to a session.
+struct SessionScope(u64);
+
+/// Picks the session id out of a span's fields.
+#[derive(Default)]
+struct SessionVisitor(Option<u64>);
+
+impl Visit for SessionVisitor {
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        if field.name() == SESSION_FIELD {
+            self.0 = Some(value);
+        }
+    }
+
+    fn record_debug(&mut self, _field: &Field, _value: &dyn fmt::Debug) {}
+}
+
+/// Collects event fields into JSON. Events with only a message become a
+/// plain string; structured fields produce an object.
+#[derive(Default)]
+struct JsonVisitor {
+    fields: Map<String, Value>,
+}
+
+impl JsonVisitor {
+    fn into_data(mut self) -> Value {
+        match self.fields.len() {
+            1 if self.fields.contains_key("message") => {
+                self.fields.remove("message").unwrap_or(Value::Null)
+            }
+            _ => Value::Object(self.fields),
+        }
+    }
+}
+
+impl Visit for JsonVisitor {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.fields.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.fields.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.fields.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.fields.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.fields.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.fields
+            .insert(field.name().to_string(), json!(format!("{:?}", value)));
+    }
+}
@@ -1,20 +1,30 @@
-mod auth;
-mod config;
-mod handlers;
-mod models;
-mod tools;
-
 use anyhow::Result;
 use clap::{Arg, Command};
-use config::{ServerConfig, TransportType};
-use handlers::{http::HttpHandler, stdio::StdioHandler};
+use time_mcp_server::config::{ServerConfig, TransportType};
+#[cfg(unix)]
+use time_mcp_server::handlers::unix::UnixHandler;
+use time_mcp_server::handlers::{http::HttpHandler, stdio::StdioHandler, ws::WsHandler};
+use time_mcp_server::logging::McpLogLayer;
+use time_mcp_server::shutdown;
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::prelude::*;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt()
-        .with_writer(std::io::stderr)
-        .with_ansi(false)
-        .with_max_level(tracing::Level::INFO)
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(std::io::stderr)
+                .with_ansi(false)
+                .with_filter(LevelFilter::INFO),
+        )
+        .with(
+            McpLogLayer::new().with_filter(
+                Targets::new()
+                    .with_target("time_mcp_server", LevelFilter::DEBUG)
+                    .with_default(LevelFilter::INFO),
+            ),
+        )
         .init();
 
     let matches = Command::new("time-mcp-server")
@@ -24,32 +34,98 @@ async fn main() -> Result<()> {
             Arg::new("transport")
                 .long("transport")
                 .value_name("TYPE")
-                .help("Transport type: stdio or http")
+                .help("Transport type: stdio, http, ws or unix")
                 .default_value("stdio")
-                .value_parser(["stdio", "http"]),
+                .value_parser(["stdio", "http", "ws", "unix"]),
         )
         .arg(
             Arg::new("host")
                 .long("host")
                 .value_name("HOST")
-                .help("Host to bind HTTP server to")
+                .help("Host to bind the HTTP or WebSocket server to")
                 .default_value("localhost"),
         )
         .arg(
             Arg::new("port")
                 .long("port")
                 .value_name("PORT")
-                .help("Port to bind HTTP server to")
+                .help("Port to bind the HTTP or WebSocket server to")
                 .default_value("8080"),
         )
+        .arg(
+            Arg::new("max-in-flight")
+                .long("max-in-flight")
+                .value_name("N")
+                .help("Maximum number of concurrent requests per connection")
+                .default_value("32"),
+        )
+        .arg(
+            Arg::new("socket-path")
+                .long("socket-path")
+                .value_name("PATH")
+                .help("Path of the Unix socket to listen on (unix transport)"),
+        )
+        .arg(
+            Arg::new("socket-mode")
+                .long("socket-mode")
+                .value_name("MODE")
+                .help("Octal permissions of the Unix socket file")
+                .default_value("600"),
+        )
+        .arg(
+            Arg::new("shutdown-timeout")
+                .long("shutdown-timeout")
+                .value_name("SECONDS")
+                .help("How long to wait for in-flight requests on SIGTERM or SIGINT")
+                .default_value("30"),
+        )
+        .arg(
+            Arg::new("allowed-origins")
+                .long("allowed-origins")
+                .value_name("ORIGINS")
+                .help("Comma-separated browser origins allowed besides localhost, or *"),
+        )
+        .arg(
+            Arg::new("max-sessions")
+                .long("max-sessions")
+                .value_name("N")
+                .help("Maximum number of open HTTP sessions")
+                .default_value("1024"),
+        )
+        .arg(
+            Arg::new("session-timeout")
+                .long("session-timeout")
+                .value_name("SECONDS")
+                .help("Close HTTP sessions idle for this long")
+                .default_value("1800"),
+        )
         .get_matches();
 
     let config = ServerConfig::from_matches(&matches)?;
+    if config.auth_enabled
+        && config.jwt.is_none()
+        && config.introspection.is_none()
+        && config.api_keys.is_none()
+    {
+        tracing::warn!(
+            "OAUTH_ENABLED is set but no token validator is configured; set OAUTH_JWT_SECRET, OAUTH_JWT_KEY_FILE, OAUTH_INTROSPECTION_URL or API_KEYS_FILE"
+        );
+    }
+    #[cfg(unix)]
+    if let Some(api_keys) = &config.api_keys {
+        api_keys.reload_on_hangup();
+    }
+    let shutdown = shutdown::on_signal();
 
-    match config.transport.clone() {
+    let result = match config.transport.clone() {
         TransportType::Stdio => {
             tracing::info!("Starting Time MCP Server with STDIO transport");
-            StdioHandler::run().await
+            StdioHandler::new()
+                .with_max_in_flight(config.max_in_flight)
+                .with_shutdown(shutdown)
+                .with_shutdown_timeout(config.shutdown_timeout)
+                .run()
+                .await
         }
         TransportType::Http { host, port } => {
             tracing::info!(
@@ -57,7 +133,43 @@ async fn main() -> Result<()> {
                 host,
                 port
             );
-            HttpHandler::new(config).run(&host, port).await
+            HttpHandler::new(config)
+                .with_shutdown(shutdown)
+                .run(&host, port)
+                .await
         }
-    }
+        TransportType::WebSocket { host, port } => {
+            tracing::info!(
+                "Starting Time MCP Server with WebSocket transport on {}:{}",
+                host,
+                port
+            );
+            WsHandler::new(config)
+                .with_shutdown(shutdown)
+                .run(&host, port)
+                .await
+        }
+        #[cfg(unix)]
+        TransportType::Unix { socket_path } => {
+            tracing::info!(
+                "Starting Time MCP Server with Unix socket transport on {}",
+                socket_path.display()
+            );
+            UnixHandler::new(config)
+                .with_shutdown(shutdown)
+                .run(&socket_path)
+                .await
+        }
+        #[cfg(not(unix))]
+        TransportType::Unix { .. } => {
+            anyhow::bail!("The unix transport is only available on Unix platforms")
+        }
+    };
+
+    result?;
+    tracing::info!("Time MCP Server stopped");
+
+    // Stdin is read on a blocking thread that would otherwise keep the
+    // runtime alive after a signal.
+    std::process::exit(0)
 }
@@ -1,9 +1,10 @@
+use crate::tools::TimeError;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::time::SystemTime;
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenInfo {
     pub user_id: String,
     pub scopes: Vec<String>,
@@ -16,6 +17,15 @@ impl TokenInfo {
     }
 }
 
+/// The `clientInfo` a client sends with `initialize`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClientInfo {
+    pub name: String,
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
 #[derive(Deserialize)]
 pub struct McpRequest {
     pub name: Option<String>,
@@ -23,10 +33,12 @@ pub struct McpRequest {
     pub uri: Option<String>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct McpError {
     pub code: i32,
     pub message: String,
+    /// Additional information about the error, sent as the JSON-RPC `data`.
+    pub data: Option<Value>,
 }
 
 impl McpError {
@@ -34,9 +46,23 @@ impl McpError {
         Self {
             code,
             message: message.into(),
+            data: None,
         }
     }
 
+    pub fn with_data(mut self, data: Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    pub fn parse_error(message: impl Into<String>) -> Self {
+        Self::new(-32700, message)
+    }
+
+    pub fn invalid_request(message: impl Into<String>) -> Self {
+        Self::new(-32600, message)
+    }
+
     pub fn invalid_params(message: impl Into<String>) -> Self {
         Self::new(-32602, message)
     }
@@ -48,6 +74,12 @@ impl McpError {
     pub fn internal_error(message: impl Into<String>) -> Self {
         Self::new(-32603, message)
     }
+
+    /// LSP's `RequestCancelled`, for requests that were cancelled but still
+    /// need an answer.
+    pub fn request_cancelled(message: impl Into<String>) -> Self {
+        Self::new(-32800, message)
+    }
 }
 
 impl From<anyhow::Error> for McpError {
@@ -56,6 +88,12 @@ impl From<anyhow::Error> for McpError {
     }
 }
 
+impl From<TimeError> for McpError {
+    fn from(err: TimeError) -> Self {
+        McpError::new(err.code(), err.to_string())
+    }
+}
+
 pub type McpResult<T> = Result<T, McpError>;
 
 #[derive(Serialize)]
@@ -94,9 +132,13 @@ impl Serialize for McpError {
         S: serde::Serializer,
     {
         use serde::ser::SerializeStruct;
-        let mut state = serializer.serialize_struct("McpError", 2)?;
+        let len = if self.data.is_some() { 3 } else { 2 };
+        let mut state = serializer.serialize_struct("McpError", len)?;
         state.serialize_field("code", &self.code)?;
         state.serialize_field("message", &self.message)?;
+        if let Some(data) = &self.data {
+            state.serialize_field("data", data)?;
+        }
         state.end()
     }
 }
@@ -0,0 +1,61 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde_json::Value;
+
+use crate::models::{McpError, McpResult};
+
+/// Items per page for `tools/list`, `resources/list`,
+/// `resources/templates/list` and `prompts/list`.
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Prefix that marks a decoded cursor as one of ours.
+const CURSOR_PREFIX: &str = "offset:";
+
+/// One page of a list, plus the cursor for the next page if there is one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+/// Encodes a position in a list as an opaque cursor string.
+pub fn encode_cursor(offset: usize) -> String {
+    URL_SAFE_NO_PAD.encode(format!("{}{}", CURSOR_PREFIX, offset))
+}
+
+/// Decodes a cursor produced by [`encode_cursor`], or `None` if the client
+/// sent something else.
+pub fn decode_cursor(cursor: &str) -> Option<usize> {
+    let decoded = URL_SAFE_NO_PAD.decode(cursor).ok()?;
+    String::from_utf8(decoded)
+        .ok()?
+        .strip_prefix(CURSOR_PREFIX)?
+        .parse()
+        .ok()
+}
+
+/// Returns the page of `items` that starts at `offset`.
+pub fn paginate<T>(items: Vec<T>, offset: usize, page_size: usize) -> Page<T> {
+    let end = offset.saturating_add(page_size);
+    let next_cursor = (end < items.len()).then(|| encode_cursor(end));
+    let items = items.into_iter().skip(offset).take(page_size).collect();
+
+    Page { items, next_cursor }
+}
+
+/// Paginates a list method's result according to the `cursor` in `params`.
+/// Invalid cursors are rejected with -32602, as the MCP spec requires.
+pub fn paginate_request<T>(
+    items: Vec<T>,
+    params: &Option<Value>,
+    page_size: usize,
+) -> McpResult<Page<T>> {
+    let offset = match params.as_ref().and_then(|p| p.get("cursor")) {
+        None | Some(Value::Null) => 0,
+        Some(cursor) => cursor
+            .as_str()
+            .and_then(decode_cursor)
+            .ok_or_else(|| McpError::invalid_params("Invalid cursor"))?,
+    };
+
+    Ok(paginate(items, offset, page_size))
+}
@@ -0,0 +1,71 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// An MCP protocol revision this server can speak.
+///
+/// Variants are ordered oldest to newest, so feature checks can compare
+/// against the revision that introduced the feature.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ProtocolVersion {
+    V2024_11_05,
+    V2025_03_26,
+    #[default]
+    V2025_06_18,
+}
+
+impl ProtocolVersion {
+    /// Every supported revision, newest first.
+    pub const ALL: &'static [ProtocolVersion] = &[
+        ProtocolVersion::V2025_06_18,
+        ProtocolVersion::V2025_03_26,
+        ProtocolVersion::V2024_11_05,
+    ];
+
+    pub const LATEST: ProtocolVersion = ProtocolVersion::V2025_06_18;
+
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            ProtocolVersion::V2024_11_05 => "2024-11-05",
+            ProtocolVersion::V2025_03_26 => "2025-03-26",
+            ProtocolVersion::V2025_06_18 => "2025-06-18",
+        }
+    }
+
+    /// The revision to answer `initialize` with: the client's if we support
+    /// it, otherwise our latest, leaving it to the client to disconnect if
+    /// it can't speak that.
+    pub fn negotiate(requested: Option<&str>) -> Self {
+        requested
+            .and_then(|version| version.parse().ok())
+            .unwrap_or(Self::LATEST)
+    }
+
+    /// `structuredContent` in tool results and `outputSchema` in tool
+    /// definitions (2025-06-18).
+    pub fn supports_structured_output(&self) -> bool {
+        *self >= ProtocolVersion::V2025_06_18
+    }
+
+    /// The `completions` server capability (2025-03-26).
+    pub fn supports_completions_capability(&self) -> bool {
+        *self >= ProtocolVersion::V2025_03_26
+    }
+}
+
+impl FromStr for ProtocolVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|version| version.as_str() == s)
+            .ok_or_else(|| format!("Unsupported protocol version: {}", s))
+    }
+}
+
+impl fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -0,0 +1,224 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use schemars::{generate::SchemaSettings, JsonSchema};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use crate::models::ClientInfo;
+use crate::session::Notifier;
+use crate::tools::{
+    CalculateDurationTool, ConvertTimezoneTool, FormatTimeTool, GetCurrentTimeTool,
+    GetTimezoneInfoTool, ListTimezonesTool, TimeError,
+};
+
+/// A tool that can be advertised through `tools/list` and invoked through
+/// `tools/call`.
+///
+/// Implement this in a downstream crate and add it to a [`ToolRegistry`] to
+/// expose additional tools alongside the built-in time tools.
+#[async_trait]
+pub trait Tool: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn description(&self) -> &str;
+
+    /// JSON Schema describing the `arguments` object accepted by `execute`.
+    fn input_schema(&self) -> Value;
+
+    /// JSON Schema describing the result produced by `execute`, if any.
+    /// Advertised as `outputSchema` in `tools/list`.
+    fn output_schema(&self) -> Option<Value> {
+        None
+    }
+
+    /// Runs the tool. JSON objects are returned to the client as
+    /// `structuredContent` alongside a serialized text fallback.
+    async fn execute(&self, arguments: Value) -> Result<Value>;
+
+    /// Runs the tool with access to the calling request's [`ToolContext`].
+    /// `tools/call` always goes through this method; override it instead of
+    /// `execute` for tools that report progress.
+    async fn execute_with_context(
+        &self,
+        arguments: Value,
+        _context: &ToolContext,
+    ) -> Result<Value> {
+        self.execute(arguments).await
+    }
+
+    fn definition(&self) -> Value {
+        let mut definition = json!({
+            "name": self.name(),
+            "description": self.description(),
+            "inputSchema": self.input_schema()
+        });
+
+        if let Some(output_schema) = self.output_schema() {
+            definition["outputSchema"] = output_schema;
+        }
+
+        definition
+    }
+}
+
+/// Per-call information handed to [`Tool::execute_with_context`].
+#[derive(Clone, Default)]
+pub struct ToolContext {
+    pub progress: ProgressReporter,
+    /// The calling client's `clientInfo` from `initialize`, e.g. for
+    /// per-client defaults. `None` outside a session or if it sent none.
+    pub client: Option<ClientInfo>,
+}
+
+/// Sends `notifications/progress` for a request that carried a
+/// `_meta.progressToken`. Reporting is a no-op when the client did not ask
+/// for progress, so tools can report unconditionally.
+#[derive(Clone, Default)]
+pub struct ProgressReporter {
+    token: Option<Value>,
+    notifier: Notifier,
+}
+
+impl ProgressReporter {
+    pub fn new(token: Value, notifier: Notifier) -> Self {
+        Self {
+            token: Some(token),
+            notifier,
+        }
+    }
+
+    /// Whether the client asked for progress and can receive it.
+    pub fn is_enabled(&self) -> bool {
+        self.token.is_some() && self.notifier.is_connected()
+    }
+
+    /// Reports `progress` out of an optional `total`. Per the spec,
+    /// `progress` must increase with every call.
+    pub fn report(&self, progress: f64, total: Option<f64>, message: Option<&str>) {
+        let Some(token) = &self.token else {
+            return;
+        };
+
+        let mut params = json!({
+            "progressToken": token,
+            "progress": progress
+        });
+
+        if let Some(total) = total {
+            params["total"] = json!(total);
+        }
+        if let Some(message) = message {
+            params["message"] = json!(message);
+        }
+
+        self.notifier.notify("notifications/progress", params);
+    }
+}
+
+/// Ordered collection of tools, looked up by name.
+#[derive(Clone, Default)]
+pub struct ToolRegistry {
+    tools: Vec<Arc<dyn Tool>>,
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry pre-populated with the six built-in time tools.
+    pub fn with_builtin_tools() -> Self {
+        let mut registry = Self::new();
+        registry
+            .register(GetCurrentTimeTool)
+            .register(ConvertTimezoneTool)
+            .register(CalculateDurationTool)
+            .register(FormatTimeTool)
+            .register(GetTimezoneInfoTool)
+            .register(ListTimezonesTool);
+        registry
+    }
+
+    /// Adds a tool to the registry. A tool with the same name as an existing
+    /// one replaces it in place, which lets callers override built-in tools.
+    pub fn register(&mut self, tool: impl Tool + 'static) -> &mut Self {
+        let tool: Arc<dyn Tool> = Arc::new(tool);
+
+        match self.tools.iter().position(|t| t.name() == tool.name()) {
+            Some(index) => self.tools[index] = tool,
+            None => self.tools.push(tool),
+        }
+
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Tool>> {
+        self.tools.iter().find(|t| t.name() == name).cloned()
+    }
+
+    pub fn definitions(&self) -> Vec<Value> {
+        self.tools.iter().map(|t| t.definition()).collect()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.tools.iter().map(|t| t.name())
+    }
+
+    pub fn len(&self) -> usize {
+        self.tools.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty()
+    }
+}
+
+/// Generates the JSON Schema for `T` in the self-contained form MCP clients
+/// expect for `inputSchema`: subschemas inlined, no `$schema` or `title`.
+pub fn schema_for<T: JsonSchema>() -> Value {
+    generate_schema::<T>(SchemaSettings::draft2020_12())
+}
+
+/// Like [`schema_for`], but describes how `T` serializes rather than what it
+/// accepts, for use as an `outputSchema`.
+pub fn output_schema_for<T: JsonSchema>() -> Value {
+    generate_schema::<T>(SchemaSettings::draft2020_12().for_serialize())
+}
+
+fn generate_schema<T: JsonSchema>(settings: SchemaSettings) -> Value {
+    let generator = settings
+        .with(|settings| {
+            settings.inline_subschemas = true;
+            settings.meta_schema = None;
+        })
+        .into_generator();
+
+    let mut schema = generator.into_root_schema_for::<T>().to_value();
+    if let Some(object) = schema.as_object_mut() {
+        object.remove("title");
+    }
+    schema
+}
+
+/// Deserializes tool `arguments` into `T`, treating missing arguments as an
+/// empty object. Failures are reported as [`TimeError::InvalidArgument`].
+pub fn parse_arguments<T: DeserializeOwned>(arguments: Value) -> Result<T> {
+    let arguments = if arguments.is_null() {
+        json!({})
+    } else {
+        arguments
+    };
+
+    serde_json::from_value(arguments).map_err(|e| {
+        let message = e.to_string();
+        let message = match message
+            .strip_prefix("missing field `")
+            .and_then(|rest| rest.strip_suffix('`'))
+        {
+            Some(field) => format!("{} required", field),
+            None => message,
+        };
+        TimeError::InvalidArgument(message).into()
+    })
+}
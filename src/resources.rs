@@ -0,0 +1,475 @@
+use crate::models::{McpError, McpResult};
+use crate::pagination;
+use crate::tools::{TimeError, DEFAULT_TIMEZONE_PAGE_SIZE};
+use chrono::{DateTime, Datelike, NaiveDate, Offset, TimeZone, Timelike, Utc};
+use chrono_tz::{OffsetComponents, Tz, TZ_VARIANTS};
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+pub const TIMEZONES_URI: &str = "time://timezones";
+pub const FORMATS_URI: &str = "time://formats";
+
+pub const ZONE_TEMPLATE: &str = "time://zone/{tz}";
+pub const NOW_TEMPLATE: &str = "time://now/{tz}{?cadence}";
+pub const TRANSITIONS_TEMPLATE: &str = "time://zone/{tz}/transitions/{year}";
+pub const CALENDAR_TEMPLATE: &str = "time://calendar/{year}/{month}";
+
+/// URIs the server used before adopting the `time://` scheme, still
+/// accepted by `resources/read`.
+const LEGACY_TIMEZONES_URI: &str = "timezone_database";
+const LEGACY_FORMATS_URI: &str = "time_formats";
+
+/// How often a subscribed resource reports `notifications/resources/updated`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Cadence {
+    /// At the start of every minute.
+    #[default]
+    Minute,
+    /// At the start of every local hour.
+    Hour,
+    /// Whenever the zone's UTC offset changes, e.g. at DST transitions.
+    Transition,
+}
+
+impl Cadence {
+    pub const NAMES: &'static [&'static str] = &["minute", "hour", "transition"];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Cadence::Minute => "minute",
+            Cadence::Hour => "hour",
+            Cadence::Transition => "transition",
+        }
+    }
+
+    /// The first tick strictly after `after`, or `None` if there will be no
+    /// more (a zone without upcoming transitions).
+    pub fn next_tick(&self, tz: Tz, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let after = after.timestamp();
+        let next = match self {
+            Cadence::Minute => after - after.rem_euclid(60) + 60,
+            Cadence::Hour => {
+                // Local hours don't always align with UTC hours (e.g. +05:30),
+                // so step minute boundaries until the local minute is zero.
+                let mut next = after - after.rem_euclid(60) + 60;
+                while DateTime::from_timestamp(next, 0)?
+                    .with_timezone(&tz)
+                    .minute()
+                    != 0
+                {
+                    next += 60;
+                }
+                next
+            }
+            Cadence::Transition => next_transition(tz, after, after + TRANSITION_HORIZON)?,
+        };
+
+        DateTime::from_timestamp(next, 0)
+    }
+}
+
+impl FromStr for Cadence {
+    type Err = McpError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "minute" => Ok(Cadence::Minute),
+            "hour" => Ok(Cadence::Hour),
+            "transition" => Ok(Cadence::Transition),
+            _ => Err(McpError::invalid_params(format!("Invalid cadence: {}", s))),
+        }
+    }
+}
+
+/// How far ahead to look for the next offset transition: a little over a
+/// year, so zones that change once a year are always covered.
+const TRANSITION_HORIZON: i64 = 400 * 24 * 3600;
+
+/// A readable `time://` resource, parsed from its URI.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimeResource {
+    /// One page of the timezone database, starting at the given offset.
+    Timezones(usize),
+    Formats,
+    Zone(Tz),
+    Now(Tz, Cadence),
+    Transitions(Tz, i32),
+    Calendar(i32, u32),
+}
+
+impl TimeResource {
+    /// Parses a concrete resource URI. Timezone names may contain a literal
+    /// `/` or its percent-encoded form, so both `time://zone/Europe/Paris`
+    /// and `time://zone/Europe%2FParis` work.
+    pub fn parse(uri: &str) -> McpResult<Self> {
+        match uri {
+            TIMEZONES_URI | LEGACY_TIMEZONES_URI => return Ok(TimeResource::Timezones(0)),
+            FORMATS_URI | LEGACY_FORMATS_URI => return Ok(TimeResource::Formats),
+            _ => {}
+        }
+
+        if let Some(query) = uri.strip_prefix("time://timezones?") {
+            let offset = query
+                .strip_prefix("cursor=")
+                .ok_or_else(|| McpError::invalid_params("Unknown query parameter"))
+                .and_then(|cursor| {
+                    pagination::decode_cursor(cursor)
+                        .ok_or_else(|| McpError::invalid_params("Invalid cursor"))
+                })?;
+            return Ok(TimeResource::Timezones(offset));
+        }
+
+        if let Some(rest) = uri.strip_prefix("time://now/") {
+            let (tz, query) = rest.split_once('?').unwrap_or((rest, ""));
+            let cadence = match query.strip_prefix("cadence=") {
+                Some(cadence) => cadence.parse()?,
+                None if query.is_empty() => Cadence::default(),
+                None => return Err(McpError::invalid_params("Unknown query parameter")),
+            };
+            return Ok(TimeResource::Now(
+                parse_timezone(&percent_decode(tz))?,
+                cadence,
+            ));
+        }
+
+        if let Some(rest) = uri.strip_prefix("time://zone/") {
+            let rest = percent_decode(rest);
+            return match rest.rsplit_once("/transitions/") {
+                Some((tz, year)) => Ok(TimeResource::Transitions(
+                    parse_timezone(tz)?,
+                    parse_year(year)?,
+                )),
+                None => Ok(TimeResource::Zone(parse_timezone(&rest)?)),
+            };
+        }
+
+        if let Some(rest) = uri.strip_prefix("time://calendar/") {
+            let (year, month) = rest.split_once('/').ok_or_else(|| {
+                McpError::invalid_params("Calendar URI requires a year and month")
+            })?;
+            return Ok(TimeResource::Calendar(
+                parse_year(year)?,
+                parse_month(month)?,
+            ));
+        }
+
+        Err(McpError::invalid_params("Unknown resource"))
+    }
+
+    /// The canonical URI of the resource: the `time://` form, with literal
+    /// `/` in timezone names and the default cadence left out. Every URI
+    /// [`TimeResource::parse`] accepts for a resource maps to this one.
+    pub fn uri(&self) -> String {
+        match self {
+            TimeResource::Timezones(0) => TIMEZONES_URI.to_string(),
+            TimeResource::Timezones(offset) => format!(
+                "{}?cursor={}",
+                TIMEZONES_URI,
+                pagination::encode_cursor(*offset)
+            ),
+            TimeResource::Formats => FORMATS_URI.to_string(),
+            TimeResource::Zone(tz) => format!("time://zone/{}", tz.name()),
+            TimeResource::Now(tz, Cadence::Minute) => format!("time://now/{}", tz.name()),
+            TimeResource::Now(tz, cadence) => {
+                format!("time://now/{}?cadence={}", tz.name(), cadence.name())
+            }
+            TimeResource::Transitions(tz, year) => {
+                format!("time://zone/{}/transitions/{}", tz.name(), year)
+            }
+            TimeResource::Calendar(year, month) => format!("time://calendar/{}/{}", year, month),
+        }
+    }
+
+    /// The resource's contents as JSON.
+    pub fn read(&self) -> Value {
+        match self {
+            TimeResource::Timezones(offset) => {
+                let timezones: Vec<&str> = TZ_VARIANTS.iter().map(|tz| tz.name()).collect();
+                let total_count = timezones.len();
+                let page = pagination::paginate(timezones, *offset, DEFAULT_TIMEZONE_PAGE_SIZE);
+
+                let mut contents = json!({
+                    "timezones": page.items,
+                    "total_count": total_count
+                });
+                if let Some(cursor) = page.next_cursor {
+                    contents["next_cursor"] = json!(cursor);
+                    contents["next_uri"] = json!(format!("{}?cursor={}", TIMEZONES_URI, cursor));
+                }
+                contents
+            }
+            TimeResource::Formats => time_formats(),
+            TimeResource::Zone(tz) => zone_info(*tz),
+            TimeResource::Now(tz, _) => now(*tz),
+            TimeResource::Transitions(tz, year) => json!({
+                "timezone": tz.name(),
+                "year": year,
+                "transitions": transitions(*tz, *year)
+            }),
+            TimeResource::Calendar(year, month) => calendar(*year, *month),
+        }
+    }
+}
+
+/// Static resources for `resources/list`.
+pub fn resource_definitions() -> Vec<Value> {
+    vec![
+        json!({
+            "uri": TIMEZONES_URI,
+            "name": "Timezone Database",
+            "description": "IANA timezone database, 100 names per page; follow next_uri for the rest",
+            "mimeType": "application/json"
+        }),
+        json!({
+            "uri": FORMATS_URI,
+            "name": "Time Formats",
+            "description": "Documentation of supported time formats",
+            "mimeType": "application/json"
+        }),
+    ]
+}
+
+/// Parameterized resources for `resources/templates/list`.
+pub fn template_definitions() -> Vec<Value> {
+    vec![
+        json!({
+            "uriTemplate": ZONE_TEMPLATE,
+            "name": "Timezone",
+            "description": "Current offset, abbreviation and local time for an IANA timezone",
+            "mimeType": "application/json"
+        }),
+        json!({
+            "uriTemplate": NOW_TEMPLATE,
+            "name": "Current Time",
+            "description": "Current time in a timezone; subscribe for updates every minute (default), hour or offset transition via ?cadence=minute|hour|transition",
+            "mimeType": "application/json"
+        }),
+        json!({
+            "uriTemplate": TRANSITIONS_TEMPLATE,
+            "name": "Timezone Transitions",
+            "description": "UTC offset changes (e.g. DST start and end) for a timezone in a given year",
+            "mimeType": "application/json"
+        }),
+        json!({
+            "uriTemplate": CALENDAR_TEMPLATE,
+            "name": "Calendar Month",
+            "description": "Dates, weekdays and ISO week numbers for a month",
+            "mimeType": "application/json"
+        }),
+    ]
+}
+
+/// Whether `uri` names a static resource or one of the resource templates.
+pub fn is_known_uri_or_template(uri: &str) -> bool {
+    [
+        TIMEZONES_URI,
+        FORMATS_URI,
+        ZONE_TEMPLATE,
+        NOW_TEMPLATE,
+        TRANSITIONS_TEMPLATE,
+        CALENDAR_TEMPLATE,
+    ]
+    .contains(&uri)
+}
+
+fn time_formats() -> Value {
+    json!({
+        "iso8601_examples": [
+            "2025-08-17T18:30:00Z",
+            "2025-08-17T18:30:00.123Z",
+            "2025-08-17T14:30:00-04:00"
+        ],
+        "rfc3339_examples": [
+            "2025-08-17T18:30:00Z",
+            "2025-08-17T18:30:00.123456Z"
+        ],
+        "custom_format_strings": {
+            "examples": [
+                "%Y-%m-%d %H:%M:%S",
+                "%B %d, %Y at %I:%M %p",
+                "%A, %b %d, %Y"
+            ],
+            "documentation": "Uses strftime format codes. Common codes: %Y=year, %m=month, %d=day, %H=hour(24h), %I=hour(12h), %M=minute, %S=second, %Z=timezone"
+        },
+        "locale_specific_formats": {
+            "us": "%m/%d/%Y %I:%M %p",
+            "eu": "%d/%m/%Y %H:%M",
+            "iso": "%Y-%m-%d %H:%M:%S"
+        }
+    })
+}
+
+fn now(tz: Tz) -> Value {
+    let now = Utc::now().with_timezone(&tz);
+
+    json!({
+        "timezone": tz.name(),
+        "timestamp": now.to_rfc3339(),
+        "unix": now.timestamp(),
+        "offset": format_offset(now.offset().fix().local_minus_utc()),
+        "abbreviation": now.format("%Z").to_string()
+    })
+}
+
+fn zone_info(tz: Tz) -> Value {
+    let now = Utc::now().with_timezone(&tz);
+    let offset = now.offset();
+
+    json!({
+        "timezone": tz.name(),
+        "offset": format_offset(offset.fix().local_minus_utc()),
+        "abbreviation": now.format("%Z").to_string(),
+        "dst_active": !offset.dst_offset().is_zero(),
+        "current_time": now.to_rfc3339()
+    })
+}
+
+/// Lists every instant in `year` where the zone's UTC offset or
+/// abbreviation changes.
+fn transitions(tz: Tz, year: i32) -> Vec<Value> {
+    let Some(start) = Utc.with_ymd_and_hms(year, 1, 1, 0, 0, 0).single() else {
+        return Vec::new();
+    };
+    let Some(end) = Utc.with_ymd_and_hms(year + 1, 1, 1, 0, 0, 0).single() else {
+        return Vec::new();
+    };
+
+    let mut transitions = Vec::new();
+    let mut after = start.timestamp() - 1;
+
+    while let Some(at) = next_transition(tz, after, end.timestamp() - 1) {
+        let (offset_before, abbreviation_before, _) = offset_state(tz, at - 1);
+        let (offset_after, abbreviation_after, dst) = offset_state(tz, at);
+        let at_utc = DateTime::from_timestamp(at, 0).unwrap_or_default();
+
+        transitions.push(json!({
+            "at": at_utc.to_rfc3339(),
+            "local_time": at_utc.with_timezone(&tz).to_rfc3339(),
+            "offset_before": format_offset(offset_before),
+            "offset_after": format_offset(offset_after),
+            "abbreviation_before": abbreviation_before,
+            "abbreviation_after": abbreviation_after,
+            "dst_active": dst
+        }));
+
+        after = at;
+    }
+
+    transitions
+}
+
+/// Finds the first second in `(after, until]` at which the zone's offset
+/// state differs from the second before, by stepping hourly and then
+/// bisecting to the exact second.
+fn next_transition(tz: Tz, after: i64, until: i64) -> Option<i64> {
+    let mut before = after;
+
+    while before < until {
+        let next = (before + 3600).min(until);
+
+        if offset_state(tz, before) != offset_state(tz, next) {
+            let (mut low, mut high) = (before, next);
+            while high - low > 1 {
+                let mid = low + (high - low) / 2;
+                if offset_state(tz, mid) == offset_state(tz, low) {
+                    low = mid;
+                } else {
+                    high = mid;
+                }
+            }
+            return Some(high);
+        }
+
+        before = next;
+    }
+
+    None
+}
+
+/// Offset in seconds, abbreviation and DST flag in effect at `timestamp`.
+fn offset_state(tz: Tz, timestamp: i64) -> (i32, String, bool) {
+    let utc = DateTime::from_timestamp(timestamp, 0).unwrap_or_default();
+    let local = utc.with_timezone(&tz);
+    let offset = local.offset();
+
+    (
+        offset.fix().local_minus_utc(),
+        local.format("%Z").to_string(),
+        !offset.dst_offset().is_zero(),
+    )
+}
+
+fn calendar(year: i32, month: u32) -> Value {
+    let days: Vec<Value> = (1..=31)
+        .map_while(|day| NaiveDate::from_ymd_opt(year, month, day))
+        .map(|date| {
+            json!({
+                "date": date.to_string(),
+                "weekday": date.format("%A").to_string(),
+                "iso_week": date.iso_week().week(),
+                "day_of_year": date.ordinal()
+            })
+        })
+        .collect();
+
+    let first = NaiveDate::from_ymd_opt(year, month, 1).unwrap_or_default();
+
+    json!({
+        "year": year,
+        "month": month,
+        "month_name": first.format("%B").to_string(),
+        "days_in_month": days.len(),
+        "first_weekday": first.format("%A").to_string(),
+        "leap_year": NaiveDate::from_ymd_opt(year, 2, 29).is_some(),
+        "days": days
+    })
+}
+
+fn format_offset(offset_seconds: i32) -> String {
+    let hours = offset_seconds / 3600;
+    let minutes = (offset_seconds % 3600) / 60;
+    format!("{:+03}:{:02}", hours, minutes.abs())
+}
+
+fn parse_timezone(timezone: &str) -> McpResult<Tz> {
+    timezone
+        .parse()
+        .map_err(|_| TimeError::InvalidTimezone(timezone.to_string()).into())
+}
+
+fn parse_year(year: &str) -> McpResult<i32> {
+    year.parse::<i32>()
+        .ok()
+        .filter(|year| (1..=9999).contains(year))
+        .ok_or_else(|| TimeError::InvalidArgument(format!("Invalid year: {}", year)).into())
+}
+
+fn parse_month(month: &str) -> McpResult<u32> {
+    month
+        .parse::<u32>()
+        .ok()
+        .filter(|month| (1..=12).contains(month))
+        .ok_or_else(|| TimeError::InvalidArgument(format!("Invalid month: {}", month)).into())
+}
+
+/// Decodes `%XX` escapes, leaving malformed ones as-is.
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(byte) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
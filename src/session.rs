@@ -0,0 +1,296 @@
+use crate::logging::{LogLevel, LogSubscription};
+use crate::models::{ClientInfo, McpError, McpResult};
+use crate::protocol::ProtocolVersion;
+use crate::sync::lock;
+use futures::future::AbortHandle;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use tokio::task;
+
+/// Capacity of a session's outbound notification queue.
+pub const NOTIFICATION_CHANNEL_CAPACITY: usize = 64;
+
+/// Sends server-initiated notifications to a session's client.
+///
+/// Delivery is best effort: notifications are dropped rather than awaited if
+/// the client is not reading fast enough or the session has no outbound
+/// channel, as with the REST routes.
+#[derive(Clone, Default)]
+pub struct Notifier {
+    outbound: Option<mpsc::Sender<Value>>,
+}
+
+impl Notifier {
+    pub fn new(outbound: mpsc::Sender<Value>) -> Self {
+        Self {
+            outbound: Some(outbound),
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.outbound.as_ref().is_some_and(|o| !o.is_closed())
+    }
+
+    pub fn notify(&self, method: &str, params: Value) {
+        if self.outbound.is_some() && !self.try_notify(method, params) {
+            tracing::debug!("Dropped {} notification", method);
+        }
+    }
+
+    /// Like [`Notifier::notify`], but reports failure instead of logging it,
+    /// for callers that must not emit tracing events.
+    pub fn try_notify(&self, method: &str, params: Value) -> bool {
+        let Some(outbound) = &self.outbound else {
+            return false;
+        };
+
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params
+        });
+
+        outbound.try_send(notification).is_ok()
+    }
+}
+
+/// Where a session is in the MCP lifecycle.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SessionState {
+    /// Waiting for `initialize`; only `initialize` and `ping` are accepted.
+    #[default]
+    Uninitialized,
+    /// `initialize` has been answered, `notifications/initialized` has not
+    /// arrived yet.
+    Initializing,
+    /// Fully initialized.
+    Ready,
+    /// The transport is closing; no new requests are accepted.
+    ShuttingDown,
+}
+
+/// Process-unique number identifying a [`Session`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SessionId(u64);
+
+impl Default for SessionId {
+    fn default() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(1);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// What a client told us about itself in `initialize`.
+#[derive(Default)]
+struct Lifecycle {
+    state: SessionState,
+    protocol_version: Option<ProtocolVersion>,
+    client_info: Option<ClientInfo>,
+    client_capabilities: Value,
+}
+
+/// Per-connection state shared by every message a client sends.
+///
+/// Each transport creates one `Session` per client connection (one per
+/// process for stdio, one per `Mcp-Session-Id` for HTTP) and passes it to
+/// the [`Dispatcher`](crate::handlers::dispatcher::Dispatcher) with every
+/// message.
+#[derive(Default)]
+pub struct Session {
+    id: SessionId,
+    in_flight: Mutex<HashMap<String, AbortHandle>>,
+    notifier: Notifier,
+    lifecycle: Mutex<Lifecycle>,
+    log_subscription: Mutex<Option<Arc<LogSubscription>>>,
+    resource_subscriptions: Mutex<HashMap<String, task::AbortHandle>>,
+}
+
+impl Session {
+    /// Session without an outbound channel; notifications are discarded.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Session whose notifications are queued on `outbound` for the
+    /// transport to deliver.
+    pub fn with_outbound(outbound: mpsc::Sender<Value>) -> Self {
+        let mut session = Self::default();
+        session.notifier = Notifier::new(outbound);
+        session
+    }
+
+    /// Identifies this session among all sessions in the process.
+    pub fn id(&self) -> u64 {
+        self.id.0
+    }
+
+    /// Span to handle this session's messages in. Log messages are only
+    /// sent to the session whose span they were raised in.
+    pub fn span(&self) -> tracing::Span {
+        tracing::info_span!("session", mcp_session = self.id())
+    }
+
+    pub fn notifier(&self) -> &Notifier {
+        &self.notifier
+    }
+
+    pub fn state(&self) -> SessionState {
+        lock(&self.lifecycle).state
+    }
+
+    /// Records the outcome of `initialize` and moves to
+    /// [`SessionState::Initializing`]. Fails if the session has already been
+    /// initialized, so a client can't renegotiate midway.
+    pub fn initialize(
+        &self,
+        version: ProtocolVersion,
+        client_info: Option<ClientInfo>,
+        client_capabilities: Value,
+    ) -> McpResult<()> {
+        let mut lifecycle = lock(&self.lifecycle);
+        if lifecycle.state != SessionState::Uninitialized {
+            return Err(McpError::invalid_request("Session is already initialized"));
+        }
+
+        *lifecycle = Lifecycle {
+            state: SessionState::Initializing,
+            protocol_version: Some(version),
+            client_info,
+            client_capabilities,
+        };
+        Ok(())
+    }
+
+    /// Handles `notifications/initialized`. Returns `false`, leaving the
+    /// state alone, unless the session was waiting for it.
+    pub fn mark_ready(&self) -> bool {
+        let mut lifecycle = lock(&self.lifecycle);
+        if lifecycle.state != SessionState::Initializing {
+            return false;
+        }
+        lifecycle.state = SessionState::Ready;
+        true
+    }
+
+    /// Stops the session from accepting new requests. Requests already
+    /// running are left to finish.
+    pub fn shut_down(&self) {
+        lock(&self.lifecycle).state = SessionState::ShuttingDown;
+    }
+
+    /// The protocol revision agreed on in `initialize`, or the latest one
+    /// before that.
+    pub fn protocol_version(&self) -> ProtocolVersion {
+        lock(&self.lifecycle)
+            .protocol_version
+            .unwrap_or(ProtocolVersion::LATEST)
+    }
+
+    /// Whether `initialize` has recorded a protocol revision yet.
+    pub fn is_negotiated(&self) -> bool {
+        lock(&self.lifecycle).protocol_version.is_some()
+    }
+
+    pub fn client_info(&self) -> Option<ClientInfo> {
+        lock(&self.lifecycle).client_info.clone()
+    }
+
+    /// The `capabilities` object the client sent with `initialize`, or
+    /// `null` before that.
+    pub fn client_capabilities(&self) -> Value {
+        lock(&self.lifecycle).client_capabilities.clone()
+    }
+
+    /// Minimum level of log messages sent to this session, if the client
+    /// has asked for any.
+    pub fn log_level(&self) -> Option<LogLevel> {
+        lock(&self.log_subscription)
+            .as_ref()
+            .map(|subscription| subscription.level())
+    }
+
+    /// Starts or adjusts delivery of `notifications/message` to this session.
+    pub fn set_log_level(&self, level: LogLevel) {
+        let mut subscription = lock(&self.log_subscription);
+        match subscription.as_ref() {
+            Some(subscription) => subscription.set_level(level),
+            None => {
+                *subscription = Some(LogSubscription::register(
+                    self.id(),
+                    level,
+                    self.notifier.clone(),
+                ))
+            }
+        }
+    }
+
+    /// Aborts the in-flight request with the given id. Returns `false` if no
+    /// such request is running, e.g. because it already completed.
+    pub fn cancel(&self, request_id: &Value) -> bool {
+        match self.lock_in_flight().remove(&Self::key(request_id)) {
+            Some(handle) => {
+                handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Number of requests currently being handled.
+    pub fn in_flight(&self) -> usize {
+        self.lock_in_flight().len()
+    }
+
+    /// Records a subscription to `uri`, served by the task behind `updates`.
+    /// Re-subscribing replaces (and stops) the previous task.
+    pub fn subscribe(&self, uri: &str, updates: task::AbortHandle) {
+        let previous = lock(&self.resource_subscriptions).insert(uri.to_string(), updates);
+        if let Some(previous) = previous {
+            previous.abort();
+        }
+    }
+
+    /// Stops updates for `uri`. Returns `false` if it was not subscribed.
+    pub fn unsubscribe(&self, uri: &str) -> bool {
+        match lock(&self.resource_subscriptions).remove(uri) {
+            Some(updates) => {
+                updates.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// URIs this session is subscribed to, in no particular order.
+    pub fn subscriptions(&self) -> Vec<String> {
+        lock(&self.resource_subscriptions).keys().cloned().collect()
+    }
+
+    pub(crate) fn track(&self, request_id: &Value, handle: AbortHandle) {
+        self.lock_in_flight().insert(Self::key(request_id), handle);
+    }
+
+    pub(crate) fn untrack(&self, request_id: &Value) {
+        self.lock_in_flight().remove(&Self::key(request_id));
+    }
+
+    /// Ids are keyed by their JSON encoding so `1` and `"1"` stay distinct.
+    fn key(request_id: &Value) -> String {
+        request_id.to_string()
+    }
+
+    fn lock_in_flight(&self) -> std::sync::MutexGuard<'_, HashMap<String, AbortHandle>> {
+        lock(&self.in_flight)
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        for updates in lock(&self.resource_subscriptions).values() {
+            updates.abort();
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::future::IntoFuture;
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+
+/// How long transports wait for in-flight requests after a shutdown signal
+/// before abandoning them.
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Creates a shutdown trigger and the [`Shutdown`] handle it fires.
+pub fn channel() -> (ShutdownTrigger, Shutdown) {
+    let (sender, receiver) = watch::channel(false);
+    (ShutdownTrigger(sender), Shutdown { receiver })
+}
+
+/// Returns a [`Shutdown`] fired by the first SIGINT or SIGTERM (Ctrl-C on
+/// non-Unix platforms).
+pub fn on_signal() -> Shutdown {
+    let (trigger, shutdown) = channel();
+
+    tokio::spawn(async move {
+        let signal = wait_for_signal().await;
+        tracing::info!("Received {}, shutting down gracefully", signal);
+        trigger.trigger();
+    });
+
+    shutdown
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() -> &'static str {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminate = match signal(SignalKind::terminate()) {
+        Ok(terminate) => terminate,
+        Err(e) => {
+            tracing::warn!("Failed to install SIGTERM handler: {}", e);
+            let _ = tokio::signal::ctrl_c().await;
+            return "SIGINT";
+        }
+    };
+
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => "SIGINT",
+        _ = terminate.recv() => "SIGTERM",
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() -> &'static str {
+    let _ = tokio::signal::ctrl_c().await;
+    "Ctrl-C"
+}
+
+/// Fires the [`Shutdown`] handles created alongside it.
+pub struct ShutdownTrigger(watch::Sender<bool>);
+
+impl ShutdownTrigger {
+    pub fn trigger(&self) {
+        self.0.send_replace(true);
+    }
+}
+
+/// A cloneable handle that transports watch to know when to stop accepting
+/// work. The default handle never fires.
+#[derive(Clone)]
+pub struct Shutdown {
+    receiver: watch::Receiver<bool>,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        let (_, receiver) = watch::channel(false);
+        Self { receiver }
+    }
+}
+
+impl Shutdown {
+    pub fn is_triggered(&self) -> bool {
+        *self.receiver.borrow()
+    }
+
+    /// Resolves once shutdown has been triggered, or never if the trigger
+    /// was dropped without firing.
+    pub async fn wait(&self) {
+        let mut receiver = self.receiver.clone();
+        if receiver.wait_for(|triggered| *triggered).await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    }
+
+    /// Runs `work` to completion, except that once shutdown is triggered it
+    /// gets only `timeout` more to finish. Returns `None` if it was cut off.
+    pub async fn with_deadline<F: IntoFuture>(
+        &self,
+        timeout: Duration,
+        work: F,
+    ) -> Option<F::Output> {
+        let work = work.into_future();
+        tokio::pin!(work);
+        tokio::select! {
+            output = &mut work => return Some(output),
+            _ = self.wait() => {}
+        }
+        tokio::time::timeout(timeout, work).await.ok()
+    }
+}
+
+/// Tracks spawned connection tasks so a server can wait for them to finish.
+/// Each task holds a [`DrainGuard`]; [`Drain::wait`] resolves once every
+/// guard has been dropped.
+pub struct Drain {
+    sender: mpsc::Sender<()>,
+    receiver: mpsc::Receiver<()>,
+}
+
+/// Held by a tracked task for as long as it runs.
+#[derive(Clone)]
+pub struct DrainGuard {
+    _sender: mpsc::Sender<()>,
+}
+
+impl Default for Drain {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel(1);
+        Self { sender, receiver }
+    }
+}
+
+impl Drain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn guard(&self) -> DrainGuard {
+        DrainGuard {
+            _sender: self.sender.clone(),
+        }
+    }
+
+    /// Waits for every guard handed out so far to be dropped.
+    pub async fn wait(self) {
+        let Drain {
+            sender,
+            mut receiver,
+        } = self;
+        drop(sender);
+        let _ = receiver.recv().await;
+    }
+}
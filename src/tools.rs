@@ -1,223 +1,451 @@
+use crate::pagination;
+use crate::registry::{output_schema_for, parse_arguments, schema_for, Tool};
 use anyhow::Result;
+use async_trait::async_trait;
 use chrono::{DateTime, Offset, TimeZone, Utc};
 use chrono_tz::{Tz, TZ_VARIANTS};
-use serde_json::{json, Value};
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+fn default_timezone() -> String {
+    "UTC".to_string()
+}
+
+/// Errors produced by the time tools, each carrying the JSON-RPC error code
+/// defined for it in the server specification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimeError {
+    InvalidTimezone(String),
+    InvalidTimestamp(String),
+    Conversion(String),
+    InvalidArgument(String),
+}
+
+impl TimeError {
+    pub fn code(&self) -> i32 {
+        match self {
+            TimeError::InvalidTimezone(_) => -32000,
+            TimeError::InvalidTimestamp(_) => -32001,
+            TimeError::Conversion(_) => -32002,
+            TimeError::InvalidArgument(_) => -32602,
+        }
+    }
+}
+
+impl fmt::Display for TimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeError::InvalidTimezone(timezone) => write!(f, "Invalid timezone: {}", timezone),
+            TimeError::InvalidTimestamp(timestamp) => {
+                write!(f, "Invalid timestamp format: {}", timestamp)
+            }
+            TimeError::Conversion(message) => write!(f, "Conversion error: {}", message),
+            TimeError::InvalidArgument(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for TimeError {}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct GetCurrentTimeArgs {
+    /// Target timezone (default: UTC)
+    pub timezone: String,
+    /// Output format
+    pub format: CurrentTimeFormat,
+    /// Custom strftime format string
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_format: Option<String>,
+}
+
+impl Default for GetCurrentTimeArgs {
+    fn default() -> Self {
+        Self {
+            timezone: default_timezone(),
+            format: CurrentTimeFormat::default(),
+            custom_format: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CurrentTimeFormat {
+    #[default]
+    Iso,
+    Unix,
+    Human,
+    Custom,
+}
+
+impl FromStr for CurrentTimeFormat {
+    type Err = TimeError;
+
+    fn from_str(s: &str) -> Result<Self, TimeError> {
+        match s {
+            "iso" => Ok(Self::Iso),
+            "unix" => Ok(Self::Unix),
+            "human" => Ok(Self::Human),
+            "custom" => Ok(Self::Custom),
+            _ => Err(TimeError::InvalidArgument(format!("Invalid format: {}", s))),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for CurrentTimeFormat {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// A timestamp rendered either as an RFC 3339 string or as Unix seconds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum TimestampValue {
+    Rfc3339(String),
+    Unix(i64),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GetCurrentTimeResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<TimestampValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unix: Option<i64>,
+    pub timezone: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formatted: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ConvertTimezoneArgs {
+    /// Input timestamp (ISO 8601 or Unix)
+    pub timestamp: String,
+    /// Source timezone
+    pub from_timezone: String,
+    /// Target timezone
+    pub to_timezone: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ZonedTimestamp {
+    pub timestamp: String,
+    pub timezone: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ConvertTimezoneResult {
+    pub original: ZonedTimestamp,
+    pub converted: ZonedTimestamp,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CalculateDurationArgs {
+    /// Start timestamp
+    pub start_time: String,
+    /// End timestamp
+    pub end_time: String,
+    /// Output units
+    #[serde(default)]
+    pub units: DurationUnits,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DurationUnits {
+    #[default]
+    Seconds,
+    Minutes,
+    Hours,
+    Days,
+}
+
+impl FromStr for DurationUnits {
+    type Err = TimeError;
+
+    fn from_str(s: &str) -> Result<Self, TimeError> {
+        match s {
+            "seconds" => Ok(Self::Seconds),
+            "minutes" => Ok(Self::Minutes),
+            "hours" => Ok(Self::Hours),
+            "days" => Ok(Self::Days),
+            _ => Err(TimeError::InvalidArgument(format!("Invalid units: {}", s))),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for DurationUnits {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DurationBreakdown {
+    pub total_seconds: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub days: Option<i64>,
+    pub hours: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minutes: Option<i64>,
+    pub human_readable: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CalculateDurationResult {
+    pub duration: DurationBreakdown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FormatTimeArgs {
+    /// Input timestamp
+    pub timestamp: String,
+    /// Format type
+    pub format: TimestampFormat,
+    /// Custom format string
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_format: Option<String>,
+    /// Target timezone
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TimestampFormat {
+    Iso8601,
+    Rfc3339,
+    Unix,
+    Custom,
+}
+
+impl FromStr for TimestampFormat {
+    type Err = TimeError;
+
+    fn from_str(s: &str) -> Result<Self, TimeError> {
+        match s {
+            "iso8601" => Ok(Self::Iso8601),
+            "rfc3339" => Ok(Self::Rfc3339),
+            "unix" => Ok(Self::Unix),
+            "custom" => Ok(Self::Custom),
+            _ => Err(TimeError::InvalidArgument(format!("Invalid format: {}", s))),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for TimestampFormat {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FormatTimeResult {
+    pub formatted: String,
+    pub timezone: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GetTimezoneInfoArgs {
+    /// Timezone identifier
+    pub timezone: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GetTimezoneInfoResult {
+    pub timezone: String,
+    pub offset: String,
+    pub dst_active: bool,
+    pub abbreviation: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ListTimezonesArgs {
+    /// Filter by region (e.g., 'America', 'Europe')
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// Maximum number of timezones to return, at most 1000. Without it, every
+    /// timezone is returned, or 100 when continuing from a cursor
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_size: Option<usize>,
+    /// The next_cursor of a previous call, to fetch the following page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ListTimezonesResult {
+    pub timezones: Vec<String>,
+    /// Number of timezones in this page.
+    pub count: usize,
+    /// Number of timezones matching the filter across all pages.
+    pub total: usize,
+    /// Pass as `cursor` to fetch the next page; absent on the last page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// Page size of `list_timezones` when the caller passes a cursor but no
+/// `page_size`.
+pub const DEFAULT_TIMEZONE_PAGE_SIZE: usize = 100;
+/// Upper bound on `list_timezones`' `page_size`.
+pub const MAX_TIMEZONE_PAGE_SIZE: usize = 1000;
 
 pub struct TimeTools;
 
 impl TimeTools {
-    pub async fn get_current_time(arguments: Value) -> Result<String> {
-        let timezone = arguments
-            .get("timezone")
-            .and_then(|v| v.as_str())
-            .unwrap_or("UTC");
-        let format = arguments
-            .get("format")
-            .and_then(|v| v.as_str())
-            .unwrap_or("iso");
-
-        let tz: Tz = timezone
-            .parse()
-            .map_err(|_| anyhow::anyhow!("Invalid timezone: {}", timezone))?;
+    pub async fn get_current_time(
+        args: GetCurrentTimeArgs,
+    ) -> Result<GetCurrentTimeResult, TimeError> {
+        let tz = Self::parse_timezone(&args.timezone)?;
 
         let now_utc = Utc::now();
         let now_tz = now_utc.with_timezone(&tz);
 
-        let result = match format {
-            "iso" => json!({
-                "timestamp": now_tz.to_rfc3339(),
-                "unix": now_utc.timestamp(),
-                "timezone": timezone,
-                "formatted": now_tz.format("%A, %B %d, %Y at %I:%M %p %Z").to_string()
-            }),
-            "unix" => json!({
-                "timestamp": now_utc.timestamp(),
-                "timezone": timezone
-            }),
-            "human" => json!({
-                "formatted": now_tz.format("%A, %B %d, %Y at %I:%M %p %Z").to_string(),
-                "timezone": timezone
-            }),
-            "custom" => {
-                let custom_format = arguments
-                    .get("custom_format")
-                    .and_then(|v| v.as_str())
-                    .ok_or_else(|| {
-                        anyhow::anyhow!("custom_format required when format is 'custom'")
-                    })?;
-                json!({
-                    "formatted": now_tz.format(custom_format).to_string(),
-                    "timezone": timezone
-                })
+        let result = match args.format {
+            CurrentTimeFormat::Iso => GetCurrentTimeResult {
+                timestamp: Some(TimestampValue::Rfc3339(now_tz.to_rfc3339())),
+                unix: Some(now_utc.timestamp()),
+                timezone: args.timezone,
+                formatted: Some(now_tz.format("%A, %B %d, %Y at %I:%M %p %Z").to_string()),
+            },
+            CurrentTimeFormat::Unix => GetCurrentTimeResult {
+                timestamp: Some(TimestampValue::Unix(now_utc.timestamp())),
+                unix: None,
+                timezone: args.timezone,
+                formatted: None,
+            },
+            CurrentTimeFormat::Human => GetCurrentTimeResult {
+                timestamp: None,
+                unix: None,
+                timezone: args.timezone,
+                formatted: Some(now_tz.format("%A, %B %d, %Y at %I:%M %p %Z").to_string()),
+            },
+            CurrentTimeFormat::Custom => {
+                let custom_format = args.custom_format.as_deref().ok_or_else(|| {
+                    TimeError::InvalidArgument(
+                        "custom_format required when format is 'custom'".to_string(),
+                    )
+                })?;
+                GetCurrentTimeResult {
+                    timestamp: None,
+                    unix: None,
+                    formatted: Some(Self::format_custom(&now_tz, custom_format)?),
+                    timezone: args.timezone,
+                }
             }
-            _ => return Err(anyhow::anyhow!("Invalid format: {}", format)),
         };
 
-        Ok(result.to_string())
+        Ok(result)
     }
 
-    pub async fn convert_timezone(arguments: Value) -> Result<String> {
-        let timestamp_str = arguments
-            .get("timestamp")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow::anyhow!("timestamp required"))?;
-        let from_tz_str = arguments
-            .get("from_timezone")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow::anyhow!("from_timezone required"))?;
-        let to_tz_str = arguments
-            .get("to_timezone")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow::anyhow!("to_timezone required"))?;
-
-        let from_tz: Tz = from_tz_str
-            .parse()
-            .map_err(|_| anyhow::anyhow!("Invalid from_timezone: {}", from_tz_str))?;
-        let to_tz: Tz = to_tz_str
-            .parse()
-            .map_err(|_| anyhow::anyhow!("Invalid to_timezone: {}", to_tz_str))?;
+    pub async fn convert_timezone(
+        args: ConvertTimezoneArgs,
+    ) -> Result<ConvertTimezoneResult, TimeError> {
+        let from_tz = Self::parse_timezone(&args.from_timezone)?;
+        let to_tz = Self::parse_timezone(&args.to_timezone)?;
 
-        let dt = Self::parse_timestamp(timestamp_str)?.with_timezone(&from_tz);
+        let dt = Self::parse_timestamp(&args.timestamp)?.with_timezone(&from_tz);
         let converted = dt.with_timezone(&to_tz);
 
-        let result = json!({
-            "original": {
-                "timestamp": dt.to_rfc3339(),
-                "timezone": from_tz_str
+        Ok(ConvertTimezoneResult {
+            original: ZonedTimestamp {
+                timestamp: dt.to_rfc3339(),
+                timezone: args.from_timezone,
             },
-            "converted": {
-                "timestamp": converted.to_rfc3339(),
-                "timezone": to_tz_str
-            }
-        });
-
-        Ok(result.to_string())
+            converted: ZonedTimestamp {
+                timestamp: converted.to_rfc3339(),
+                timezone: args.to_timezone,
+            },
+        })
     }
 
-    pub async fn calculate_duration(arguments: Value) -> Result<String> {
-        let start_str = arguments
-            .get("start_time")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow::anyhow!("start_time required"))?;
-        let end_str = arguments
-            .get("end_time")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow::anyhow!("end_time required"))?;
-        let units = arguments
-            .get("units")
-            .and_then(|v| v.as_str())
-            .unwrap_or("seconds");
-
-        let start_dt = Self::parse_timestamp(start_str)?;
-        let end_dt = Self::parse_timestamp(end_str)?;
+    pub async fn calculate_duration(
+        args: CalculateDurationArgs,
+    ) -> Result<CalculateDurationResult, TimeError> {
+        let start_dt = Self::parse_timestamp(&args.start_time)?;
+        let end_dt = Self::parse_timestamp(&args.end_time)?;
 
         let duration = end_dt.signed_duration_since(start_dt);
         let total_seconds = duration.num_seconds();
-
-        let result = match units {
-            "seconds" => json!({
-                "duration": {
-                    "total_seconds": total_seconds,
-                    "hours": total_seconds / 3600,
-                    "minutes": total_seconds / 60,
-                    "human_readable": format!("{} seconds", total_seconds)
-                }
-            }),
-            "minutes" => {
-                let minutes = total_seconds / 60;
-                json!({
-                    "duration": {
-                        "total_seconds": total_seconds,
-                        "minutes": minutes,
-                        "hours": minutes / 60,
-                        "human_readable": format!("{} minutes", minutes)
-                    }
-                })
-            }
-            "hours" => {
-                let hours = total_seconds / 3600;
-                json!({
-                    "duration": {
-                        "total_seconds": total_seconds,
-                        "hours": hours,
-                        "minutes": total_seconds / 60,
-                        "human_readable": format!("{} hours", hours)
-                    }
-                })
-            }
-            "days" => {
+        let minutes = total_seconds / 60;
+        let hours = total_seconds / 3600;
+
+        let duration = match args.units {
+            DurationUnits::Seconds => DurationBreakdown {
+                total_seconds,
+                days: None,
+                hours,
+                minutes: Some(minutes),
+                human_readable: format!("{} seconds", total_seconds),
+            },
+            DurationUnits::Minutes => DurationBreakdown {
+                total_seconds,
+                days: None,
+                hours,
+                minutes: Some(minutes),
+                human_readable: format!("{} minutes", minutes),
+            },
+            DurationUnits::Hours => DurationBreakdown {
+                total_seconds,
+                days: None,
+                hours,
+                minutes: Some(minutes),
+                human_readable: format!("{} hours", hours),
+            },
+            DurationUnits::Days => {
                 let days = total_seconds / (24 * 3600);
-                json!({
-                    "duration": {
-                        "total_seconds": total_seconds,
-                        "days": days,
-                        "hours": total_seconds / 3600,
-                        "human_readable": format!("{} days", days)
-                    }
-                })
+                DurationBreakdown {
+                    total_seconds,
+                    days: Some(days),
+                    hours,
+                    minutes: None,
+                    human_readable: format!("{} days", days),
+                }
             }
-            _ => return Err(anyhow::anyhow!("Invalid units: {}", units)),
         };
 
-        Ok(result.to_string())
-    }
-
-    pub async fn format_time(arguments: Value) -> Result<String> {
-        let timestamp_str = arguments
-            .get("timestamp")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow::anyhow!("timestamp required"))?;
-        let format = arguments
-            .get("format")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow::anyhow!("format required"))?;
-        let timezone_str = arguments
-            .get("timezone")
-            .and_then(|v| v.as_str())
-            .unwrap_or("UTC");
-
-        let dt = Self::parse_timestamp(timestamp_str)?;
-        let tz: Tz = timezone_str
-            .parse()
-            .map_err(|_| anyhow::anyhow!("Invalid timezone: {}", timezone_str))?;
+        Ok(CalculateDurationResult { duration })
+    }
+
+    pub async fn format_time(args: FormatTimeArgs) -> Result<FormatTimeResult, TimeError> {
+        let dt = Self::parse_timestamp(&args.timestamp)?;
+        let tz = Self::parse_timezone(&args.timezone)?;
         let dt_tz = dt.with_timezone(&tz);
 
-        let result = match format {
-            "iso8601" | "rfc3339" => json!({
-                "formatted": dt_tz.to_rfc3339(),
-                "timezone": timezone_str
-            }),
-            "unix" => json!({
-                "formatted": dt.timestamp().to_string(),
-                "timezone": timezone_str
-            }),
-            "custom" => {
-                let custom_format = arguments
-                    .get("custom_format")
-                    .and_then(|v| v.as_str())
-                    .ok_or_else(|| {
-                        anyhow::anyhow!("custom_format required when format is 'custom'")
-                    })?;
-                json!({
-                    "formatted": dt_tz.format(custom_format).to_string(),
-                    "timezone": timezone_str
-                })
+        let formatted = match args.format {
+            TimestampFormat::Iso8601 | TimestampFormat::Rfc3339 => dt_tz.to_rfc3339(),
+            TimestampFormat::Unix => dt.timestamp().to_string(),
+            TimestampFormat::Custom => {
+                let custom_format = args.custom_format.as_deref().ok_or_else(|| {
+                    TimeError::InvalidArgument(
+                        "custom_format required when format is 'custom'".to_string(),
+                    )
+                })?;
+                Self::format_custom(&dt_tz, custom_format)?
             }
-            _ => return Err(anyhow::anyhow!("Invalid format: {}", format)),
         };
 
-        Ok(result.to_string())
+        Ok(FormatTimeResult {
+            formatted,
+            timezone: args.timezone,
+        })
     }
 
-    pub async fn get_timezone_info(arguments: Value) -> Result<String> {
-        let timezone_str = arguments
-            .get("timezone")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow::anyhow!("timezone required"))?;
-
-        let tz: Tz = timezone_str
-            .parse()
-            .map_err(|_| anyhow::anyhow!("Invalid timezone: {}", timezone_str))?;
+    pub async fn get_timezone_info(
+        args: GetTimezoneInfoArgs,
+    ) -> Result<GetTimezoneInfoResult, TimeError> {
+        let tz = Self::parse_timezone(&args.timezone)?;
 
         let now = Utc::now().with_timezone(&tz);
         let offset = now.offset();
@@ -234,47 +462,237 @@ impl TimeTools {
         let offset_minutes = (offset_seconds % 3600) / 60;
         let offset_str = format!("{:+03}:{:02}", offset_hours, offset_minutes.abs());
 
-        let result = json!({
-            "timezone": timezone_str,
-            "offset": offset_str,
-            "dst_active": dst_active,
-            "abbreviation": abbreviation
-        });
-
-        Ok(result.to_string())
+        Ok(GetTimezoneInfoResult {
+            timezone: args.timezone,
+            offset: offset_str,
+            dst_active,
+            abbreviation,
+        })
     }
 
-    pub async fn list_timezones(arguments: Value) -> Result<String> {
-        let region_filter = arguments.get("region").and_then(|v| v.as_str());
-
+    pub async fn list_timezones(args: ListTimezonesArgs) -> Result<ListTimezonesResult, TimeError> {
         let timezones: Vec<String> = TZ_VARIANTS
             .iter()
             .map(|tz| tz.name().to_string())
             .filter(|name| {
-                if let Some(region) = region_filter {
-                    name.starts_with(region)
+                if let Some(region) = &args.region {
+                    name.starts_with(region.as_str())
                 } else {
                     true
                 }
             })
             .collect();
 
-        let result = json!({
-            "timezones": timezones,
-            "count": timezones.len()
-        });
+        let page_size = match args.page_size {
+            Some(0) => {
+                return Err(TimeError::InvalidArgument(
+                    "page_size must be at least 1".to_string(),
+                ))
+            }
+            Some(page_size) => page_size.min(MAX_TIMEZONE_PAGE_SIZE),
+            // Callers that don't page get every timezone, as they did before
+            // paging existed.
+            None if args.cursor.is_none() => usize::MAX,
+            None => DEFAULT_TIMEZONE_PAGE_SIZE,
+        };
+        let offset = match &args.cursor {
+            Some(cursor) => pagination::decode_cursor(cursor)
+                .ok_or_else(|| TimeError::InvalidArgument("Invalid cursor".to_string()))?,
+            None => 0,
+        };
+
+        let total = timezones.len();
+        let page = pagination::paginate(timezones, offset, page_size);
 
-        Ok(result.to_string())
+        Ok(ListTimezonesResult {
+            count: page.items.len(),
+            timezones: page.items,
+            total,
+            next_cursor: page.next_cursor,
+        })
+    }
+
+    fn parse_timezone(timezone: &str) -> Result<Tz, TimeError> {
+        timezone
+            .parse()
+            .map_err(|_| TimeError::InvalidTimezone(timezone.to_string()))
     }
 
-    fn parse_timestamp(timestamp_str: &str) -> Result<DateTime<Utc>> {
-        if let Ok(unix_timestamp) = timestamp_str.parse::<i64>() {
+    fn parse_timestamp(timestamp_str: &str) -> Result<DateTime<Utc>, TimeError> {
+        let parsed = if let Ok(unix_timestamp) = timestamp_str.parse::<i64>() {
             DateTime::from_timestamp(unix_timestamp, 0)
-                .ok_or_else(|| anyhow::anyhow!("Invalid Unix timestamp"))
         } else {
             DateTime::parse_from_rfc3339(timestamp_str)
                 .map(|dt| dt.with_timezone(&Utc))
-                .map_err(|_| anyhow::anyhow!("Invalid timestamp format"))
-        }
+                .ok()
+        };
+
+        parsed.ok_or_else(|| TimeError::InvalidTimestamp(timestamp_str.to_string()))
+    }
+
+    /// Renders a user-supplied strftime string, reporting unknown specifiers
+    /// as a conversion error instead of panicking inside `to_string`.
+    fn format_custom(dt: &DateTime<Tz>, custom_format: &str) -> Result<String, TimeError> {
+        let mut formatted = String::new();
+        write!(formatted, "{}", dt.format(custom_format)).map_err(|_| {
+            TimeError::Conversion(format!("Invalid custom format string: {}", custom_format))
+        })?;
+        Ok(formatted)
+    }
+}
+
+pub struct GetCurrentTimeTool;
+
+#[async_trait]
+impl Tool for GetCurrentTimeTool {
+    fn name(&self) -> &str {
+        "get_current_time"
+    }
+
+    fn description(&self) -> &str {
+        "Get the current time in various formats and timezones"
+    }
+
+    fn input_schema(&self) -> Value {
+        schema_for::<GetCurrentTimeArgs>()
+    }
+
+    fn output_schema(&self) -> Option<Value> {
+        Some(output_schema_for::<GetCurrentTimeResult>())
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<Value> {
+        let result = TimeTools::get_current_time(parse_arguments(arguments)?).await?;
+        Ok(serde_json::to_value(result)?)
+    }
+}
+
+pub struct ConvertTimezoneTool;
+
+#[async_trait]
+impl Tool for ConvertTimezoneTool {
+    fn name(&self) -> &str {
+        "convert_timezone"
+    }
+
+    fn description(&self) -> &str {
+        "Convert time between different timezones"
+    }
+
+    fn input_schema(&self) -> Value {
+        schema_for::<ConvertTimezoneArgs>()
+    }
+
+    fn output_schema(&self) -> Option<Value> {
+        Some(output_schema_for::<ConvertTimezoneResult>())
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<Value> {
+        let result = TimeTools::convert_timezone(parse_arguments(arguments)?).await?;
+        Ok(serde_json::to_value(result)?)
+    }
+}
+
+pub struct CalculateDurationTool;
+
+#[async_trait]
+impl Tool for CalculateDurationTool {
+    fn name(&self) -> &str {
+        "calculate_duration"
+    }
+
+    fn description(&self) -> &str {
+        "Calculate time differences between two timestamps"
+    }
+
+    fn input_schema(&self) -> Value {
+        schema_for::<CalculateDurationArgs>()
+    }
+
+    fn output_schema(&self) -> Option<Value> {
+        Some(output_schema_for::<CalculateDurationResult>())
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<Value> {
+        let result = TimeTools::calculate_duration(parse_arguments(arguments)?).await?;
+        Ok(serde_json::to_value(result)?)
+    }
+}
+
+pub struct FormatTimeTool;
+
+#[async_trait]
+impl Tool for FormatTimeTool {
+    fn name(&self) -> &str {
+        "format_time"
+    }
+
+    fn description(&self) -> &str {
+        "Format timestamps according to various standards"
+    }
+
+    fn input_schema(&self) -> Value {
+        schema_for::<FormatTimeArgs>()
+    }
+
+    fn output_schema(&self) -> Option<Value> {
+        Some(output_schema_for::<FormatTimeResult>())
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<Value> {
+        let result = TimeTools::format_time(parse_arguments(arguments)?).await?;
+        Ok(serde_json::to_value(result)?)
+    }
+}
+
+pub struct GetTimezoneInfoTool;
+
+#[async_trait]
+impl Tool for GetTimezoneInfoTool {
+    fn name(&self) -> &str {
+        "get_timezone_info"
+    }
+
+    fn description(&self) -> &str {
+        "Retrieve timezone information and current offset"
+    }
+
+    fn input_schema(&self) -> Value {
+        schema_for::<GetTimezoneInfoArgs>()
+    }
+
+    fn output_schema(&self) -> Option<Value> {
+        Some(output_schema_for::<GetTimezoneInfoResult>())
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<Value> {
+        let result = TimeTools::get_timezone_info(parse_arguments(arguments)?).await?;
+        Ok(serde_json::to_value(result)?)
+    }
+}
+
+pub struct ListTimezonesTool;
+
+#[async_trait]
+impl Tool for ListTimezonesTool {
+    fn name(&self) -> &str {
+        "list_timezones"
+    }
+
+    fn description(&self) -> &str {
+        "List available timezone identifiers"
+    }
+
+    fn input_schema(&self) -> Value {
+        schema_for::<ListTimezonesArgs>()
+    }
+
+    fn output_schema(&self) -> Option<Value> {
+        Some(output_schema_for::<ListTimezonesResult>())
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<Value> {
+        let result = TimeTools::list_timezones(parse_arguments(arguments)?).await?;
+        Ok(serde_json::to_value(result)?)
     }
 }
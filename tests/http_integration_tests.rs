@@ -1,7 +1,10 @@
 use anyhow::Result;
 use std::net::TcpListener;
-use time_mcp_server::config::{ServerConfig, TransportType};
-use time_mcp_server::handlers::http::HttpHandler;
+use time_mcp_server::auth::{JwtValidator, ProtectedResource, ScopePolicy, ScopeTarget};
+use time_mcp_server::config::{ServerConfig, TransportType, DEFAULT_PROTOCOL_VERSION};
+use time_mcp_server::handlers::{dispatcher::Dispatcher, http::HttpHandler, origin::OriginPolicy};
+use time_mcp_server::registry::{Tool, ToolContext, ToolRegistry};
+use time_mcp_server::shutdown;
 use tokio::time::{sleep, Duration};
 
 async fn start_http_server(config: ServerConfig) -> Result<()> {
@@ -32,6 +35,7 @@ async fn test_http_server_starts_and_responds() {
         host: "127.0.0.1".to_string(),
         port,
         auth_enabled: false,
+        ..ServerConfig::default()
     };
 
     // Run the server in a separate thread
@@ -63,6 +67,7 @@ async fn test_http_get_capabilities() {
         host: "127.0.0.1".to_string(),
         port,
         auth_enabled: false,
+        ..ServerConfig::default()
     };
 
     tokio::spawn(async move {
@@ -80,7 +85,7 @@ async fn test_http_get_capabilities() {
 
     assert_eq!(res.status(), reqwest::StatusCode::OK);
     let body: serde_json::Value = res.json().await.expect("Failed to parse json");
-    assert_eq!(body["protocolVersion"], "1.0");
+    assert_eq!(body["protocolVersion"], DEFAULT_PROTOCOL_VERSION);
 }
 
 #[tokio::test]
@@ -94,6 +99,7 @@ async fn test_http_call_tool() {
         host: "127.0.0.1".to_string(),
         port,
         auth_enabled: false,
+        ..ServerConfig::default()
     };
 
     tokio::spawn(async move {
@@ -131,6 +137,7 @@ async fn test_http_read_resource() {
         host: "127.0.0.1".to_string(),
         port,
         auth_enabled: false,
+        ..ServerConfig::default()
     };
 
     tokio::spawn(async move {
@@ -166,6 +173,7 @@ async fn test_http_get_prompt() {
         host: "127.0.0.1".to_string(),
         port,
         auth_enabled: false,
+        ..ServerConfig::default()
     };
 
     tokio::spawn(async move {
@@ -200,6 +208,7 @@ async fn test_http_auth_required() {
         host: "127.0.0.1".to_string(),
         port,
         auth_enabled: true,
+        ..ServerConfig::default()
     };
 
     tokio::spawn(async move {
@@ -217,3 +226,1124 @@ async fn test_http_auth_required() {
 
     assert_eq!(res.status(), reqwest::StatusCode::UNAUTHORIZED);
 }
+
+#[tokio::test]
+async fn test_http_serves_protected_resource_metadata() {
+    let port = get_available_port();
+    let config = ServerConfig {
+        transport: TransportType::Http {
+            host: "127.0.0.1".to_string(),
+            port,
+        },
+        host: "127.0.0.1".to_string(),
+        port,
+        auth_enabled: true,
+        resource: Some(
+            ProtectedResource::new("https://time.example.com/mcp")
+                .unwrap()
+                .with_authorization_server("https://auth.example.com")
+                .unwrap(),
+        ),
+        ..ServerConfig::default()
+    };
+
+    tokio::spawn(async move {
+        start_http_server(config).await.unwrap();
+    });
+
+    sleep(Duration::from_millis(100)).await;
+
+    let client = reqwest::Client::new();
+    for path in [
+        "/.well-known/oauth-protected-resource",
+        "/.well-known/oauth-protected-resource/mcp",
+    ] {
+        let res = client
+            .get(format!("http://127.0.0.1:{}{}", port, path))
+            .send()
+            .await
+            .expect("Failed to send request");
+        assert_eq!(res.status(), reqwest::StatusCode::OK);
+
+        let metadata: serde_json::Value = res.json().await.unwrap();
+        assert_eq!(metadata["resource"], "https://time.example.com/mcp");
+        assert_eq!(
+            metadata["authorization_servers"],
+            serde_json::json!(["https://auth.example.com"])
+        );
+    }
+
+    let res = client
+        .post(format!("http://127.0.0.1:{}/mcp", port))
+        .header("Accept", "application/json, text/event-stream")
+        .json(&serde_json::json!({"jsonrpc": "2.0", "id": 1, "method": "ping"}))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(res.status(), reqwest::StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        res.headers()["www-authenticate"],
+        r#"Bearer resource_metadata="https://time.example.com/.well-known/oauth-protected-resource/mcp""#
+    );
+
+    let res = client
+        .get(format!("http://127.0.0.1:{}/mcp/capabilities", port))
+        .bearer_auth("unknown-token")
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(res.status(), reqwest::StatusCode::UNAUTHORIZED);
+    assert!(res.headers()["www-authenticate"]
+        .to_str()
+        .unwrap()
+        .starts_with(r#"Bearer error="invalid_token""#));
+}
+
+#[tokio::test]
+async fn test_http_accepts_valid_jwt() {
+    let port = get_available_port();
+    let config = ServerConfig {
+        transport: TransportType::Http {
+            host: "127.0.0.1".to_string(),
+            port,
+        },
+        host: "127.0.0.1".to_string(),
+        port,
+        auth_enabled: true,
+        jwt: Some(JwtValidator::hs256(b"test-secret").with_audience("time-mcp")),
+        ..ServerConfig::default()
+    };
+
+    tokio::spawn(async move {
+        start_http_server(config).await.unwrap();
+    });
+
+    sleep(Duration::from_millis(100)).await;
+
+    let exp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        + 3600;
+    let sign = |audience: &str| {
+        jsonwebtoken::encode(
+            &jsonwebtoken::Header::default(),
+            &serde_json::json!({"sub": "alice", "aud": audience, "exp": exp}),
+            &jsonwebtoken::EncodingKey::from_secret(b"test-secret"),
+        )
+        .unwrap()
+    };
+
+    let client = reqwest::Client::new();
+    let res = client
+        .get(format!("http://127.0.0.1:{}/mcp/capabilities", port))
+        .bearer_auth(sign("time-mcp"))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+
+    let res = client
+        .get(format!("http://127.0.0.1:{}/mcp/capabilities", port))
+        .bearer_auth(sign("another-service"))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(res.status(), reqwest::StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_http_enforces_scope_policy() {
+    let port = get_available_port();
+    let config = ServerConfig {
+        transport: TransportType::Http {
+            host: "127.0.0.1".to_string(),
+            port,
+        },
+        host: "127.0.0.1".to_string(),
+        port,
+        auth_enabled: true,
+        jwt: Some(JwtValidator::hs256(b"test-secret")),
+        scope_policy: ScopePolicy::new()
+            .with_rule(ScopeTarget::Tool, "*", ["time:read"])
+            .with_rule(ScopeTarget::Tool, "list_timezones", ["tz:list"]),
+        ..ServerConfig::default()
+    };
+
+    tokio::spawn(async move {
+        start_http_server(config).await.unwrap();
+    });
+
+    sleep(Duration::from_millis(100)).await;
+
+    let exp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        + 3600;
+    let token = jsonwebtoken::encode(
+        &jsonwebtoken::Header::default(),
+        &serde_json::json!({
+            "sub": "alice",
+            "aud": format!("http://127.0.0.1:{}/mcp", port),
+            "scope": "time:read",
+            "exp": exp
+        }),
+        &jsonwebtoken::EncodingKey::from_secret(b"test-secret"),
+    )
+    .unwrap();
+
+    let client = reqwest::Client::new();
+    let res = client
+        .get(format!("http://127.0.0.1:{}/mcp/tools/list", port))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+    let body: serde_json::Value = res.json().await.unwrap();
+    let names: Vec<_> = body["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tool| tool["name"].as_str().unwrap())
+        .collect();
+    assert!(!names.contains(&"list_timezones"));
+    assert!(names.contains(&"get_current_time"));
+
+    let res = client
+        .post(format!("http://127.0.0.1:{}/mcp/tools/call", port))
+        .bearer_auth(&token)
+        .json(&serde_json::json!({"name": "list_timezones", "arguments": {}}))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    assert_eq!(
+        res.headers()["www-authenticate"],
+        format!(
+            r#"Bearer error="insufficient_scope", scope="tz:list", resource_metadata="http://127.0.0.1:{}/.well-known/oauth-protected-resource/mcp""#,
+            port
+        )
+        .as_str()
+    );
+
+    // Streamable HTTP reports the same refusal with the JSON-RPC error.
+    let res = client
+        .post(format!("http://127.0.0.1:{}/mcp", port))
+        .bearer_auth(&token)
+        .header("Accept", "application/json, text/event-stream")
+        .json(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": DEFAULT_PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": {"name": "test-client", "version": "1.0.0"}
+            }
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    let session_id = res.headers()["mcp-session-id"]
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    let res = client
+        .post(format!("http://127.0.0.1:{}/mcp", port))
+        .bearer_auth(&token)
+        .header("Accept", "application/json, text/event-stream")
+        .header("Mcp-Session-Id", &session_id)
+        .header("MCP-Protocol-Version", DEFAULT_PROTOCOL_VERSION)
+        .json(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "tools/call",
+            "params": {"name": "list_timezones", "arguments": {}}
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    assert!(res.headers()["www-authenticate"]
+        .to_str()
+        .unwrap()
+        .contains("insufficient_scope"));
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["error"]["code"], 403);
+    assert_eq!(body["error"]["data"]["scope"], "tz:list");
+}
+
+#[tokio::test]
+async fn test_http_custom_dispatcher_keeps_scope_policy() {
+    let port = get_available_port();
+    let config = ServerConfig {
+        host: "127.0.0.1".to_string(),
+        port,
+        auth_enabled: true,
+        jwt: Some(JwtValidator::hs256(b"test-secret")),
+        scope_policy: ScopePolicy::new()
+            .with_rule(ScopeTarget::Tool, "*", ["time:read"])
+            .with_rule(ScopeTarget::Tool, "list_timezones", ["tz:list"]),
+        ..ServerConfig::default()
+    };
+    let handler = HttpHandler::new(config)
+        .with_dispatcher(Dispatcher::with_tools(ToolRegistry::with_builtin_tools()));
+    tokio::spawn(async move { handler.run("127.0.0.1", port).await.unwrap() });
+    sleep(Duration::from_millis(100)).await;
+
+    let exp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        + 3600;
+    let token = jsonwebtoken::encode(
+        &jsonwebtoken::Header::default(),
+        &serde_json::json!({
+            "sub": "alice",
+            "aud": format!("http://127.0.0.1:{}/mcp", port),
+            "scope": "time:read",
+            "exp": exp
+        }),
+        &jsonwebtoken::EncodingKey::from_secret(b"test-secret"),
+    )
+    .unwrap();
+
+    let res = reqwest::Client::new()
+        .post(format!("http://127.0.0.1:{}/mcp/tools/call", port))
+        .bearer_auth(&token)
+        .json(&serde_json::json!({"name": "list_timezones", "arguments": {}}))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    assert!(res.headers()["www-authenticate"]
+        .to_str()
+        .unwrap()
+        .contains("insufficient_scope"));
+}
+
+#[tokio::test]
+async fn test_http_sessions_belong_to_their_caller() {
+    let port = get_available_port();
+    let config = ServerConfig {
+        transport: TransportType::Http {
+            host: "127.0.0.1".to_string(),
+            port,
+        },
+        host: "127.0.0.1".to_string(),
+        port,
+        auth_enabled: true,
+        jwt: Some(JwtValidator::hs256(b"test-secret")),
+        scope_policy: ScopePolicy::new().with_rule(ScopeTarget::Tool, "*", ["time:read"]),
+        ..ServerConfig::default()
+    };
+
+    tokio::spawn(async move {
+        start_http_server(config).await.unwrap();
+    });
+
+    sleep(Duration::from_millis(100)).await;
+
+    let exp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        + 3600;
+    let token = |sub: &str, scope: &str| {
+        jsonwebtoken::encode(
+            &jsonwebtoken::Header::default(),
+            &serde_json::json!({
+                "sub": sub,
+                "aud": format!("http://127.0.0.1:{}/mcp", port),
+                "scope": scope,
+                "exp": exp
+            }),
+            &jsonwebtoken::EncodingKey::from_secret(b"test-secret"),
+        )
+        .unwrap()
+    };
+    let alice = token("alice", "");
+    let mallory = token("mallory", "time:read");
+
+    let client = reqwest::Client::new();
+    let res = client
+        .post(format!("http://127.0.0.1:{}/mcp", port))
+        .bearer_auth(&alice)
+        .header("Accept", "application/json, text/event-stream")
+        .json(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": DEFAULT_PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": {"name": "test-client", "version": "1.0.0"}
+            }
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    let session_id = res.headers()["mcp-session-id"]
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    let call = |token: &str, id: u32| {
+        client
+            .post(format!("http://127.0.0.1:{}/mcp", port))
+            .bearer_auth(token)
+            .header("Accept", "application/json, text/event-stream")
+            .header("Mcp-Session-Id", &session_id)
+            .json(&serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "method": "tools/call",
+                "params": {"name": "get_current_time", "arguments": {}}
+            }))
+            .send()
+    };
+
+    // Another caller's token neither works in, nor lends its scopes to,
+    // alice's session.
+    let res = call(&mallory, 2).await.expect("Failed to send request");
+    assert_eq!(res.status(), reqwest::StatusCode::NOT_FOUND);
+    let res = call(&alice, 3).await.expect("Failed to send request");
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+
+    let res = client
+        .get(format!("http://127.0.0.1:{}/mcp", port))
+        .bearer_auth(&mallory)
+        .header("Accept", "text/event-stream")
+        .header("Mcp-Session-Id", &session_id)
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(res.status(), reqwest::StatusCode::NOT_FOUND);
+
+    let delete = |token: &str| {
+        client
+            .delete(format!("http://127.0.0.1:{}/mcp", port))
+            .bearer_auth(token)
+            .header("Mcp-Session-Id", &session_id)
+            .send()
+    };
+    let res = delete(&mallory).await.expect("Failed to send request");
+    assert_eq!(res.status(), reqwest::StatusCode::NOT_FOUND);
+    let res = delete(&alice).await.expect("Failed to send request");
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+}
+
+async fn spawn_test_server(auth_enabled: bool) -> u16 {
+    let port = get_available_port();
+    let config = ServerConfig {
+        transport: TransportType::Http {
+            host: "127.0.0.1".to_string(),
+            port,
+        },
+        host: "127.0.0.1".to_string(),
+        port,
+        auth_enabled,
+        ..ServerConfig::default()
+    };
+
+    tokio::spawn(async move {
+        start_http_server(config).await.unwrap();
+    });
+
+    sleep(Duration::from_millis(100)).await;
+    port
+}
+
+async fn initialize_session(client: &reqwest::Client, port: u16) -> String {
+    let res = client
+        .post(format!("http://127.0.0.1:{}/mcp", port))
+        .header("Accept", "application/json, text/event-stream")
+        .json(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": DEFAULT_PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": {"name": "test-client", "version": "1.0.0"}
+            }
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+    res.headers()
+        .get("mcp-session-id")
+        .expect("initialize response should carry a session id")
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+#[tokio::test]
+async fn test_streamable_http_initialize_assigns_session() {
+    let port = spawn_test_server(false).await;
+    let client = reqwest::Client::new();
+
+    let res = client
+        .post(format!("http://127.0.0.1:{}/mcp", port))
+        .json(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {"protocolVersion": DEFAULT_PROTOCOL_VERSION}
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+    assert!(res.headers().get("mcp-session-id").is_some());
+
+    let body: serde_json::Value = res.json().await.expect("Failed to parse json");
+    assert_eq!(body["jsonrpc"], "2.0");
+    assert_eq!(body["id"], 1);
+    assert_eq!(body["result"]["protocolVersion"], DEFAULT_PROTOCOL_VERSION);
+}
+
+#[tokio::test]
+async fn test_streamable_http_tools_call_with_session() {
+    let port = spawn_test_server(false).await;
+    let client = reqwest::Client::new();
+    let session_id = initialize_session(&client, port).await;
+
+    let res = client
+        .post(format!("http://127.0.0.1:{}/mcp", port))
+        .header("mcp-session-id", &session_id)
+        .header("mcp-protocol-version", DEFAULT_PROTOCOL_VERSION)
+        .json(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "tools/call",
+            "params": {
+                "name": "get_current_time",
+                "arguments": {"timezone": "UTC"}
+            }
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+    let body: serde_json::Value = res.json().await.expect("Failed to parse json");
+    assert_eq!(body["id"], 2);
+    assert!(body["result"]["content"][0]["text"].is_string());
+}
+
+#[tokio::test]
+async fn test_streamable_http_notification_accepted() {
+    let port = spawn_test_server(false).await;
+    let client = reqwest::Client::new();
+    let session_id = initialize_session(&client, port).await;
+
+    let res = client
+        .post(format!("http://127.0.0.1:{}/mcp", port))
+        .header("mcp-session-id", &session_id)
+        .json(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/initialized"
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(res.status(), reqwest::StatusCode::ACCEPTED);
+    assert!(res.bytes().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_streamable_http_session_errors() {
+    let port = spawn_test_server(false).await;
+    let client = reqwest::Client::new();
+    let request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "tools/list"
+    });
+
+    let res = client
+        .post(format!("http://127.0.0.1:{}/mcp", port))
+        .json(&request)
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+
+    let res = client
+        .post(format!("http://127.0.0.1:{}/mcp", port))
+        .header("mcp-session-id", "unknown-session")
+        .json(&request)
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(res.status(), reqwest::StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_streamable_http_rejects_unsupported_protocol_version() {
+    let port = spawn_test_server(false).await;
+    let client = reqwest::Client::new();
+    let session_id = initialize_session(&client, port).await;
+
+    let res = client
+        .post(format!("http://127.0.0.1:{}/mcp", port))
+        .header("mcp-session-id", &session_id)
+        .header("mcp-protocol-version", "1999-01-01")
+        .json(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "tools/list"
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_streamable_http_parse_error() {
+    let port = spawn_test_server(false).await;
+    let client = reqwest::Client::new();
+
+    let res = client
+        .post(format!("http://127.0.0.1:{}/mcp", port))
+        .header("Content-Type", "application/json")
+        .body("{not json")
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = res.json().await.expect("Failed to parse json");
+    assert_eq!(body["error"]["code"], -32700);
+}
+
+#[tokio::test]
+async fn test_streamable_http_sse_stream_and_delete() {
+    let port = spawn_test_server(false).await;
+    let client = reqwest::Client::new();
+    let session_id = initialize_session(&client, port).await;
+
+    let res = client
+        .get(format!("http://127.0.0.1:{}/mcp", port))
+        .header("mcp-session-id", &session_id)
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(res.status(), reqwest::StatusCode::METHOD_NOT_ALLOWED);
+
+    let res = client
+        .get(format!("http://127.0.0.1:{}/mcp", port))
+        .header("mcp-session-id", &session_id)
+        .header("Accept", "text/event-stream")
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+    assert!(res.headers()["content-type"]
+        .to_str()
+        .unwrap()
+        .starts_with("text/event-stream"));
+    drop(res);
+
+    let res = client
+        .delete(format!("http://127.0.0.1:{}/mcp", port))
+        .header("mcp-session-id", &session_id)
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+
+    let res = client
+        .delete(format!("http://127.0.0.1:{}/mcp", port))
+        .header("mcp-session-id", &session_id)
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(res.status(), reqwest::StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_streamable_http_auth_required() {
+    let port = spawn_test_server(true).await;
+    let client = reqwest::Client::new();
+
+    let res = client
+        .post(format!("http://127.0.0.1:{}/mcp", port))
+        .json(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize"
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(res.status(), reqwest::StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_http_list_tools() {
+    let port = spawn_test_server(false).await;
+    let client = reqwest::Client::new();
+
+    let res = client
+        .get(format!("http://127.0.0.1:{}/mcp/tools/list", port))
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+    let body: serde_json::Value = res.json().await.expect("Failed to parse json");
+    assert_eq!(body["tools"].as_array().unwrap().len(), 6);
+}
+
+#[tokio::test]
+async fn test_http_call_unknown_tool() {
+    let port = spawn_test_server(false).await;
+    let client = reqwest::Client::new();
+
+    let res = client
+        .post(format!("http://127.0.0.1:{}/mcp/tools/call", port))
+        .json(&serde_json::json!({
+            "name": "no_such_tool",
+            "arguments": {}
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_http_call_tool_error_is_reported_in_result() {
+    let port = spawn_test_server(false).await;
+    let client = reqwest::Client::new();
+
+    let res = client
+        .post(format!("http://127.0.0.1:{}/mcp/tools/call", port))
+        .json(&serde_json::json!({
+            "name": "get_current_time",
+            "arguments": {"timezone": "Invalid/Zone"}
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+    let body: serde_json::Value = res.json().await.expect("Failed to parse json");
+    assert_eq!(body["isError"], true);
+    assert_eq!(body["structuredContent"]["error"]["code"], -32000);
+}
+
+/// Reports one progress update per step before answering.
+struct ProgressTool;
+
+#[async_trait::async_trait]
+impl Tool for ProgressTool {
+    fn name(&self) -> &str {
+        "progress"
+    }
+
+    fn description(&self) -> &str {
+        "Report progress before answering"
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        serde_json::json!({"type": "object"})
+    }
+
+    async fn execute(&self, arguments: serde_json::Value) -> Result<serde_json::Value> {
+        Ok(arguments)
+    }
+
+    async fn execute_with_context(
+        &self,
+        arguments: serde_json::Value,
+        context: &ToolContext,
+    ) -> Result<serde_json::Value> {
+        context.progress.report(1.0, Some(2.0), None);
+        context.progress.report(2.0, Some(2.0), None);
+        Ok(arguments)
+    }
+}
+
+#[tokio::test]
+async fn test_streamable_http_progress_over_sse() {
+    let port = get_available_port();
+    let mut registry = ToolRegistry::with_builtin_tools();
+    registry.register(ProgressTool);
+    let handler =
+        HttpHandler::new(ServerConfig::default()).with_dispatcher(Dispatcher::with_tools(registry));
+    tokio::spawn(async move { handler.run("127.0.0.1", port).await.unwrap() });
+    sleep(Duration::from_millis(100)).await;
+
+    let client = reqwest::Client::new();
+    let session_id = initialize_session(&client, port).await;
+
+    let mut events = client
+        .get(format!("http://127.0.0.1:{}/mcp", port))
+        .header("mcp-session-id", &session_id)
+        .header("Accept", "text/event-stream")
+        .send()
+        .await
+        .expect("Failed to open SSE stream");
+    assert_eq!(events.status(), reqwest::StatusCode::OK);
+
+    let res = client
+        .post(format!("http://127.0.0.1:{}/mcp", port))
+        .header("mcp-session-id", &session_id)
+        .json(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "tools/call",
+            "params": {
+                "name": "progress",
+                "arguments": {},
+                "_meta": {"progressToken": 7}
+            }
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    let body: serde_json::Value = res.json().await.expect("Failed to parse json");
+    assert_eq!(body["id"], 2);
+
+    let mut received = String::new();
+    while received.matches("notifications/progress").count() < 2 {
+        let chunk = tokio::time::timeout(Duration::from_secs(5), events.chunk())
+            .await
+            .expect("Timed out waiting for progress")
+            .expect("Failed to read SSE stream")
+            .expect("SSE stream closed");
+        received.push_str(&String::from_utf8_lossy(&chunk));
+    }
+
+    assert!(received.contains("\"progressToken\":7"));
+    assert!(received.contains("\"progress\":2.0"));
+}
+
+#[tokio::test]
+async fn test_streamable_http_rejects_version_other_than_negotiated() {
+    let port = spawn_test_server(false).await;
+    let client = reqwest::Client::new();
+
+    let res = client
+        .post(format!("http://127.0.0.1:{}/mcp", port))
+        .json(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {"protocolVersion": "2025-03-26"}
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    let session_id = res.headers()["mcp-session-id"]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let body: serde_json::Value = res.json().await.expect("Failed to parse json");
+    assert_eq!(body["result"]["protocolVersion"], "2025-03-26");
+
+    let list_tools = |version: &'static str| {
+        client
+            .post(format!("http://127.0.0.1:{}/mcp", port))
+            .header("mcp-session-id", &session_id)
+            .header("mcp-protocol-version", version)
+            .json(&serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 2,
+                "method": "tools/list"
+            }))
+            .send()
+    };
+
+    let res = list_tools(DEFAULT_PROTOCOL_VERSION).await.unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+
+    let res = list_tools("2025-03-26").await.unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+    let body: serde_json::Value = res.json().await.expect("Failed to parse json");
+    assert!(body["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|tool| tool.get("outputSchema").is_none()));
+}
+
+/// Sleeps for `ms` milliseconds, then returns its arguments.
+struct SleepTool;
+
+#[async_trait::async_trait]
+impl Tool for SleepTool {
+    fn name(&self) -> &str {
+        "sleep"
+    }
+
+    fn description(&self) -> &str {
+        "Sleep before answering"
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        serde_json::json!({"type": "object"})
+    }
+
+    async fn execute(&self, arguments: serde_json::Value) -> Result<serde_json::Value> {
+        let ms = arguments["ms"].as_u64().unwrap_or(0);
+        sleep(Duration::from_millis(ms)).await;
+        Ok(arguments)
+    }
+}
+
+#[tokio::test]
+async fn test_streamable_http_graceful_shutdown() {
+    let port = get_available_port();
+    let mut registry = ToolRegistry::new();
+    registry.register(SleepTool);
+    let (trigger, shutdown) = shutdown::channel();
+    let handler = HttpHandler::new(ServerConfig::default())
+        .with_dispatcher(Dispatcher::with_tools(registry))
+        .with_shutdown(shutdown);
+    let server = tokio::spawn(async move { handler.run("127.0.0.1", port).await });
+    sleep(Duration::from_millis(100)).await;
+
+    let client = reqwest::Client::new();
+    let session_id = initialize_session(&client, port).await;
+
+    let mut events = client
+        .get(format!("http://127.0.0.1:{}/mcp", port))
+        .header("mcp-session-id", &session_id)
+        .header("Accept", "text/event-stream")
+        .send()
+        .await
+        .expect("Failed to open SSE stream");
+    assert_eq!(events.status(), reqwest::StatusCode::OK);
+
+    let in_flight = client
+        .post(format!("http://127.0.0.1:{}/mcp", port))
+        .header("mcp-session-id", &session_id)
+        .json(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "tools/call",
+            "params": {"name": "sleep", "arguments": {"ms": 300}}
+        }))
+        .send();
+    let in_flight = tokio::spawn(in_flight);
+
+    sleep(Duration::from_millis(50)).await;
+    trigger.trigger();
+
+    let res = in_flight
+        .await
+        .unwrap()
+        .expect("In-flight request was dropped");
+    let body: serde_json::Value = res.json().await.expect("Failed to parse json");
+    assert_eq!(body["id"], 2);
+    assert_eq!(body["result"]["structuredContent"]["ms"], 300);
+
+    // The SSE stream ends rather than being cut off.
+    let end = tokio::time::timeout(Duration::from_secs(5), async {
+        while events.chunk().await.expect("SSE stream failed").is_some() {}
+    })
+    .await;
+    assert!(end.is_ok(), "SSE stream stayed open after shutdown");
+
+    tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("Server did not stop")
+        .unwrap()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_http_rejects_untrusted_origin() {
+    let port = get_available_port();
+    let config = ServerConfig {
+        allowed_origins: OriginPolicy::new().with_origin("https://app.example.com"),
+        ..ServerConfig::default()
+    };
+    tokio::spawn(async move { HttpHandler::new(config).run("127.0.0.1", port).await });
+    sleep(Duration::from_millis(100)).await;
+
+    let client = reqwest::Client::new();
+    let initialize = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {"protocolVersion": DEFAULT_PROTOCOL_VERSION}
+    });
+
+    let res = client
+        .post(format!("http://127.0.0.1:{}/mcp", port))
+        .header("Origin", "https://evil.example")
+        .json(&initialize)
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    assert!(res.headers().get("mcp-session-id").is_none());
+
+    let res = client
+        .get(format!("http://127.0.0.1:{}/mcp/tools/list", port))
+        .header("Origin", "https://evil.example")
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+
+    for origin in ["https://app.example.com", "http://localhost:5173"] {
+        let res = client
+            .post(format!("http://127.0.0.1:{}/mcp", port))
+            .header("Origin", origin)
+            .json(&initialize)
+            .send()
+            .await
+            .expect("Failed to send request");
+        assert_eq!(res.status(), reqwest::StatusCode::OK, "origin {}", origin);
+    }
+
+    // Discovery stays reachable from anywhere.
+    let res = client
+        .get(format!(
+            "http://127.0.0.1:{}/.well-known/oauth-protected-resource",
+            port
+        ))
+        .header("Origin", "https://evil.example")
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_streamable_http_caps_open_sessions() {
+    let port = get_available_port();
+    let config = ServerConfig {
+        max_sessions: 1,
+        ..ServerConfig::default()
+    };
+    tokio::spawn(async move { HttpHandler::new(config).run("127.0.0.1", port).await });
+    sleep(Duration::from_millis(100)).await;
+
+    let client = reqwest::Client::new();
+    let session_id = initialize_session(&client, port).await;
+
+    let res = client
+        .post(format!("http://127.0.0.1:{}/mcp", port))
+        .json(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {"protocolVersion": DEFAULT_PROTOCOL_VERSION}
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(res.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+    assert!(res.headers().get("mcp-session-id").is_none());
+
+    let res = client
+        .delete(format!("http://127.0.0.1:{}/mcp", port))
+        .header("mcp-session-id", &session_id)
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+
+    initialize_session(&client, port).await;
+}
+
+#[tokio::test]
+async fn test_streamable_http_expires_idle_sessions() {
+    let port = get_available_port();
+    let config = ServerConfig {
+        session_idle_timeout: Duration::from_millis(300),
+        ..ServerConfig::default()
+    };
+    tokio::spawn(async move { HttpHandler::new(config).run("127.0.0.1", port).await });
+    sleep(Duration::from_millis(100)).await;
+
+    let client = reqwest::Client::new();
+    let session_id = initialize_session(&client, port).await;
+    let ping = |id: u64| {
+        client
+            .post(format!("http://127.0.0.1:{}/mcp", port))
+            .header("mcp-session-id", &session_id)
+            .json(&serde_json::json!({"jsonrpc": "2.0", "id": id, "method": "ping"}))
+            .send()
+    };
+
+    // Requests keep the session alive.
+    for id in 2..5 {
+        sleep(Duration::from_millis(150)).await;
+        let res = ping(id).await.expect("Failed to send request");
+        assert_eq!(res.status(), reqwest::StatusCode::OK);
+    }
+
+    sleep(Duration::from_millis(500)).await;
+    let res = ping(5).await.expect("Failed to send request");
+    assert_eq!(res.status(), reqwest::StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_streamable_http_answers_cancelled_requests() {
+    let port = get_available_port();
+    let mut registry = ToolRegistry::new();
+    registry.register(SleepTool);
+    let handler =
+        HttpHandler::new(ServerConfig::default()).with_dispatcher(Dispatcher::with_tools(registry));
+    tokio::spawn(async move { handler.run("127.0.0.1", port).await.unwrap() });
+    sleep(Duration::from_millis(100)).await;
+
+    let client = reqwest::Client::new();
+    let session_id = initialize_session(&client, port).await;
+
+    let in_flight = client
+        .post(format!("http://127.0.0.1:{}/mcp", port))
+        .header("mcp-session-id", &session_id)
+        .json(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "tools/call",
+            "params": {"name": "sleep", "arguments": {"ms": 5_000}}
+        }))
+        .send();
+    let in_flight = tokio::spawn(in_flight);
+    sleep(Duration::from_millis(100)).await;
+
+    let res = client
+        .post(format!("http://127.0.0.1:{}/mcp", port))
+        .header("mcp-session-id", &session_id)
+        .json(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/cancelled",
+            "params": {"requestId": 2}
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(res.status(), reqwest::StatusCode::ACCEPTED);
+
+    let res = tokio::time::timeout(Duration::from_secs(2), in_flight)
+        .await
+        .expect("Cancelled request was not answered")
+        .unwrap()
+        .expect("Cancelled request was dropped");
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+    let body: serde_json::Value = res.json().await.expect("Failed to parse json");
+    assert_eq!(body["id"], 2);
+    assert_eq!(body["error"]["code"], -32800);
+}
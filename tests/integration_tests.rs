@@ -2,9 +2,38 @@ use serde_json::json;
 use std::io::Write;
 use std::process::{Command, Stdio};
 
+/// Performs the initialize handshake the server requires before any other
+/// request.
+fn initialize(stdin: &mut impl Write) {
+    let request = json!({
+        "jsonrpc": "2.0",
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2025-06-18",
+            "capabilities": {},
+            "clientInfo": {"name": "integration-tests", "version": "1.0.0"}
+        },
+        "id": 0
+    });
+    writeln!(stdin, "{}", request).unwrap();
+    writeln!(
+        stdin,
+        "{}",
+        json!({"jsonrpc": "2.0", "method": "notifications/initialized"})
+    )
+    .unwrap();
+}
+
+/// The last JSON line the server wrote, i.e. the answer to the request sent
+/// after the handshake.
+fn last_response(stdout: &[u8]) -> serde_json::Value {
+    let stdout = String::from_utf8_lossy(stdout);
+    serde_json::from_str(stdout.lines().last().expect("Server wrote nothing")).unwrap()
+}
+
 #[tokio::test]
 async fn test_stdio_get_current_time() {
-    let mut child = Command::new("./target/release/time-mcp-server")
+    let mut child = Command::new(env!("CARGO_BIN_EXE_time-mcp-server"))
         .arg("--transport=stdio")
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
@@ -12,6 +41,7 @@ async fn test_stdio_get_current_time() {
         .expect("Failed to start server");
 
     let stdin = child.stdin.as_mut().unwrap();
+    initialize(stdin);
     let request = json!({
         "jsonrpc": "2.0",
         "method": "tools/call",
@@ -26,7 +56,7 @@ async fn test_stdio_get_current_time() {
     stdin.flush().unwrap();
 
     let output = child.wait_with_output().unwrap();
-    let response: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let response = last_response(&output.stdout);
 
     assert_eq!(response["jsonrpc"], "2.0");
     assert_eq!(response["id"], 1);
@@ -65,7 +95,7 @@ async fn test_stdio_all_tools() {
     ];
 
     for (tool_name, args) in tools {
-        let mut child = Command::new("./target/release/time-mcp-server")
+        let mut child = Command::new(env!("CARGO_BIN_EXE_time-mcp-server"))
             .arg("--transport=stdio")
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
@@ -73,6 +103,7 @@ async fn test_stdio_all_tools() {
             .expect("Failed to start server");
 
         let stdin = child.stdin.as_mut().unwrap();
+        initialize(stdin);
         let request = json!({
             "jsonrpc": "2.0",
             "method": "tools/call",
@@ -87,7 +118,7 @@ async fn test_stdio_all_tools() {
         stdin.flush().unwrap();
 
         let output = child.wait_with_output().unwrap();
-        let response: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let response = last_response(&output.stdout);
 
         assert_eq!(response["jsonrpc"], "2.0", "Tool {} failed", tool_name);
         assert_eq!(response["id"], 1);
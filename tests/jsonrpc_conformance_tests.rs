@@ -0,0 +1,380 @@
+use serde_json::{json, Value};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use time_mcp_server::handlers::dispatcher::Dispatcher;
+use time_mcp_server::session::Session;
+
+const INITIALIZE: &str = r#"{"jsonrpc":"2.0","id":"init","method":"initialize","params":{"protocolVersion":"2025-06-18","capabilities":{}}}"#;
+const INITIALIZED: &str = r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#;
+
+/// Initializes a stdio server, feeds it `lines`, closes stdin and returns
+/// every JSON line it wrote back after the initialize response.
+fn run_stdio(lines: &[&str]) -> Vec<Value> {
+    let mut output = run_stdio_raw(&[&[INITIALIZE, INITIALIZED], lines].concat());
+    assert_eq!(output.remove(0)["id"], "init");
+    output
+}
+
+/// Feeds `lines` to a stdio server as-is, without the initialize handshake.
+fn run_stdio_raw(lines: &[&str]) -> Vec<Value> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_time-mcp-server"))
+        .arg("--transport=stdio")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("Failed to start server");
+
+    let mut stdin = child.stdin.take().unwrap();
+    for line in lines {
+        writeln!(stdin, "{}", line).unwrap();
+    }
+    drop(stdin);
+
+    let output = child.wait_with_output().unwrap();
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("Server wrote invalid JSON"))
+        .collect()
+}
+
+/// A session that has completed the initialize handshake.
+async fn ready_session(dispatcher: &Dispatcher) -> Session {
+    let session = Session::new();
+    dispatcher
+        .handle_message(
+            &session,
+            None,
+            json!({"jsonrpc": "2.0", "id": 0, "method": "initialize", "params": {}}),
+        )
+        .await;
+    dispatcher
+        .handle_message(
+            &session,
+            None,
+            json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+        )
+        .await;
+    session
+}
+
+async fn handle(message: Value) -> Option<Value> {
+    let dispatcher = Dispatcher::new();
+    let session = ready_session(&dispatcher).await;
+    dispatcher.handle_message(&session, None, message).await
+}
+
+async fn handle_error(message: Value) -> Value {
+    let response = handle(message).await.expect("Expected an error response");
+    assert_eq!(response["jsonrpc"], "2.0");
+    assert!(response.get("result").is_none());
+    response
+}
+
+#[tokio::test]
+async fn test_request_echoes_string_and_number_ids() {
+    for id in [json!(1), json!("req-1"), json!(-5), json!(1.5)] {
+        let response = handle(json!({"jsonrpc": "2.0", "id": id, "method": "tools/list"}))
+            .await
+            .unwrap();
+
+        assert_eq!(response["id"], id);
+        assert!(response["result"]["tools"].is_array());
+    }
+}
+
+#[tokio::test]
+async fn test_missing_jsonrpc_version_is_invalid_request() {
+    let response = handle_error(json!({"id": 1, "method": "tools/list"})).await;
+
+    assert_eq!(response["error"]["code"], -32600);
+    assert_eq!(response["id"], 1);
+}
+
+#[tokio::test]
+async fn test_wrong_jsonrpc_version_is_invalid_request() {
+    let response = handle_error(json!({"jsonrpc": "1.0", "id": 2, "method": "tools/list"})).await;
+
+    assert_eq!(response["error"]["code"], -32600);
+    assert_eq!(response["id"], 2);
+}
+
+#[tokio::test]
+async fn test_invalid_id_types_are_invalid_requests() {
+    for id in [json!(null), json!(true), json!({"a": 1}), json!([1])] {
+        let response =
+            handle_error(json!({"jsonrpc": "2.0", "id": id, "method": "tools/list"})).await;
+
+        assert_eq!(response["error"]["code"], -32600);
+        assert_eq!(response["id"], Value::Null);
+    }
+}
+
+#[tokio::test]
+async fn test_missing_or_non_string_method_is_invalid_request() {
+    let response = handle_error(json!({"jsonrpc": "2.0", "id": 3})).await;
+    assert_eq!(response["error"]["code"], -32600);
+    assert_eq!(response["id"], 3);
+
+    let response = handle_error(json!({"jsonrpc": "2.0", "id": 4, "method": 1})).await;
+    assert_eq!(response["error"]["code"], -32600);
+}
+
+#[tokio::test]
+async fn test_scalar_params_are_invalid_request() {
+    let response = handle_error(json!({
+        "jsonrpc": "2.0",
+        "id": 5,
+        "method": "tools/list",
+        "params": "bar"
+    }))
+    .await;
+
+    assert_eq!(response["error"]["code"], -32600);
+}
+
+#[tokio::test]
+async fn test_non_object_message_is_invalid_request() {
+    let response = handle_error(json!(1)).await;
+
+    assert_eq!(response["error"]["code"], -32600);
+    assert_eq!(response["id"], Value::Null);
+}
+
+#[tokio::test]
+async fn test_unknown_method_is_method_not_found() {
+    let response = handle_error(json!({"jsonrpc": "2.0", "id": 6, "method": "foo/bar"})).await;
+
+    assert_eq!(response["error"]["code"], -32601);
+    assert_eq!(response["id"], 6);
+}
+
+#[tokio::test]
+async fn test_notifications_get_no_response() {
+    assert!(
+        handle(json!({"jsonrpc": "2.0", "method": "notifications/initialized"}))
+            .await
+            .is_none()
+    );
+    assert!(handle(json!({"jsonrpc": "2.0", "method": "foo/bar"}))
+        .await
+        .is_none());
+    assert!(handle(json!({
+        "jsonrpc": "2.0",
+        "method": "tools/call",
+        "params": {"name": "get_current_time"}
+    }))
+    .await
+    .is_none());
+}
+
+#[tokio::test]
+async fn test_client_responses_get_no_response() {
+    assert!(handle(json!({"jsonrpc": "2.0", "id": 1, "result": {}}))
+        .await
+        .is_none());
+    assert!(handle(json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "error": {"code": -32601, "message": "Method not found"}
+    }))
+    .await
+    .is_none());
+}
+
+#[tokio::test]
+async fn test_batch_returns_responses_for_requests_only() {
+    let response = handle(json!([
+        {"jsonrpc": "2.0", "id": 1, "method": "tools/list"},
+        {"jsonrpc": "2.0", "method": "notifications/initialized"},
+        {"jsonrpc": "2.0", "id": "two", "method": "prompts/list"},
+        {"foo": "boo"},
+        {"jsonrpc": "2.0", "id": 3, "method": "foo/bar"}
+    ]))
+    .await
+    .unwrap();
+
+    let responses = response.as_array().unwrap();
+    assert_eq!(responses.len(), 4);
+    assert_eq!(responses[0]["id"], 1);
+    assert!(responses[0]["result"]["tools"].is_array());
+    assert_eq!(responses[1]["id"], "two");
+    assert!(responses[1]["result"]["prompts"].is_array());
+    assert_eq!(responses[2]["error"]["code"], -32600);
+    assert_eq!(responses[3]["error"]["code"], -32601);
+}
+
+#[tokio::test]
+async fn test_empty_batch_is_invalid_request() {
+    let response = handle_error(json!([])).await;
+
+    assert_eq!(response["error"]["code"], -32600);
+    assert_eq!(response["id"], Value::Null);
+}
+
+#[tokio::test]
+async fn test_batch_of_invalid_entries_answers_each() {
+    let response = handle(json!([1, 2])).await.unwrap();
+    let responses = response.as_array().unwrap();
+
+    assert_eq!(responses.len(), 2);
+    assert!(responses.iter().all(|r| r["error"]["code"] == -32600));
+}
+
+#[tokio::test]
+async fn test_batch_of_notifications_gets_no_response() {
+    let response = handle(json!([
+        {"jsonrpc": "2.0", "method": "notifications/initialized"},
+        {"jsonrpc": "2.0", "method": "foo/bar"}
+    ]))
+    .await;
+
+    assert!(response.is_none());
+}
+
+#[tokio::test]
+async fn test_raw_parse_error() {
+    let response = Dispatcher::new()
+        .handle_raw(
+            &Session::new(),
+            None,
+            r#"{"jsonrpc": "2.0", "method": "foobar, "params": "bar", "baz]"#,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response["error"]["code"], -32700);
+    assert_eq!(response["id"], Value::Null);
+}
+
+#[tokio::test]
+async fn test_raw_invalid_batch_json_is_parse_error() {
+    let response = Dispatcher::new()
+        .handle_raw(
+            &Session::new(), None,
+            r#"[{"jsonrpc": "2.0", "method": "tools/list", "id": "1"},{"jsonrpc": "2.0", "method"]"#,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response["error"]["code"], -32700);
+}
+
+#[test]
+fn test_stdio_answers_parse_errors_and_keeps_running() {
+    let responses = run_stdio(&[
+        "this is not json",
+        r#"{"jsonrpc":"2.0","id":1,"method":"tools/list"}"#,
+    ]);
+
+    assert_eq!(responses.len(), 2);
+    assert_eq!(responses[0]["error"]["code"], -32700);
+    assert_eq!(responses[0]["id"], Value::Null);
+    assert_eq!(responses[1]["id"], 1);
+    assert!(responses[1]["result"]["tools"].is_array());
+}
+
+#[test]
+fn test_stdio_invalid_request_and_notification() {
+    let responses = run_stdio(&[
+        r#"{"id":1,"method":"tools/list"}"#,
+        r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#,
+        r#"{"jsonrpc":"2.0","id":2,"method":"prompts/list"}"#,
+    ]);
+
+    assert_eq!(responses.len(), 2);
+    assert_eq!(responses[0]["id"], 1);
+    assert_eq!(responses[0]["error"]["code"], -32600);
+    assert_eq!(responses[1]["id"], 2);
+}
+
+#[test]
+fn test_stdio_batch() {
+    let responses = run_stdio(&[
+        r#"[{"jsonrpc":"2.0","id":1,"method":"tools/list"},{"jsonrpc":"2.0","method":"notifications/initialized"},{"jsonrpc":"2.0","id":2,"method":"foo"}]"#,
+    ]);
+
+    assert_eq!(responses.len(), 1);
+    let batch = responses[0].as_array().unwrap();
+    assert_eq!(batch.len(), 2);
+    assert_eq!(batch[0]["id"], 1);
+    assert_eq!(batch[1]["error"]["code"], -32601);
+}
+
+#[test]
+fn test_stdio_ping_and_set_level() {
+    let responses = run_stdio(&[
+        r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#,
+        r#"{"jsonrpc":"2.0","id":2,"method":"logging/setLevel","params":{"level":"info"}}"#,
+    ]);
+
+    let ping = responses.iter().find(|r| r["id"] == 1).unwrap();
+    assert_eq!(ping["result"], json!({}));
+
+    let set_level = responses.iter().find(|r| r["id"] == 2).unwrap();
+    assert_eq!(set_level["result"], json!({}));
+
+    assert!(responses
+        .iter()
+        .any(|r| r["method"] == "notifications/message" && r["params"]["level"] == "info"));
+}
+
+#[tokio::test]
+async fn test_requests_before_initialize_are_rejected() {
+    let dispatcher = Dispatcher::new();
+    let session = Session::new();
+
+    let response = dispatcher
+        .handle_message(
+            &session,
+            None,
+            json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"}),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response["error"]["code"], -32600);
+    assert_eq!(response["id"], 1);
+
+    let response = dispatcher
+        .handle_message(
+            &session,
+            None,
+            json!({"jsonrpc": "2.0", "id": 2, "method": "ping"}),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response["result"], json!({}));
+}
+
+#[tokio::test]
+async fn test_second_initialize_is_rejected() {
+    let dispatcher = Dispatcher::new();
+    let session = ready_session(&dispatcher).await;
+
+    let response = dispatcher
+        .handle_message(
+            &session,
+            None,
+            json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}}),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response["error"]["code"], -32600);
+}
+
+#[test]
+fn test_stdio_rejects_requests_before_initialize() {
+    let responses = run_stdio_raw(&[
+        r#"{"jsonrpc":"2.0","id":1,"method":"tools/list"}"#,
+        INITIALIZE,
+        INITIALIZED,
+        r#"{"jsonrpc":"2.0","id":2,"method":"tools/list"}"#,
+    ]);
+
+    let early = responses.iter().find(|r| r["id"] == 1).unwrap();
+    assert_eq!(early["error"]["code"], -32600);
+
+    let late = responses.iter().find(|r| r["id"] == 2).unwrap();
+    assert!(late["result"]["tools"].is_array());
+}
@@ -1,20 +1,36 @@
+use std::net::TcpListener;
 use std::process::Command;
 use std::time::Duration;
 use tokio::time::sleep;
 
 #[tokio::test]
 async fn test_main_http_transport() {
-    let mut child = Command::new("cargo")
-        .args(&["run", "--", "--transport", "http", "--port", "8081"])
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+        .to_string();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_time-mcp-server"))
+        .args([
+            "--transport",
+            "http",
+            "--host",
+            "127.0.0.1",
+            "--port",
+            &port,
+        ])
         .spawn()
         .expect("Failed to start server");
 
+    let url = format!("http://127.0.0.1:{}", port);
     let start_time = std::time::Instant::now();
     let timeout = Duration::from_secs(10);
     let mut connected = false;
 
     while start_time.elapsed() < timeout {
-        if let Ok(res) = reqwest::get("http://127.0.0.1:8081").await {
+        if let Ok(res) = reqwest::get(&url).await {
             if res.status().is_success() {
                 connected = true;
                 break;
@@ -24,12 +40,14 @@ async fn test_main_http_transport() {
     }
 
     if !connected {
+        child.kill().expect("Failed to kill server");
+        child.wait().expect("Failed to wait for server");
         panic!("Failed to connect to server within 10 seconds");
     }
 
     let client = reqwest::Client::new();
     let res = client
-        .get("http://127.0.0.1:8081")
+        .get(&url)
         .send()
         .await
         .expect("Failed to send request");
@@ -37,4 +55,192 @@ async fn test_main_http_transport() {
     assert_eq!(res.status(), reqwest::StatusCode::OK);
 
     child.kill().expect("Failed to kill server");
+    child.wait().expect("Failed to wait for server");
+}
+
+/// Sends SIGTERM to `child` and waits for it, failing if it takes longer than
+/// `limit` to exit.
+#[cfg(unix)]
+fn terminate(child: &mut std::process::Child, limit: Duration) -> std::process::Output {
+    let status = Command::new("kill")
+        .args(["-TERM", &child.id().to_string()])
+        .status()
+        .expect("Failed to run kill");
+    assert!(status.success());
+
+    let start_time = std::time::Instant::now();
+    while child.try_wait().unwrap().is_none() {
+        if start_time.elapsed() > limit {
+            child.kill().expect("Failed to kill server");
+            panic!("Server did not exit within {:?} of SIGTERM", limit);
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+
+    let mut stderr = String::new();
+    if let Some(mut pipe) = child.stderr.take() {
+        std::io::Read::read_to_string(&mut pipe, &mut stderr).unwrap();
+    }
+    std::process::Output {
+        status: child.wait().unwrap(),
+        stdout: Vec::new(),
+        stderr: stderr.into_bytes(),
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_main_stdio_exits_cleanly_on_sigterm() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_time-mcp-server"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to start server");
+
+    // Keep stdin open so only the signal can stop the server.
+    let mut stdin = child.stdin.take().unwrap();
+    writeln!(
+        stdin,
+        r#"{{"jsonrpc":"2.0","id":1,"method":"initialize","params":{{}}}}"#
+    )
+    .unwrap();
+    std::thread::sleep(Duration::from_millis(500));
+
+    let output = terminate(&mut child, Duration::from_secs(10));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "exit status: {}", output.status);
+    assert!(stderr.contains("Received SIGTERM, shutting down gracefully"));
+    assert!(stderr.contains("Time MCP Server stopped"));
+    drop(stdin);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_main_http_exits_cleanly_on_sigterm() {
+    use std::process::Stdio;
+
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+        .to_string();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_time-mcp-server"))
+        .args([
+            "--transport",
+            "http",
+            "--host",
+            "127.0.0.1",
+            "--port",
+            &port,
+        ])
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to start server");
+
+    let url = format!("http://127.0.0.1:{}/health", port);
+    let start_time = std::time::Instant::now();
+    while reqwest::get(&url).await.is_err() {
+        assert!(
+            start_time.elapsed() < Duration::from_secs(10),
+            "Server did not start"
+        );
+        sleep(Duration::from_millis(100)).await;
+    }
+
+    let output = terminate(&mut child, Duration::from_secs(10));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "exit status: {}", output.status);
+    assert!(stderr.contains("HTTP server stopped"));
+    assert!(stderr.contains("Time MCP Server stopped"));
+}
+
+#[tokio::test]
+async fn test_main_reloads_api_keys_on_sighup() {
+    use std::process::Stdio;
+
+    fn write_keys(path: &std::path::Path, hash: &str) {
+        let keys = serde_json::json!({"keys": [{"owner": "ci", "hash": hash}]});
+        std::fs::write(path, keys.to_string()).unwrap();
+    }
+
+    // SHA-256 of "second-key" and "first-key".
+    const SECOND: &str = "sha256:f397f260a275cc4d42e7965c556167bf3f068aed491d35a8f5b38c8c2db96bb0";
+    const FIRST: &str = "sha256:9e24b55356ef2a121ab5fd1a3ee8a3c9191e837f3853e399a369e9044ff183f7";
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("keys.json");
+    write_keys(&path, FIRST);
+
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+        .to_string();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_time-mcp-server"))
+        .args([
+            "--transport",
+            "http",
+            "--host",
+            "127.0.0.1",
+            "--port",
+            &port,
+        ])
+        .env("OAUTH_ENABLED", "true")
+        .env("API_KEYS_FILE", &path)
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to start server");
+
+    let url = format!("http://127.0.0.1:{}/health", port);
+    let start_time = std::time::Instant::now();
+    while reqwest::get(&url).await.is_err() {
+        assert!(
+            start_time.elapsed() < Duration::from_secs(10),
+            "Server did not start"
+        );
+        sleep(Duration::from_millis(100)).await;
+    }
+
+    let client = reqwest::Client::new();
+    let status = |key: &'static str| {
+        let request = client
+            .get(format!("http://127.0.0.1:{}/mcp/capabilities", port))
+            .header("X-API-Key", key);
+        async move { request.send().await.unwrap().status() }
+    };
+    assert_eq!(status("first-key").await, reqwest::StatusCode::OK);
+    assert_eq!(
+        status("second-key").await,
+        reqwest::StatusCode::UNAUTHORIZED
+    );
+
+    write_keys(&path, SECOND);
+    let hangup = Command::new("kill")
+        .args(["-HUP", &child.id().to_string()])
+        .status()
+        .expect("Failed to run kill");
+    assert!(hangup.success());
+
+    let start_time = std::time::Instant::now();
+    while status("second-key").await != reqwest::StatusCode::OK {
+        assert!(
+            start_time.elapsed() < Duration::from_secs(10),
+            "API keys were not reloaded"
+        );
+        sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(status("first-key").await, reqwest::StatusCode::UNAUTHORIZED);
+
+    let output = terminate(&mut child, Duration::from_secs(10));
+    assert!(output.status.success(), "exit status: {}", output.status);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Loaded 1 API keys"));
 }
@@ -1,13 +1,63 @@
 use axum::http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
 use serde_json::{json, Value};
+use std::sync::{Mutex, MutexGuard};
 use std::time::{Duration, SystemTime};
 use time_mcp_server::{
     auth::AuthManager,
-    config::{ServerConfig, TransportType},
-    models::{McpError, McpResponse, TokenInfo},
-    tools::TimeTools,
+    completion,
+    config::{
+        ServerConfig, TransportType, DEFAULT_MAX_IN_FLIGHT, DEFAULT_MAX_SESSIONS,
+        DEFAULT_SESSION_IDLE_TIMEOUT,
+    },
+    handlers::{dispatcher::Dispatcher, stdio::StdioHandler},
+    logging::{LogLevel, McpLogLayer},
+    models::{ClientInfo, McpError, McpResponse, McpResult, TokenInfo},
+    pagination,
+    protocol::ProtocolVersion,
+    registry::{Tool, ToolContext, ToolRegistry},
+    session::{Session, SessionState},
+    shutdown::{self, Drain, Shutdown},
+    tools::{
+        CalculateDurationArgs, CalculateDurationTool, ConvertTimezoneArgs, ConvertTimezoneTool,
+        CurrentTimeFormat, DurationUnits, FormatTimeArgs, FormatTimeTool, GetCurrentTimeArgs,
+        GetCurrentTimeTool, GetTimezoneInfoArgs, GetTimezoneInfoTool, ListTimezonesArgs,
+        ListTimezonesTool, TimeError, TimeTools, TimestampFormat, TimestampValue,
+        DEFAULT_TIMEZONE_PAGE_SIZE,
+    },
 };
 
+/// Held by tests that set environment variables or read configuration from
+/// them, since the environment is shared by every test in the process.
+static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+fn env_lock() -> MutexGuard<'static, ()> {
+    ENV_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// A session that has completed the initialize handshake, as the dispatcher
+/// requires before serving other requests.
+async fn ready_session(dispatcher: &Dispatcher) -> Session {
+    let session = Session::new();
+    dispatcher
+        .handle_message(
+            &session,
+            None,
+            json!({"jsonrpc": "2.0", "id": 0, "method": "initialize", "params": {}}),
+        )
+        .await
+        .expect("initialize should be answered");
+    dispatcher
+        .handle_message(
+            &session,
+            None,
+            json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+        )
+        .await;
+    session
+}
+
 #[cfg(test)]
 mod time_tools_tests {
     use super::*;
@@ -15,11 +65,10 @@ mod time_tools_tests {
     #[tokio::test]
     async fn test_get_current_time_default_params() {
         let args = json!({});
-        let result = TimeTools::get_current_time(args).await;
+        let result = GetCurrentTimeTool.execute(args).await;
 
         assert!(result.is_ok());
-        let response_str = result.unwrap();
-        let response: Value = serde_json::from_str(&response_str).unwrap();
+        let response = result.unwrap();
 
         assert!(response.get("timestamp").is_some());
         assert_eq!(response.get("timezone").unwrap().as_str().unwrap(), "UTC");
@@ -32,11 +81,10 @@ mod time_tools_tests {
             "format": "iso"
         });
 
-        let result = TimeTools::get_current_time(args).await;
+        let result = GetCurrentTimeTool.execute(args).await;
 
         assert!(result.is_ok());
-        let response_str = result.unwrap();
-        let response: Value = serde_json::from_str(&response_str).unwrap();
+        let response = result.unwrap();
 
         assert!(response.get("timestamp").is_some());
         assert!(response.get("unix").is_some());
@@ -54,11 +102,10 @@ mod time_tools_tests {
             "timezone": "UTC"
         });
 
-        let result = TimeTools::get_current_time(args).await;
+        let result = GetCurrentTimeTool.execute(args).await;
 
         assert!(result.is_ok());
-        let response_str = result.unwrap();
-        let response: Value = serde_json::from_str(&response_str).unwrap();
+        let response = result.unwrap();
 
         assert!(response.get("timestamp").is_some());
         assert!(response.get("timestamp").unwrap().is_i64());
@@ -73,11 +120,10 @@ mod time_tools_tests {
             "timezone": "UTC"
         });
 
-        let result = TimeTools::get_current_time(args).await;
+        let result = GetCurrentTimeTool.execute(args).await;
 
         assert!(result.is_ok());
-        let response_str = result.unwrap();
-        let response: Value = serde_json::from_str(&response_str).unwrap();
+        let response = result.unwrap();
 
         assert!(response.get("formatted").is_some());
         let formatted = response.get("formatted").unwrap().as_str().unwrap();
@@ -95,11 +141,10 @@ mod time_tools_tests {
             "timezone": "UTC"
         });
 
-        let result = TimeTools::get_current_time(args).await;
+        let result = GetCurrentTimeTool.execute(args).await;
 
         assert!(result.is_ok());
-        let response_str = result.unwrap();
-        let response: Value = serde_json::from_str(&response_str).unwrap();
+        let response = result.unwrap();
 
         assert!(response.get("formatted").is_some());
     }
@@ -111,7 +156,7 @@ mod time_tools_tests {
             "format": "iso"
         });
 
-        let result = TimeTools::get_current_time(args).await;
+        let result = GetCurrentTimeTool.execute(args).await;
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("Invalid timezone"));
     }
@@ -123,7 +168,7 @@ mod time_tools_tests {
             "timezone": "UTC"
         });
 
-        let result = TimeTools::get_current_time(args).await;
+        let result = GetCurrentTimeTool.execute(args).await;
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("Invalid format"));
     }
@@ -135,7 +180,7 @@ mod time_tools_tests {
             "timezone": "UTC"
         });
 
-        let result = TimeTools::get_current_time(args).await;
+        let result = GetCurrentTimeTool.execute(args).await;
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
@@ -151,11 +196,10 @@ mod time_tools_tests {
             "to_timezone": "America/New_York"
         });
 
-        let result = TimeTools::convert_timezone(args).await;
+        let result = ConvertTimezoneTool.execute(args).await;
 
         assert!(result.is_ok());
-        let response_str = result.unwrap();
-        let response: Value = serde_json::from_str(&response_str).unwrap();
+        let response = result.unwrap();
 
         assert!(response.get("original").is_some());
         assert!(response.get("converted").is_some());
@@ -178,7 +222,7 @@ mod time_tools_tests {
             "to_timezone": "Europe/London"
         });
 
-        let result = TimeTools::convert_timezone(args).await;
+        let result = ConvertTimezoneTool.execute(args).await;
         assert!(result.is_ok());
     }
 
@@ -190,7 +234,7 @@ mod time_tools_tests {
             // missing to_timezone
         });
 
-        let result = TimeTools::convert_timezone(args).await;
+        let result = ConvertTimezoneTool.execute(args).await;
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
@@ -206,7 +250,7 @@ mod time_tools_tests {
             "to_timezone": "America/New_York"
         });
 
-        let result = TimeTools::convert_timezone(args).await;
+        let result = ConvertTimezoneTool.execute(args).await;
         assert!(result.is_err());
     }
 
@@ -218,11 +262,10 @@ mod time_tools_tests {
             "units": "seconds"
         });
 
-        let result = TimeTools::calculate_duration(args).await;
+        let result = CalculateDurationTool.execute(args).await;
 
         assert!(result.is_ok());
-        let response_str = result.unwrap();
-        let response: Value = serde_json::from_str(&response_str).unwrap();
+        let response = result.unwrap();
 
         let duration = response.get("duration").unwrap();
         assert_eq!(
@@ -241,11 +284,10 @@ mod time_tools_tests {
             "units": "seconds"
         });
 
-        let result = TimeTools::calculate_duration(args).await;
+        let result = CalculateDurationTool.execute(args).await;
 
         assert!(result.is_ok());
-        let response_str = result.unwrap();
-        let response: Value = serde_json::from_str(&response_str).unwrap();
+        let response = result.unwrap();
 
         let duration = response.get("duration").unwrap();
         assert_eq!(
@@ -262,11 +304,10 @@ mod time_tools_tests {
             "units": "hours"
         });
 
-        let result = TimeTools::calculate_duration(args).await;
+        let result = CalculateDurationTool.execute(args).await;
 
         assert!(result.is_ok());
-        let response_str = result.unwrap();
-        let response: Value = serde_json::from_str(&response_str).unwrap();
+        let response = result.unwrap();
 
         let duration = response.get("duration").unwrap();
         assert_eq!(duration.get("hours").unwrap().as_i64().unwrap(), 2);
@@ -286,11 +327,10 @@ mod time_tools_tests {
             "units": "minutes"
         });
 
-        let result = TimeTools::calculate_duration(args).await;
+        let result = CalculateDurationTool.execute(args).await;
 
         assert!(result.is_ok());
-        let response_str = result.unwrap();
-        let response: Value = serde_json::from_str(&response_str).unwrap();
+        let response = result.unwrap();
 
         let duration = response.get("duration").unwrap();
         assert_eq!(duration.get("minutes").unwrap().as_i64().unwrap(), 2880);
@@ -301,11 +341,10 @@ mod time_tools_tests {
             "units": "days"
         });
 
-        let result = TimeTools::calculate_duration(args).await;
+        let result = CalculateDurationTool.execute(args).await;
 
         assert!(result.is_ok());
-        let response_str = result.unwrap();
-        let response: Value = serde_json::from_str(&response_str).unwrap();
+        let response = result.unwrap();
 
         let duration = response.get("duration").unwrap();
         assert_eq!(duration.get("days").unwrap().as_i64().unwrap(), 2);
@@ -318,7 +357,7 @@ mod time_tools_tests {
             "units": "invalid_units"
         });
 
-        let result = TimeTools::calculate_duration(args).await;
+        let result = CalculateDurationTool.execute(args).await;
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("Invalid units"));
     }
@@ -331,11 +370,10 @@ mod time_tools_tests {
             "timezone": "UTC"
         });
 
-        let result = TimeTools::format_time(args).await;
+        let result = FormatTimeTool.execute(args).await;
 
         assert!(result.is_ok());
-        let response_str = result.unwrap();
-        let response: Value = serde_json::from_str(&response_str).unwrap();
+        let response = result.unwrap();
 
         assert!(response.get("formatted").is_some());
         let formatted = response.get("formatted").unwrap().as_str().unwrap();
@@ -350,11 +388,10 @@ mod time_tools_tests {
             "timezone": "UTC"
         });
 
-        let result = TimeTools::format_time(args).await;
+        let result = FormatTimeTool.execute(args).await;
 
         assert!(result.is_ok());
-        let response_str = result.unwrap();
-        let response: Value = serde_json::from_str(&response_str).unwrap();
+        let response = result.unwrap();
 
         assert!(response.get("formatted").is_some());
         let formatted = response.get("formatted").unwrap().as_str().unwrap();
@@ -369,11 +406,10 @@ mod time_tools_tests {
             "timezone": "UTC"
         });
 
-        let result = TimeTools::format_time(args).await;
+        let result = FormatTimeTool.execute(args).await;
 
         assert!(result.is_ok());
-        let response_str = result.unwrap();
-        let response: Value = serde_json::from_str(&response_str).unwrap();
+        let response = result.unwrap();
 
         let formatted = response.get("formatted").unwrap().as_str().unwrap();
         assert!(formatted.parse::<i64>().is_ok());
@@ -388,11 +424,10 @@ mod time_tools_tests {
             "timezone": "UTC"
         });
 
-        let result = TimeTools::format_time(args).await;
+        let result = FormatTimeTool.execute(args).await;
 
         assert!(result.is_ok());
-        let response_str = result.unwrap();
-        let response: Value = serde_json::from_str(&response_str).unwrap();
+        let response = result.unwrap();
 
         let formatted = response.get("formatted").unwrap().as_str().unwrap();
         assert_eq!(formatted, "January 01, 2023");
@@ -404,18 +439,14 @@ mod time_tools_tests {
             "timezone": "UTC"
         });
 
-        let result = TimeTools::get_timezone_info(args).await;
+        let result = GetTimezoneInfoTool.execute(args).await;
 
         assert!(result.is_ok());
-        let response_str = result.unwrap();
-        let response: Value = serde_json::from_str(&response_str).unwrap();
+        let response = result.unwrap();
 
         assert_eq!(response.get("timezone").unwrap().as_str().unwrap(), "UTC");
         assert_eq!(response.get("offset").unwrap().as_str().unwrap(), "+00:00");
-        assert_eq!(
-            response.get("dst_active").unwrap().as_bool().unwrap(),
-            false
-        );
+        assert!(!response.get("dst_active").unwrap().as_bool().unwrap());
     }
 
     #[tokio::test]
@@ -424,11 +455,10 @@ mod time_tools_tests {
             "timezone": "America/New_York"
         });
 
-        let result = TimeTools::get_timezone_info(args).await;
+        let result = GetTimezoneInfoTool.execute(args).await;
 
         assert!(result.is_ok());
-        let response_str = result.unwrap();
-        let response: Value = serde_json::from_str(&response_str).unwrap();
+        let response = result.unwrap();
 
         assert_eq!(
             response.get("timezone").unwrap().as_str().unwrap(),
@@ -443,17 +473,16 @@ mod time_tools_tests {
     async fn test_list_timezones_all() {
         let args = json!({});
 
-        let result = TimeTools::list_timezones(args).await;
+        let result = ListTimezonesTool.execute(args).await;
 
         assert!(result.is_ok());
-        let response_str = result.unwrap();
-        let response: Value = serde_json::from_str(&response_str).unwrap();
+        let response = result.unwrap();
 
         assert!(response.get("timezones").is_some());
         assert!(response.get("count").is_some());
 
         let timezones = response.get("timezones").unwrap().as_array().unwrap();
-        assert!(timezones.len() > 0);
+        assert!(!timezones.is_empty());
         assert!(response.get("count").unwrap().as_u64().unwrap() == timezones.len() as u64);
     }
 
@@ -463,11 +492,10 @@ mod time_tools_tests {
             "region": "America"
         });
 
-        let result = TimeTools::list_timezones(args).await;
+        let result = ListTimezonesTool.execute(args).await;
 
         assert!(result.is_ok());
-        let response_str = result.unwrap();
-        let response: Value = serde_json::from_str(&response_str).unwrap();
+        let response = result.unwrap();
 
         let timezones = response.get("timezones").unwrap().as_array().unwrap();
 
@@ -486,6 +514,7 @@ mod config_tests {
 
     #[test]
     fn test_server_config_from_matches_stdio() {
+        let _env = env_lock();
         let app = Command::new("test")
             .disable_help_flag(true)
             .arg(Arg::new("transport").long("transport").required(true))
@@ -509,6 +538,7 @@ mod config_tests {
 
     #[test]
     fn test_server_config_from_matches_http() {
+        let _env = env_lock();
         let app = Command::new("test")
             .disable_help_flag(true)
             .arg(Arg::new("transport").long("transport").required(true))
@@ -541,8 +571,86 @@ mod config_tests {
         assert_eq!(config.port, 3000);
     }
 
+    #[test]
+    fn test_server_config_from_matches_ws() {
+        let _env = env_lock();
+        let app = Command::new("test")
+            .disable_help_flag(true)
+            .arg(Arg::new("transport").long("transport").required(true))
+            .arg(Arg::new("host").long("host"))
+            .arg(Arg::new("port").long("port"));
+
+        let matches = app
+            .try_get_matches_from(vec!["test", "--transport", "ws", "--port", "9000"])
+            .unwrap();
+
+        let config = ServerConfig::from_matches(&matches).unwrap();
+
+        match config.transport {
+            TransportType::WebSocket { host, port } => {
+                assert_eq!(host, "localhost");
+                assert_eq!(port, 9000);
+            }
+            _ => panic!("Expected WebSocket transport"),
+        }
+    }
+
+    #[test]
+    fn test_server_config_from_matches_unix() {
+        let _env = env_lock();
+        let app = Command::new("test")
+            .disable_help_flag(true)
+            .arg(Arg::new("transport").long("transport").required(true))
+            .arg(Arg::new("host").long("host"))
+            .arg(Arg::new("port").long("port"))
+            .arg(Arg::new("socket-path").long("socket-path"))
+            .arg(Arg::new("socket-mode").long("socket-mode"));
+
+        let matches = app
+            .clone()
+            .try_get_matches_from(vec![
+                "test",
+                "--transport",
+                "unix",
+                "--socket-path",
+                "/tmp/time.sock",
+                "--socket-mode",
+                "660",
+            ])
+            .unwrap();
+        let config = ServerConfig::from_matches(&matches).unwrap();
+
+        match config.transport {
+            TransportType::Unix { socket_path } => {
+                assert_eq!(socket_path, std::path::PathBuf::from("/tmp/time.sock"));
+            }
+            _ => panic!("Expected Unix transport"),
+        }
+        assert_eq!(config.socket_mode, 0o660);
+
+        let matches = app
+            .clone()
+            .try_get_matches_from(vec!["test", "--transport", "unix"])
+            .unwrap();
+        assert!(ServerConfig::from_matches(&matches).is_err());
+
+        let matches = app
+            .try_get_matches_from(vec![
+                "test",
+                "--transport",
+                "unix",
+                "--socket-path",
+                "/tmp/time.sock",
+                "--socket-mode",
+                "rw",
+            ])
+            .unwrap();
+        assert!(ServerConfig::from_matches(&matches).is_err());
+    }
+
     #[test]
     fn test_server_config_invalid_transport() {
+        let _env = env_lock();
         let app = Command::new("test")
             .disable_help_flag(true)
             .arg(Arg::new("transport").long("transport").required(true))
@@ -563,6 +671,7 @@ mod config_tests {
 
     #[test]
     fn test_server_config_auth_enabled() {
+        let _env = env_lock();
         std::env::set_var("OAUTH_ENABLED", "true");
 
         let app = Command::new("test")
@@ -581,6 +690,178 @@ mod config_tests {
         // Clean up
         std::env::remove_var("OAUTH_ENABLED");
     }
+
+    #[test]
+    fn test_server_config_max_in_flight() {
+        let _env = env_lock();
+        let app = Command::new("test")
+            .disable_help_flag(true)
+            .arg(Arg::new("transport").long("transport"))
+            .arg(Arg::new("host").long("host"))
+            .arg(Arg::new("port").long("port"))
+            .arg(Arg::new("max-in-flight").long("max-in-flight"));
+
+        let matches = app
+            .clone()
+            .try_get_matches_from(vec!["test", "--transport", "stdio", "--max-in-flight", "4"])
+            .unwrap();
+        assert_eq!(
+            ServerConfig::from_matches(&matches).unwrap().max_in_flight,
+            4
+        );
+
+        let matches = app
+            .try_get_matches_from(vec!["test", "--transport", "stdio", "--max-in-flight", "0"])
+            .unwrap();
+        assert!(ServerConfig::from_matches(&matches).is_err());
+    }
+
+    #[test]
+    fn test_server_config_shutdown_timeout() {
+        let _env = env_lock();
+        let app = Command::new("test")
+            .disable_help_flag(true)
+            .arg(Arg::new("transport").long("transport"))
+            .arg(Arg::new("host").long("host"))
+            .arg(Arg::new("port").long("port"))
+            .arg(Arg::new("shutdown-timeout").long("shutdown-timeout"));
+
+        let matches = app
+            .clone()
+            .try_get_matches_from(vec!["test", "--transport", "stdio"])
+            .unwrap();
+        assert_eq!(
+            ServerConfig::from_matches(&matches)
+                .unwrap()
+                .shutdown_timeout,
+            shutdown::DEFAULT_SHUTDOWN_TIMEOUT
+        );
+
+        let matches = app
+            .clone()
+            .try_get_matches_from(vec![
+                "test",
+                "--transport",
+                "stdio",
+                "--shutdown-timeout",
+                "5",
+            ])
+            .unwrap();
+        assert_eq!(
+            ServerConfig::from_matches(&matches)
+                .unwrap()
+                .shutdown_timeout,
+            Duration::from_secs(5)
+        );
+
+        let matches = app
+            .try_get_matches_from(vec![
+                "test",
+                "--transport",
+                "stdio",
+                "--shutdown-timeout",
+                "soon",
+            ])
+            .unwrap();
+        assert!(ServerConfig::from_matches(&matches).is_err());
+    }
+
+    #[test]
+    fn test_server_config_http_session_limits() {
+        let _env = env_lock();
+        let app = Command::new("test")
+            .disable_help_flag(true)
+            .arg(Arg::new("transport").long("transport"))
+            .arg(Arg::new("host").long("host"))
+            .arg(Arg::new("port").long("port"))
+            .arg(Arg::new("allowed-origins").long("allowed-origins"))
+            .arg(Arg::new("max-sessions").long("max-sessions"))
+            .arg(Arg::new("session-timeout").long("session-timeout"));
+
+        let matches = app
+            .clone()
+            .try_get_matches_from(vec!["test", "--transport", "http"])
+            .unwrap();
+        let config = ServerConfig::from_matches(&matches).unwrap();
+        assert_eq!(config.max_sessions, DEFAULT_MAX_SESSIONS);
+        assert_eq!(config.session_idle_timeout, DEFAULT_SESSION_IDLE_TIMEOUT);
+        assert!(!config
+            .allowed_origins
+            .allows(Some("https://app.example.com")));
+
+        let matches = app
+            .clone()
+            .try_get_matches_from(vec![
+                "test",
+                "--transport",
+                "http",
+                "--allowed-origins",
+                "https://app.example.com, https://admin.example.com",
+                "--max-sessions",
+                "10",
+                "--session-timeout",
+                "60",
+            ])
+            .unwrap();
+        let config = ServerConfig::from_matches(&matches).unwrap();
+        assert_eq!(config.max_sessions, 10);
+        assert_eq!(config.session_idle_timeout, Duration::from_secs(60));
+        assert!(config
+            .allowed_origins
+            .allows(Some("https://admin.example.com")));
+
+        let matches = app
+            .try_get_matches_from(vec!["test", "--transport", "http", "--max-sessions", "0"])
+            .unwrap();
+        assert!(ServerConfig::from_matches(&matches).is_err());
+    }
+}
+
+#[cfg(test)]
+mod origin_tests {
+    use super::*;
+    use time_mcp_server::handlers::origin::OriginPolicy;
+
+    #[test]
+    fn test_default_policy_allows_only_loopback() {
+        let policy = OriginPolicy::new();
+
+        assert!(policy.allows(None));
+        assert!(policy.allows(Some("http://localhost:3000")));
+        assert!(policy.allows(Some("http://127.0.0.1")));
+        assert!(policy.allows(Some("https://[::1]:8443")));
+        assert!(!policy.allows(Some("https://evil.example")));
+        assert!(!policy.allows(Some("http://localhost.evil.example")));
+        assert!(!policy.allows(Some("null")));
+    }
+
+    #[test]
+    fn test_listed_origins_are_allowed() {
+        let policy =
+            OriginPolicy::from_list("https://App.Example.com:443/, http://tools.example.com:8080");
+
+        assert!(policy.allows(Some("https://app.example.com")));
+        assert!(policy.allows(Some("http://tools.example.com:8080")));
+        assert!(!policy.allows(Some("http://tools.example.com")));
+        assert!(!policy.allows(Some("https://evil.example")));
+
+        assert!(OriginPolicy::new()
+            .with_origin("*")
+            .allows(Some("https://evil.example")));
+    }
+
+    #[test]
+    fn test_allows_request_reads_origin_header() {
+        let policy = OriginPolicy::new();
+        let mut headers = HeaderMap::new();
+        assert!(policy.allows_request(&headers));
+
+        headers.insert("origin", HeaderValue::from_static("https://evil.example"));
+        assert!(!policy.allows_request(&headers));
+
+        headers.insert("origin", HeaderValue::from_static("http://localhost:5173"));
+        assert!(policy.allows_request(&headers));
+    }
 }
 
 #[cfg(test)]
@@ -694,7 +975,7 @@ mod edge_cases_tests {
     #[tokio::test]
     async fn test_empty_json_arguments() {
         let args = json!(null);
-        let result = TimeTools::get_current_time(args).await;
+        let result = GetCurrentTimeTool.execute(args).await;
 
         // Should handle null arguments gracefully
         assert!(result.is_ok());
@@ -717,7 +998,7 @@ mod edge_cases_tests {
                 "to_timezone": "America/New_York"
             });
 
-            let result = TimeTools::convert_timezone(args).await;
+            let result = ConvertTimezoneTool.execute(args).await;
             assert!(result.is_err(), "Should fail for timestamp: {}", timestamp);
         }
     }
@@ -730,7 +1011,7 @@ mod edge_cases_tests {
             "from_timezone": "UTC",
             "to_timezone": "America/New_York"
         });
-        let result = TimeTools::convert_timezone(args).await;
+        let result = ConvertTimezoneTool.execute(args).await;
         assert!(result.is_ok());
 
         // Test far future timestamp (year 2038+)
@@ -739,7 +1020,7 @@ mod edge_cases_tests {
             "from_timezone": "UTC",
             "to_timezone": "America/New_York"
         });
-        let result = TimeTools::convert_timezone(args).await;
+        let result = ConvertTimezoneTool.execute(args).await;
         assert!(result.is_ok());
     }
 
@@ -759,7 +1040,7 @@ mod edge_cases_tests {
                 "timezone": tz
             });
 
-            let result = TimeTools::get_timezone_info(args).await;
+            let result = GetTimezoneInfoTool.execute(args).await;
             assert!(result.is_ok(), "Should work for timezone: {}", tz);
         }
     }
@@ -772,7 +1053,7 @@ mod edge_cases_tests {
             "to_timezone": "Europe/London"
         });
 
-        let result = TimeTools::convert_timezone(args).await;
+        let result = ConvertTimezoneTool.execute(args).await;
         assert!(result.is_ok());
     }
 
@@ -785,7 +1066,7 @@ mod edge_cases_tests {
             "to_timezone": "America/New_York"
         });
 
-        let result = TimeTools::convert_timezone(spring_forward).await;
+        let result = ConvertTimezoneTool.execute(spring_forward).await;
         assert!(result.is_ok());
 
         let fall_back = json!({
@@ -794,7 +1075,7 @@ mod edge_cases_tests {
             "to_timezone": "America/New_York"
         });
 
-        let result = TimeTools::convert_timezone(fall_back).await;
+        let result = ConvertTimezoneTool.execute(fall_back).await;
         assert!(result.is_ok());
     }
 
@@ -807,11 +1088,10 @@ mod edge_cases_tests {
             "units": "seconds"
         });
 
-        let result = TimeTools::calculate_duration(args).await;
+        let result = CalculateDurationTool.execute(args).await;
         assert!(result.is_ok());
 
-        let response_str = result.unwrap();
-        let response: Value = serde_json::from_str(&response_str).unwrap();
+        let response = result.unwrap();
         let duration = response.get("duration").unwrap();
         assert_eq!(duration.get("total_seconds").unwrap().as_i64().unwrap(), 0);
     }
@@ -825,7 +1105,7 @@ mod edge_cases_tests {
             "timezone": "UTC"
         });
 
-        let result = TimeTools::get_current_time(args).await;
+        let result = GetCurrentTimeTool.execute(args).await;
         assert!(result.is_ok(), "Valid format should work");
 
         // Test escaped percent
@@ -835,7 +1115,7 @@ mod edge_cases_tests {
             "timezone": "UTC"
         });
 
-        let result = TimeTools::get_current_time(args).await;
+        let result = GetCurrentTimeTool.execute(args).await;
         assert!(result.is_ok(), "Escaped percent should work");
 
         // Test missing custom_format field
@@ -844,7 +1124,7 @@ mod edge_cases_tests {
             "timezone": "UTC"
         });
 
-        let result = TimeTools::get_current_time(args).await;
+        let result = GetCurrentTimeTool.execute(args).await;
         assert!(result.is_err(), "Missing custom_format should fail");
         assert!(result
             .unwrap_err()
@@ -1066,3 +1346,3495 @@ mod auth_tests {
         );
     }
 }
+
+#[cfg(test)]
+mod jwt_tests {
+    use super::*;
+    use jsonwebtoken::{Algorithm, EncodingKey, Header};
+    use std::path::PathBuf;
+    use time_mcp_server::auth::JwtValidator;
+
+    const SECRET: &[u8] = b"test-secret";
+
+    fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/jwt")
+            .join(name)
+    }
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    fn claims() -> Value {
+        json!({
+            "sub": "alice",
+            "iss": "https://issuer.example",
+            "aud": "time-mcp",
+            "exp": now() + 3600,
+            "scope": "time:read tz:list"
+        })
+    }
+
+    fn hs256(claims: &Value) -> String {
+        jsonwebtoken::encode(
+            &Header::new(Algorithm::HS256),
+            claims,
+            &EncodingKey::from_secret(SECRET),
+        )
+        .unwrap()
+    }
+
+    fn signed(algorithm: Algorithm, kid: Option<&str>, claims: &Value) -> String {
+        let pem = std::fs::read(fixture(match algorithm {
+            Algorithm::RS256 => "rsa_private.pem",
+            _ => "ec_private.pem",
+        }))
+        .unwrap();
+        let key = match algorithm {
+            Algorithm::RS256 => EncodingKey::from_rsa_pem(&pem).unwrap(),
+            _ => EncodingKey::from_ec_pem(&pem).unwrap(),
+        };
+        let mut header = Header::new(algorithm);
+        header.kid = kid.map(str::to_string);
+        jsonwebtoken::encode(&header, claims, &key).unwrap()
+    }
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            HeaderName::from_static("authorization"),
+            HeaderValue::from_str(&format!("Bearer {}", token)).unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn test_hs256_claims_map_to_token_info() {
+        let validator = JwtValidator::hs256(SECRET);
+
+        let info = validator.validate(&hs256(&claims())).unwrap();
+
+        assert_eq!(info.user_id, "alice");
+        assert_eq!(info.scopes, vec!["time:read", "tz:list"]);
+        assert!(!info.is_expired());
+    }
+
+    #[test]
+    fn test_unrepresentable_exp_never_expires() {
+        let validator = JwtValidator::hs256(SECRET);
+        let mut claims = claims();
+        claims["exp"] = json!(u64::MAX);
+
+        let info = validator.validate(&hs256(&claims)).unwrap();
+        assert_eq!(info.user_id, "alice");
+        assert!(!info.is_expired());
+    }
+
+    #[test]
+    fn test_scp_claim_is_accepted() {
+        let validator = JwtValidator::hs256(SECRET);
+        let mut claims = claims();
+        claims.as_object_mut().unwrap().remove("scope");
+        claims["scp"] = json!(["time:admin"]);
+
+        let info = validator.validate(&hs256(&claims)).unwrap();
+        assert_eq!(info.scopes, vec!["time:admin"]);
+    }
+
+    #[test]
+    fn test_wrong_secret_is_rejected() {
+        let validator = JwtValidator::hs256(b"another-secret");
+
+        let error = validator.validate(&hs256(&claims())).unwrap_err();
+        assert_eq!(error.code, 401);
+        assert_eq!(error.message, "Invalid token signature");
+    }
+
+    #[test]
+    fn test_time_claims_are_checked() {
+        let validator = JwtValidator::hs256(SECRET);
+
+        let mut expired = claims();
+        expired["exp"] = json!(now() - 3600);
+        let error = validator.validate(&hs256(&expired)).unwrap_err();
+        assert_eq!(error.message, "Token expired");
+
+        let mut early = claims();
+        early["nbf"] = json!(now() + 3600);
+        let error = validator.validate(&hs256(&early)).unwrap_err();
+        assert_eq!(error.message, "Token not yet valid");
+
+        let mut no_exp = claims();
+        no_exp.as_object_mut().unwrap().remove("exp");
+        assert!(validator.validate(&hs256(&no_exp)).is_err());
+    }
+
+    #[test]
+    fn test_issuer_and_audience_are_checked() {
+        let validator = JwtValidator::hs256(SECRET)
+            .with_issuer("https://issuer.example")
+            .with_audience("other")
+            .with_audience("time-mcp");
+        assert!(validator.validate(&hs256(&claims())).is_ok());
+
+        let mut wrong_issuer = claims();
+        wrong_issuer["iss"] = json!("https://evil.example");
+        let error = validator.validate(&hs256(&wrong_issuer)).unwrap_err();
+        assert_eq!(error.message, "Invalid token issuer");
+
+        let mut wrong_audience = claims();
+        wrong_audience["aud"] = json!(["someone-else"]);
+        let error = validator.validate(&hs256(&wrong_audience)).unwrap_err();
+        assert_eq!(error.message, "Invalid token audience");
+    }
+
+    #[test]
+    fn test_rs256_and_es256_pem_keys() {
+        let rsa = JwtValidator::from_key_file(&fixture("rsa_public.pem")).unwrap();
+        assert!(rsa
+            .validate(&signed(Algorithm::RS256, None, &claims()))
+            .is_ok());
+
+        let ec = JwtValidator::from_key_file(&fixture("ec_public.pem")).unwrap();
+        assert!(ec
+            .validate(&signed(Algorithm::ES256, None, &claims()))
+            .is_ok());
+
+        // A key only accepts the algorithm it was loaded for.
+        assert!(rsa
+            .validate(&signed(Algorithm::ES256, None, &claims()))
+            .is_err());
+        assert!(rsa.validate(&hs256(&claims())).is_err());
+    }
+
+    #[test]
+    fn test_jwks_selects_key_by_kid() {
+        let validator = JwtValidator::from_key_file(&fixture("jwks.json")).unwrap();
+
+        assert!(validator
+            .validate(&signed(Algorithm::RS256, Some("rsa-1"), &claims()))
+            .is_ok());
+        assert!(validator
+            .validate(&signed(Algorithm::ES256, Some("ec-1"), &claims()))
+            .is_ok());
+        assert!(validator
+            .validate(&signed(Algorithm::ES256, None, &claims()))
+            .is_ok());
+
+        let error = validator
+            .validate(&signed(Algorithm::RS256, Some("unknown"), &claims()))
+            .unwrap_err();
+        assert_eq!(error.code, 401);
+    }
+
+    #[test]
+    fn test_invalid_key_files_are_rejected() {
+        assert!(JwtValidator::from_key_file(&fixture("missing.pem")).is_err());
+        assert!(JwtValidator::from_pem(b"not a key").is_err());
+        assert!(JwtValidator::from_jwks(r#"{"keys": []}"#).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_auth_manager_accepts_valid_jwt() {
+        let auth = AuthManager::new(true).with_jwt(JwtValidator::hs256(SECRET));
+
+        assert!(auth.authenticate(&bearer(&hs256(&claims()))).await.is_ok());
+
+        let error = auth.authenticate(&bearer("not-a-jwt")).await.unwrap_err();
+        assert_eq!(error.code, 401);
+        assert_eq!(error.message, "Invalid token");
+    }
+
+    #[test]
+    fn test_config_reads_jwt_secret_from_env() {
+        let _env = env_lock();
+        use clap::{Arg, Command};
+
+        std::env::set_var("OAUTH_JWT_SECRET", "from-env");
+        let matches = Command::new("test")
+            .arg(Arg::new("transport").long("transport"))
+            .arg(Arg::new("host").long("host"))
+            .arg(Arg::new("port").long("port"))
+            .try_get_matches_from(vec!["test", "--transport", "http"])
+            .unwrap();
+        let config = ServerConfig::from_matches(&matches);
+        std::env::remove_var("OAUTH_JWT_SECRET");
+
+        assert!(config.unwrap().jwt.is_some());
+    }
+}
+
+#[cfg(test)]
+mod scope_policy_tests {
+    use super::*;
+    use time_mcp_server::auth::{ScopePolicy, ScopeTarget};
+
+    fn principal(scopes: &[&str]) -> TokenInfo {
+        TokenInfo {
+            user_id: "alice".to_string(),
+            scopes: scopes.iter().map(|s| s.to_string()).collect(),
+            expires_at: SystemTime::now() + Duration::from_secs(3600),
+        }
+    }
+
+    fn policy() -> ScopePolicy {
+        ScopePolicy::new()
+            .with_rule(ScopeTarget::Tool, "*", ["time:read", "time:admin"])
+            .with_rule(
+                ScopeTarget::Tool,
+                "list_timezones",
+                ["tz:list", "time:admin"],
+            )
+            .with_rule(ScopeTarget::Resource, "time://*", ["time:read"])
+            .with_rule(ScopeTarget::Resource, "time://timezones*", ["tz:list"])
+            .with_rule(ScopeTarget::Prompt, "time_query_assistant", ["time:admin"])
+    }
+
+    #[test]
+    fn test_most_specific_rule_wins() {
+        let policy = policy();
+
+        assert_eq!(
+            policy.required_scopes(ScopeTarget::Tool, "list_timezones"),
+            Some(&["tz:list".to_string(), "time:admin".to_string()][..])
+        );
+        assert_eq!(
+            policy.required_scopes(ScopeTarget::Tool, "get_current_time"),
+            Some(&["time:read".to_string(), "time:admin".to_string()][..])
+        );
+        assert_eq!(
+            policy.required_scopes(ScopeTarget::Resource, "time://timezones?cursor=abc"),
+            Some(&["tz:list".to_string()][..])
+        );
+        assert_eq!(
+            policy.required_scopes(ScopeTarget::Resource, "time://formats"),
+            Some(&["time:read".to_string()][..])
+        );
+        assert_eq!(policy.required_scopes(ScopeTarget::Prompt, "other"), None);
+    }
+
+    #[test]
+    fn test_check_reports_insufficient_scope() {
+        let policy = policy();
+        let reader = principal(&["time:read"]);
+
+        assert!(policy
+            .check(Some(&reader), ScopeTarget::Tool, "get_current_time")
+            .is_ok());
+        assert!(policy
+            .check(None, ScopeTarget::Tool, "list_timezones")
+            .is_ok());
+
+        let error = policy
+            .check(Some(&reader), ScopeTarget::Tool, "list_timezones")
+            .unwrap_err();
+        assert_eq!(error.code, 403);
+        assert_eq!(
+            error.data,
+            Some(json!({"error": "insufficient_scope", "scope": "tz:list time:admin"}))
+        );
+
+        let mut expired = principal(&["time:admin"]);
+        expired.expires_at = SystemTime::now() - Duration::from_secs(1);
+        let error = policy
+            .check(Some(&expired), ScopeTarget::Tool, "get_current_time")
+            .unwrap_err();
+        assert_eq!(error.code, 401);
+    }
+
+    #[test]
+    fn test_policy_from_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("policy.json");
+        std::fs::write(
+            &path,
+            r#"{"tools": {"*": ["time:read"]}, "prompts": {"*": ["time:admin"]}}"#,
+        )
+        .unwrap();
+
+        let policy = ScopePolicy::from_file(&path).unwrap();
+        assert!(!policy.is_empty());
+        assert!(!policy.allows(
+            Some(&principal(&["time:read"])),
+            ScopeTarget::Prompt,
+            "time_query_assistant"
+        ));
+
+        std::fs::write(&path, r#"{"tool": {"*": ["time:read"]}}"#).unwrap();
+        assert!(ScopePolicy::from_file(&path).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tools_list_is_filtered_by_scope() {
+        let dispatcher = Dispatcher::new().with_scope_policy(policy());
+
+        let session = ready_session(&dispatcher).await;
+        let caller = principal(&["tz:list"]);
+        let result = dispatcher
+            .dispatch_with_session(&session, Some(&caller), "tools/list", None)
+            .await
+            .unwrap();
+        let names: Vec<_> = result["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["list_timezones"]);
+
+        let admin = principal(&["time:admin"]);
+        let result = dispatcher
+            .dispatch_with_session(&session, Some(&admin), "tools/list", None)
+            .await
+            .unwrap();
+        assert_eq!(result["tools"].as_array().unwrap().len(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_calls_require_scope() {
+        let dispatcher = Dispatcher::new().with_scope_policy(policy());
+        let session = ready_session(&dispatcher).await;
+        let caller = principal(&["time:read"]);
+
+        let response = dispatcher
+            .handle_message(
+                &session,
+                Some(&caller),
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "tools/call",
+                    "params": {"name": "list_timezones", "arguments": {}}
+                }),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response["error"]["code"], 403);
+        assert_eq!(response["error"]["data"]["error"], "insufficient_scope");
+
+        let error = dispatcher
+            .dispatch_with_session(
+                &session,
+                Some(&caller),
+                "resources/read",
+                Some(json!({"uri": "time://timezones"})),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(error.code, 403);
+
+        let error = dispatcher
+            .dispatch_with_session(
+                &session,
+                Some(&caller),
+                "prompts/get",
+                Some(json!({"name": "time_query_assistant", "arguments": {"user_query": "now?"}})),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(error.code, 403);
+
+        let error = dispatcher
+            .dispatch_with_session(
+                &session,
+                Some(&caller),
+                "completion/complete",
+                Some(json!({
+                    "ref": {"type": "ref/prompt", "name": "time_query_assistant"},
+                    "argument": {"name": "timezone", "value": "Eur"}
+                })),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(error.code, 403);
+
+        assert!(dispatcher
+            .dispatch_with_session(
+                &session,
+                Some(&caller),
+                "tools/call",
+                Some(json!({"name": "get_current_time", "arguments": {}})),
+            )
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_lists_hide_what_caller_cannot_use() {
+        let dispatcher = Dispatcher::new().with_scope_policy(policy());
+        let session = ready_session(&dispatcher).await;
+        let caller = principal(&["time:read"]);
+
+        let resources = dispatcher
+            .dispatch_with_session(&session, Some(&caller), "resources/list", None)
+            .await
+            .unwrap();
+        let uris: Vec<_> = resources["resources"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["uri"].as_str().unwrap())
+            .collect();
+        assert!(!uris.contains(&"time://timezones"));
+        assert!(uris.contains(&"time://formats"));
+
+        let prompts = dispatcher
+            .dispatch_with_session(&session, Some(&caller), "prompts/list", None)
+            .await
+            .unwrap();
+        assert_eq!(prompts["prompts"], json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_resource_aliases_get_canonical_rules() {
+        let dispatcher = Dispatcher::new().with_scope_policy(policy().with_rule(
+            ScopeTarget::Resource,
+            "time://zone/Europe/Paris*",
+            ["paris"],
+        ));
+        let session = ready_session(&dispatcher).await;
+        let caller = principal(&["time:read"]);
+        let read = |uri: &'static str| {
+            dispatcher.dispatch_with_session(
+                &session,
+                Some(&caller),
+                "resources/read",
+                Some(json!({"uri": uri})),
+            )
+        };
+
+        // The legacy alias of time://timezones needs tz:list like it does.
+        let error = read("timezone_database").await.unwrap_err();
+        assert_eq!(error.code, 403);
+        assert_eq!(error.data.unwrap()["scope"], "tz:list");
+        assert!(read("time_formats").await.is_ok());
+
+        for uri in [
+            "time://zone/Europe/Paris",
+            "time://zone/Europe%2FParis",
+            "time://zone/Europe%2fParis/transitions/2025",
+        ] {
+            assert_eq!(read(uri).await.unwrap_err().code, 403, "{}", uri);
+        }
+        assert!(read("time://zone/Europe%2FBerlin").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_sessions_without_principal_are_unrestricted() {
+        let dispatcher = Dispatcher::new().with_scope_policy(policy());
+        let session = ready_session(&dispatcher).await;
+
+        let result = dispatcher
+            .dispatch_with_session(&session, None, "tools/list", None)
+            .await
+            .unwrap();
+        assert_eq!(result["tools"].as_array().unwrap().len(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_returns_principal() {
+        let auth = AuthManager::new(true);
+        auth.tokens
+            .write()
+            .await
+            .insert("token".to_string(), principal(&["time:read"]));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            HeaderName::from_static("authorization"),
+            HeaderValue::from_static("Bearer token"),
+        );
+        let principal = auth.authenticate(&headers).await.unwrap().unwrap();
+        assert_eq!(principal.user_id, "alice");
+        assert_eq!(principal.scopes, vec!["time:read"]);
+
+        let disabled = AuthManager::new(false);
+        assert!(disabled.authenticate(&headers).await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_challenges() {
+        let auth = AuthManager::new(true);
+
+        let missing = McpError::new(401, "Authorization header required");
+        assert_eq!(auth.challenge(&missing).unwrap(), "Bearer");
+
+        let expired = McpError::new(401, "Token expired");
+        assert_eq!(
+            auth.challenge(&expired).unwrap(),
+            r#"Bearer error="invalid_token", error_description="Token expired""#
+        );
+
+        let forbidden = policy()
+            .check(
+                Some(&principal(&[])),
+                ScopeTarget::Prompt,
+                "time_query_assistant",
+            )
+            .unwrap_err();
+        assert_eq!(
+            auth.challenge(&forbidden).unwrap(),
+            r#"Bearer error="insufficient_scope", scope="time:admin""#
+        );
+        assert_eq!(StatusCode::from(forbidden), StatusCode::FORBIDDEN);
+
+        assert!(auth.challenge(&McpError::invalid_params("nope")).is_none());
+    }
+}
+
+#[cfg(test)]
+mod protected_resource_tests {
+    use super::*;
+    use time_mcp_server::auth::{JwtValidator, ProtectedResource, ScopePolicy, ScopeTarget};
+
+    const SECRET: &[u8] = b"resource-secret";
+
+    fn token(audience: Option<&str>) -> HeaderMap {
+        let exp = SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + 3600;
+        let mut claims = json!({"sub": "alice", "exp": exp});
+        if let Some(audience) = audience {
+            claims["aud"] = json!(audience);
+        }
+        let jwt = jsonwebtoken::encode(
+            &jsonwebtoken::Header::default(),
+            &claims,
+            &jsonwebtoken::EncodingKey::from_secret(SECRET),
+        )
+        .unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            HeaderName::from_static("authorization"),
+            HeaderValue::from_str(&format!("Bearer {}", jwt)).unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn test_resource_uri_is_canonicalized() {
+        let resource = ProtectedResource::new("HTTPS://Time.Example.COM/mcp/").unwrap();
+        assert_eq!(resource.resource(), "https://time.example.com/mcp");
+        assert_eq!(
+            resource.metadata_url(),
+            "https://time.example.com/.well-known/oauth-protected-resource/mcp"
+        );
+
+        let resource = ProtectedResource::new("https://time.example.com:8443").unwrap();
+        assert_eq!(resource.resource(), "https://time.example.com:8443");
+        assert_eq!(
+            resource.metadata_url(),
+            "https://time.example.com:8443/.well-known/oauth-protected-resource"
+        );
+    }
+
+    #[test]
+    fn test_invalid_resource_uris_are_rejected() {
+        assert!(ProtectedResource::new("/mcp").is_err());
+        assert!(ProtectedResource::new("ftp://time.example.com/mcp").is_err());
+        assert!(ProtectedResource::new("https://time.example.com/mcp#section").is_err());
+        assert!(ProtectedResource::new("https://time.example.com/mcp")
+            .unwrap()
+            .with_authorization_server("not a url")
+            .is_err());
+    }
+
+    #[test]
+    fn test_metadata_document() {
+        let resource = ProtectedResource::new("https://time.example.com/mcp")
+            .unwrap()
+            .with_authorization_server("https://auth.example.com/")
+            .unwrap()
+            .with_scopes(vec!["time:read".to_string(), "tz:list".to_string()]);
+
+        let metadata = resource.metadata();
+        assert_eq!(metadata["resource"], "https://time.example.com/mcp");
+        assert_eq!(
+            metadata["authorization_servers"],
+            json!(["https://auth.example.com"])
+        );
+        assert_eq!(
+            metadata["scopes_supported"],
+            json!(["time:read", "tz:list"])
+        );
+        assert_eq!(metadata["bearer_methods_supported"], json!(["header"]));
+
+        let bare = ProtectedResource::new("https://time.example.com/mcp")
+            .unwrap()
+            .metadata();
+        assert!(bare.get("authorization_servers").is_none());
+        assert!(bare.get("scopes_supported").is_none());
+    }
+
+    #[test]
+    fn test_config_advertises_policy_scopes() {
+        let config = ServerConfig {
+            host: "127.0.0.1".to_string(),
+            port: 9000,
+            scope_policy: ScopePolicy::new()
+                .with_rule(ScopeTarget::Tool, "*", ["time:read", "time:admin"])
+                .with_rule(ScopeTarget::Prompt, "*", ["time:admin"]),
+            ..ServerConfig::default()
+        };
+
+        let resource = config.protected_resource();
+        assert_eq!(resource.resource(), "http://127.0.0.1:9000/mcp");
+        assert_eq!(
+            resource.metadata()["scopes_supported"],
+            json!(["time:admin", "time:read"])
+        );
+    }
+
+    #[test]
+    fn test_challenges_point_at_metadata() {
+        let resource = ProtectedResource::new("https://time.example.com/mcp").unwrap();
+        let auth = AuthManager::new(true).with_resource(resource);
+        let metadata = r#"resource_metadata="https://time.example.com/.well-known/oauth-protected-resource/mcp""#;
+
+        let missing = McpError::new(401, "Authorization header required");
+        assert_eq!(
+            auth.challenge(&missing).unwrap(),
+            format!("Bearer {}", metadata)
+        );
+
+        let invalid = McpError::new(401, "Invalid token");
+        assert_eq!(
+            auth.challenge(&invalid).unwrap(),
+            format!(
+                r#"Bearer error="invalid_token", error_description="Invalid token", {}"#,
+                metadata
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_jwt_audience_must_match_resource() {
+        let config = ServerConfig {
+            auth_enabled: true,
+            jwt: Some(JwtValidator::hs256(SECRET)),
+            resource: Some(ProtectedResource::new("https://time.example.com/mcp").unwrap()),
+            ..ServerConfig::default()
+        };
+        let auth = AuthManager::from_config(&config);
+
+        assert!(auth
+            .authenticate(&token(Some("https://time.example.com/mcp")))
+            .await
+            .is_ok());
+
+        let error = auth
+            .authenticate(&token(Some("https://other.example.com/mcp")))
+            .await
+            .unwrap_err();
+        assert_eq!(error.message, "Invalid token audience");
+
+        let error = auth.authenticate(&token(None)).await.unwrap_err();
+        assert_eq!(error.code, 401);
+    }
+
+    #[test]
+    fn test_config_reads_resource_from_env() {
+        let _env = env_lock();
+        use clap::{Arg, Command};
+
+        std::env::set_var("OAUTH_RESOURCE", "https://time.example.com/mcp");
+        std::env::set_var(
+            "OAUTH_AUTHORIZATION_SERVERS",
+            "https://auth.example.com, https://backup.example.com",
+        );
+        let matches = Command::new("test")
+            .arg(Arg::new("transport").long("transport"))
+            .arg(Arg::new("host").long("host"))
+            .arg(Arg::new("port").long("port"))
+            .try_get_matches_from(vec!["test", "--transport", "http"])
+            .unwrap();
+        let config = ServerConfig::from_matches(&matches);
+        std::env::remove_var("OAUTH_RESOURCE");
+        std::env::remove_var("OAUTH_AUTHORIZATION_SERVERS");
+
+        let resource = config.unwrap().protected_resource();
+        assert_eq!(resource.resource(), "https://time.example.com/mcp");
+        assert_eq!(
+            resource.authorization_servers(),
+            ["https://auth.example.com", "https://backup.example.com"]
+        );
+    }
+}
+
+#[cfg(test)]
+mod introspection_tests {
+    use super::*;
+    use base64::Engine;
+    use mockito::Matcher;
+    use time_mcp_server::auth::{IntrospectionClient, JwtValidator};
+
+    fn exp(from_now: u64) -> u64 {
+        SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + from_now
+    }
+
+    fn client(server: &mockito::Server) -> IntrospectionClient {
+        IntrospectionClient::new(&format!("{}/introspect", server.url()), "time-mcp")
+            .unwrap()
+            .with_client_secret("client-secret")
+    }
+
+    async fn mock_introspection(
+        server: &mut mockito::Server,
+        token: &str,
+        response: Value,
+    ) -> mockito::Mock {
+        let credentials =
+            base64::engine::general_purpose::STANDARD.encode("time-mcp:client-secret");
+        server
+            .mock("POST", "/introspect")
+            .match_header("authorization", format!("Basic {}", credentials).as_str())
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("token".to_string(), token.to_string()),
+                Matcher::UrlEncoded("token_type_hint".to_string(), "access_token".to_string()),
+            ]))
+            .with_header("content-type", "application/json")
+            .with_body(response.to_string())
+            .create_async()
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_active_token_is_accepted() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = mock_introspection(
+            &mut server,
+            "opaque-token",
+            json!({
+                "active": true,
+                "sub": "alice",
+                "scope": "time:read tz:list",
+                "exp": exp(3600)
+            }),
+        )
+        .await;
+
+        let info = client(&server).validate("opaque-token").await.unwrap();
+        assert_eq!(info.user_id, "alice");
+        assert_eq!(info.scopes, vec!["time:read", "tz:list"]);
+        assert!(!info.is_expired());
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_results_are_cached() {
+        let mut server = mockito::Server::new_async().await;
+        let active = mock_introspection(
+            &mut server,
+            "cached-token",
+            json!({"active": true, "username": "bob", "exp": exp(3600)}),
+        )
+        .await
+        .expect(1);
+        let inactive = mock_introspection(&mut server, "revoked-token", json!({"active": false}))
+            .await
+            .expect(1);
+
+        let client = client(&server);
+        for _ in 0..3 {
+            assert_eq!(
+                client.validate("cached-token").await.unwrap().user_id,
+                "bob"
+            );
+            let error = client.validate("revoked-token").await.unwrap_err();
+            assert_eq!(error.code, 401);
+            assert_eq!(error.message, "Invalid token");
+        }
+
+        active.assert_async().await;
+        inactive.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_zero_ttl_disables_cache() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = mock_introspection(
+            &mut server,
+            "token",
+            json!({"active": true, "sub": "alice", "exp": exp(3600)}),
+        )
+        .await
+        .expect(2);
+
+        let client = client(&server).with_cache_ttl(Duration::ZERO);
+        assert!(client.validate("token").await.is_ok());
+        assert!(client.validate("token").await.is_ok());
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_claims_are_checked() {
+        let mut server = mockito::Server::new_async().await;
+        mock_introspection(
+            &mut server,
+            "other-audience",
+            json!({"active": true, "sub": "alice", "iss": "https://auth.example.com", "aud": ["https://other.example.com"], "exp": exp(3600)}),
+        )
+        .await;
+        mock_introspection(
+            &mut server,
+            "no-audience",
+            json!({"active": true, "sub": "alice", "iss": "https://auth.example.com", "exp": exp(3600)}),
+        )
+        .await;
+        mock_introspection(
+            &mut server,
+            "expired",
+            json!({"active": true, "sub": "alice", "iss": "https://auth.example.com", "aud": "https://time.example.com/mcp", "exp": exp(0) - 10}),
+        )
+        .await;
+        mock_introspection(
+            &mut server,
+            "wrong-issuer",
+            json!({"active": true, "sub": "alice", "aud": "https://time.example.com/mcp", "iss": "https://evil.example.com", "exp": exp(3600)}),
+        )
+        .await;
+
+        let client = client(&server)
+            .with_issuer("https://auth.example.com")
+            .with_audience("https://time.example.com/mcp");
+
+        let message = |token: &'static str| {
+            let client = client.clone();
+            async move { client.validate(token).await.unwrap_err().message }
+        };
+        assert_eq!(message("other-audience").await, "Invalid token audience");
+        assert_eq!(message("no-audience").await, "Invalid token audience");
+        assert_eq!(message("expired").await, "Token expired");
+        assert_eq!(message("wrong-issuer").await, "Invalid token issuer");
+    }
+
+    #[tokio::test]
+    async fn test_unrepresentable_times_do_not_panic() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = mock_introspection(
+            &mut server,
+            "far-future",
+            json!({"active": true, "sub": "alice", "exp": u64::MAX}),
+        )
+        .await
+        .expect(1);
+        mock_introspection(
+            &mut server,
+            "not-yet-valid",
+            json!({"active": true, "sub": "alice", "nbf": u64::MAX, "exp": exp(3600)}),
+        )
+        .await;
+
+        let client = client(&server).with_cache_ttl(Duration::MAX);
+        for _ in 0..2 {
+            let info = client.validate("far-future").await.unwrap();
+            assert!(!info.is_expired());
+        }
+        let error = client.validate("not-yet-valid").await.unwrap_err();
+        assert_eq!(error.message, "Token not yet valid");
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_server_is_not_cached() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/introspect")
+            .with_status(500)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let client = client(&server);
+        for _ in 0..2 {
+            let error = client.validate("token").await.unwrap_err();
+            assert_eq!(error.code, 503);
+            assert_eq!(StatusCode::from(error), StatusCode::SERVICE_UNAVAILABLE);
+        }
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_auth_manager_routes_jwts_locally() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = mock_introspection(
+            &mut server,
+            "opaque-token",
+            json!({"active": true, "sub": "carol", "exp": exp(3600)}),
+        )
+        .await
+        .expect(1);
+
+        let auth = AuthManager::new(true)
+            .with_jwt(JwtValidator::hs256(b"jwt-secret"))
+            .with_introspection(client(&server));
+        let bearer = |token: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                HeaderName::from_static("authorization"),
+                HeaderValue::from_str(&format!("Bearer {}", token)).unwrap(),
+            );
+            headers
+        };
+
+        let jwt = jsonwebtoken::encode(
+            &jsonwebtoken::Header::default(),
+            &json!({"sub": "alice", "exp": exp(3600)}),
+            &jsonwebtoken::EncodingKey::from_secret(b"jwt-secret"),
+        )
+        .unwrap();
+        let principal = auth.authenticate(&bearer(&jwt)).await.unwrap().unwrap();
+        assert_eq!(principal.user_id, "alice");
+
+        let principal = auth
+            .authenticate(&bearer("opaque-token"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(principal.user_id, "carol");
+        mock.assert_async().await;
+    }
+
+    #[test]
+    fn test_config_reads_introspection_from_env() {
+        let _env = env_lock();
+        use clap::{Arg, Command};
+
+        std::env::set_var("OAUTH_CLIENT_ID", "time-mcp");
+        std::env::set_var(
+            "OAUTH_INTROSPECTION_URL",
+            "https://auth.example.com/introspect",
+        );
+        std::env::set_var("OAUTH_INTROSPECTION_CACHE_TTL", "30");
+        let matches = Command::new("test")
+            .arg(Arg::new("transport").long("transport"))
+            .arg(Arg::new("host").long("host"))
+            .arg(Arg::new("port").long("port"))
+            .try_get_matches_from(vec!["test", "--transport", "http"])
+            .unwrap();
+        let config = ServerConfig::from_matches(&matches);
+        std::env::remove_var("OAUTH_INTROSPECTION_URL");
+        std::env::remove_var("OAUTH_CLIENT_ID");
+        std::env::remove_var("OAUTH_INTROSPECTION_CACHE_TTL");
+
+        assert!(config.unwrap().introspection.is_some());
+        assert!(IntrospectionClient::new("not a url", "time-mcp").is_err());
+    }
+}
+
+#[cfg(test)]
+mod api_key_tests {
+    use super::*;
+    use argon2::password_hash::{PasswordHasher, SaltString};
+    use sha2::{Digest, Sha256};
+    use time_mcp_server::auth::ApiKeyStore;
+
+    fn sha256(key: &str) -> String {
+        let hex: String = Sha256::digest(key.as_bytes())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        format!("sha256:{}", hex)
+    }
+
+    fn argon2(key: &str) -> String {
+        let salt = SaltString::from_b64("dGltZS1tY3Atc2FsdA").unwrap();
+        argon2::Argon2::default()
+            .hash_password(key.as_bytes(), &salt)
+            .unwrap()
+            .to_string()
+    }
+
+    fn write_keys(path: &std::path::Path, keys: Value) {
+        std::fs::write(path, json!({ "keys": keys }).to_string()).unwrap();
+    }
+
+    fn header(name: &'static str, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            HeaderName::from_static(name),
+            HeaderValue::from_str(value).unwrap(),
+        );
+        headers
+    }
+
+    fn store(keys: Value) -> (tempfile::TempDir, ApiKeyStore) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keys.json");
+        write_keys(&path, keys);
+        let store = ApiKeyStore::from_file(&path).unwrap();
+        (dir, store)
+    }
+
+    #[tokio::test]
+    async fn test_hashed_keys_are_accepted() {
+        let (_dir, store) = store(json!([
+            {"owner": "ci", "hash": sha256("ci-key"), "scopes": ["time:read"]},
+            {"owner": "ops", "id": "ops1", "hash": argon2("ops1.ops-key"), "scopes": ["time:admin"]}
+        ]));
+        assert_eq!(store.len(), 2);
+
+        let ci = store.validate("ci-key").await.unwrap().unwrap();
+        assert_eq!(ci.user_id, "ci");
+        assert_eq!(ci.scopes, vec!["time:read"]);
+        assert!(!ci.is_expired());
+
+        // The second lookup is served from the verified-key cache.
+        for _ in 0..2 {
+            let ops = store.validate("ops1.ops-key").await.unwrap().unwrap();
+            assert_eq!(ops.user_id, "ops");
+            assert_eq!(ops.scopes, vec!["time:admin"]);
+        }
+
+        assert!(store.validate("unknown-key").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_argon2_keys_are_addressed_by_id() {
+        let (_dir, store) = store(json!([
+            {"owner": "ops", "id": "ops1", "hash": argon2("ops1.ops-key")},
+            {"owner": "dev", "id": "dev1", "hash": argon2("dev1.dev-key")}
+        ]));
+
+        assert!(store.validate("ops1.ops-key").await.unwrap().is_some());
+        // Only the entry named by the id is tried.
+        assert!(store.validate("dev1.ops-key").await.unwrap().is_none());
+        assert!(store.validate("ops1.wrong-key").await.unwrap().is_none());
+        assert!(store.validate("ops-key").await.unwrap().is_none());
+        assert!(store.validate("nobody.ops-key").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_expired_keys_are_rejected() {
+        let (_dir, store) = store(json!([
+            {"owner": "old", "hash": sha256("old-key"), "expires_at": "2020-01-01T00:00:00Z"},
+            {"owner": "new", "hash": sha256("new-key"), "expires_at": "2999-01-01T00:00:00Z"}
+        ]));
+
+        let error = store.validate("old-key").await.unwrap_err();
+        assert_eq!(error.code, 401);
+        assert_eq!(error.message, "API key expired");
+        assert!(store.validate("new-key").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_auth_manager_accepts_bearer_and_header_keys() {
+        let (_dir, store) = store(json!([
+            {"owner": "ci", "hash": sha256("ci-key"), "scopes": ["time:read"]}
+        ]));
+        let auth = AuthManager::new(true).with_api_keys(store);
+
+        let principal = auth
+            .authenticate(&header("authorization", "Bearer ci-key"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(principal.user_id, "ci");
+
+        let principal = auth
+            .authenticate(&header("x-api-key", "ci-key"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(principal.user_id, "ci");
+
+        let error = auth
+            .authenticate(&header("x-api-key", "wrong-key"))
+            .await
+            .unwrap_err();
+        assert_eq!(error.message, "Invalid API key");
+
+        let error = auth
+            .authenticate(&header("authorization", "Bearer wrong-key"))
+            .await
+            .unwrap_err();
+        assert_eq!(error.message, "Invalid token");
+    }
+
+    #[tokio::test]
+    async fn test_reload_replaces_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keys.json");
+        write_keys(&path, json!([{"owner": "ci", "hash": sha256("first-key")}]));
+        let store = ApiKeyStore::from_file(&path).unwrap();
+        let shared = store.clone();
+
+        write_keys(
+            &path,
+            json!([{"owner": "ci", "hash": sha256("second-key")}]),
+        );
+        assert!(store.validate("first-key").await.unwrap().is_some());
+        store.reload().unwrap();
+
+        assert!(shared.validate("first-key").await.unwrap().is_none());
+        assert!(shared.validate("second-key").await.unwrap().is_some());
+
+        // A broken file leaves the current keys in place.
+        std::fs::write(&path, "{").unwrap();
+        assert!(store.reload().is_err());
+        assert!(shared.validate("second-key").await.unwrap().is_some());
+    }
+
+    #[test]
+    fn test_invalid_key_files_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keys.json");
+
+        for keys in [
+            json!([{"owner": "ci", "hash": "plaintext-key"}]),
+            json!([{"owner": "ci", "hash": "sha256:abc"}]),
+            json!([{"owner": "ci", "id": "ci", "hash": "$argon2id$garbage"}]),
+            json!([{"owner": "ci", "hash": argon2("ci.key")}]),
+            json!([{"owner": "ci", "id": "c.i", "hash": argon2("c.i.key")}]),
+            json!([
+                {"owner": "ci", "id": "ci", "hash": argon2("ci.key")},
+                {"owner": "cd", "id": "ci", "hash": argon2("ci.other-key")}
+            ]),
+            json!([{"owner": "ci", "hash": sha256("key"), "scope": "time:read"}]),
+            json!([{"hash": sha256("key")}]),
+        ] {
+            write_keys(&path, keys.clone());
+            assert!(ApiKeyStore::from_file(&path).is_err(), "accepted {}", keys);
+        }
+
+        assert!(ApiKeyStore::from_file(&dir.path().join("missing.json")).is_err());
+    }
+}
+
+#[cfg(test)]
+mod dispatcher_tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_dispatch_tools_list() {
+        let dispatcher = Dispatcher::new();
+        let result = dispatcher.dispatch("tools/list", None).await.unwrap();
+
+        let tools = result.get("tools").unwrap().as_array().unwrap();
+        assert_eq!(tools.len(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_unknown_method() {
+        let dispatcher = Dispatcher::new();
+        let error = dispatcher
+            .dispatch("unknown/method", None)
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.code, -32601);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_unknown_tool() {
+        let dispatcher = Dispatcher::new();
+        let error = dispatcher
+            .dispatch(
+                "tools/call",
+                Some(json!({"name": "no_such_tool", "arguments": {}})),
+            )
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.code, -32602);
+        assert!(error.message.contains("no_such_tool"));
+    }
+
+    #[tokio::test]
+    async fn test_handle_message_wraps_result() {
+        let dispatcher = Dispatcher::new();
+        let response = dispatcher
+            .handle_message(
+                &ready_session(&dispatcher).await,
+                None,
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 7,
+                    "method": "prompts/list"
+                }),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response["jsonrpc"], "2.0");
+        assert_eq!(response["id"], 7);
+        assert!(response["result"]["prompts"].is_array());
+    }
+
+    #[tokio::test]
+    async fn test_handle_message_wraps_error() {
+        let dispatcher = Dispatcher::new();
+        let response = dispatcher
+            .handle_message(
+                &ready_session(&dispatcher).await,
+                None,
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": "abc",
+                    "method": "resources/read",
+                    "params": {"uri": "missing"}
+                }),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response["id"], "abc");
+        assert_eq!(response["error"]["code"], -32602);
+        assert!(response.get("result").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_handle_message_notification_has_no_response() {
+        let dispatcher = Dispatcher::new();
+        let response = dispatcher
+            .handle_message(
+                &Session::new(),
+                None,
+                json!({
+                    "jsonrpc": "2.0",
+                    "method": "notifications/initialized"
+                }),
+            )
+            .await;
+
+        assert!(response.is_none());
+    }
+}
+
+#[cfg(test)]
+mod registry_tests {
+    use super::*;
+
+    struct EchoTool;
+
+    #[async_trait::async_trait]
+    impl Tool for EchoTool {
+        fn name(&self) -> &str {
+            "echo"
+        }
+
+        fn description(&self) -> &str {
+            "Echo the arguments back"
+        }
+
+        fn input_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+
+        async fn execute(&self, arguments: Value) -> anyhow::Result<Value> {
+            Ok(arguments)
+        }
+    }
+
+    struct OverrideTool;
+
+    #[async_trait::async_trait]
+    impl Tool for OverrideTool {
+        fn name(&self) -> &str {
+            "list_timezones"
+        }
+
+        fn description(&self) -> &str {
+            "Overridden timezone list"
+        }
+
+        fn input_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+
+        async fn execute(&self, _arguments: Value) -> anyhow::Result<Value> {
+            Ok(json!([]))
+        }
+    }
+
+    #[test]
+    fn test_builtin_tools_registered_in_order() {
+        let registry = ToolRegistry::with_builtin_tools();
+        let names: Vec<&str> = registry.names().collect();
+
+        assert_eq!(
+            names,
+            vec![
+                "get_current_time",
+                "convert_timezone",
+                "calculate_duration",
+                "format_time",
+                "get_timezone_info",
+                "list_timezones"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_definitions_match_registered_tools() {
+        let registry = ToolRegistry::with_builtin_tools();
+        let definitions = registry.definitions();
+
+        assert_eq!(definitions.len(), registry.len());
+        for definition in definitions {
+            assert!(definition["name"].is_string());
+            assert!(definition["description"].is_string());
+            assert_eq!(definition["inputSchema"]["type"], "object");
+        }
+    }
+
+    #[test]
+    fn test_register_replaces_tool_with_same_name() {
+        let mut registry = ToolRegistry::with_builtin_tools();
+        registry.register(OverrideTool);
+
+        assert_eq!(registry.len(), 6);
+        assert_eq!(
+            registry.get("list_timezones").unwrap().description(),
+            "Overridden timezone list"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dispatcher_uses_custom_registry() {
+        let mut registry = ToolRegistry::with_builtin_tools();
+        registry.register(EchoTool);
+        let dispatcher = Dispatcher::with_tools(registry);
+
+        let list = dispatcher.dispatch("tools/list", None).await.unwrap();
+        let tools = list["tools"].as_array().unwrap();
+        assert_eq!(tools.len(), 7);
+        assert_eq!(tools[6]["name"], "echo");
+
+        let result = dispatcher
+            .dispatch(
+                "tools/call",
+                Some(json!({"name": "echo", "arguments": {"hello": "world"}})),
+            )
+            .await
+            .unwrap();
+        assert_eq!(result["content"][0]["text"], r#"{"hello":"world"}"#);
+        assert_eq!(result["structuredContent"], json!({"hello": "world"}));
+    }
+
+    #[tokio::test]
+    async fn test_empty_registry_has_no_tools() {
+        let dispatcher = Dispatcher::with_tools(ToolRegistry::new());
+
+        let list = dispatcher.dispatch("tools/list", None).await.unwrap();
+        assert!(list["tools"].as_array().unwrap().is_empty());
+
+        let error = dispatcher
+            .dispatch("tools/call", Some(json!({"name": "get_current_time"})))
+            .await
+            .unwrap_err();
+        assert_eq!(error.code, -32602);
+    }
+}
+
+#[cfg(test)]
+mod typed_tools_tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_typed_convert_timezone() {
+        let result = TimeTools::convert_timezone(ConvertTimezoneArgs {
+            timestamp: "2023-01-01T12:00:00Z".to_string(),
+            from_timezone: "UTC".to_string(),
+            to_timezone: "Asia/Tokyo".to_string(),
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(result.original.timezone, "UTC");
+        assert_eq!(result.converted.timezone, "Asia/Tokyo");
+        assert_eq!(result.converted.timestamp, "2023-01-01T21:00:00+09:00");
+    }
+
+    #[tokio::test]
+    async fn test_typed_get_current_time_unix() {
+        let result = TimeTools::get_current_time(GetCurrentTimeArgs {
+            format: CurrentTimeFormat::Unix,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(result.timezone, "UTC");
+        assert!(matches!(result.timestamp, Some(TimestampValue::Unix(_))));
+        assert!(result.formatted.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_typed_calculate_duration_days() {
+        let result = TimeTools::calculate_duration(CalculateDurationArgs {
+            start_time: "2023-01-01T00:00:00Z".to_string(),
+            end_time: "2023-01-04T00:00:00Z".to_string(),
+            units: DurationUnits::Days,
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(result.duration.total_seconds, 259200);
+        assert_eq!(result.duration.days, Some(3));
+        assert_eq!(result.duration.hours, 72);
+        assert!(result.duration.minutes.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_typed_format_time_custom_requires_format_string() {
+        let result = TimeTools::format_time(FormatTimeArgs {
+            timestamp: "2023-01-01T12:00:00Z".to_string(),
+            format: TimestampFormat::Custom,
+            custom_format: None,
+            timezone: "UTC".to_string(),
+        })
+        .await;
+
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("custom_format required"));
+    }
+
+    #[tokio::test]
+    async fn test_typed_get_timezone_info() {
+        let result = TimeTools::get_timezone_info(GetTimezoneInfoArgs {
+            timezone: "Asia/Kolkata".to_string(),
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(result.offset, "+05:30");
+        assert_eq!(result.abbreviation, "IST");
+    }
+
+    #[tokio::test]
+    async fn test_typed_list_timezones_count_matches() {
+        let result = TimeTools::list_timezones(ListTimezonesArgs {
+            region: Some("Europe".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(result.count, result.timezones.len());
+        assert!(result.timezones.iter().all(|tz| tz.starts_with("Europe")));
+    }
+
+    #[test]
+    fn test_args_deserialize_defaults() {
+        let args: GetCurrentTimeArgs = serde_json::from_value(json!({})).unwrap();
+        assert_eq!(args.timezone, "UTC");
+        assert_eq!(args.format, CurrentTimeFormat::Iso);
+
+        let args: FormatTimeArgs = serde_json::from_value(json!({
+            "timestamp": "0",
+            "format": "unix"
+        }))
+        .unwrap();
+        assert_eq!(args.timezone, "UTC");
+        assert_eq!(args.format, TimestampFormat::Unix);
+    }
+
+    #[test]
+    fn test_args_reject_unknown_enum_values() {
+        let error = serde_json::from_value::<CalculateDurationArgs>(json!({
+            "start_time": "0",
+            "end_time": "1",
+            "units": "fortnights"
+        }))
+        .unwrap_err();
+
+        assert!(error.to_string().contains("Invalid units"));
+    }
+
+    #[test]
+    fn test_input_schema_generated_from_args() {
+        let schema = ConvertTimezoneTool.input_schema();
+
+        assert_eq!(schema["type"], "object");
+        assert_eq!(
+            schema["required"],
+            json!(["timestamp", "from_timezone", "to_timezone"])
+        );
+        assert!(schema.get("$schema").is_none());
+        assert!(schema.get("title").is_none());
+
+        let schema = CalculateDurationTool.input_schema();
+        assert_eq!(
+            schema["properties"]["units"]["enum"],
+            json!(["seconds", "minutes", "hours", "days"])
+        );
+        assert_eq!(schema["properties"]["units"]["default"], "seconds");
+    }
+}
+
+#[cfg(test)]
+mod structured_output_tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_tools_advertise_output_schema() {
+        let registry = ToolRegistry::with_builtin_tools();
+
+        for definition in registry.definitions() {
+            let output_schema = &definition["outputSchema"];
+            assert_eq!(
+                output_schema["type"], "object",
+                "{} should advertise an object outputSchema",
+                definition["name"]
+            );
+            assert!(output_schema.get("$schema").is_none());
+        }
+    }
+
+    #[test]
+    fn test_output_schema_describes_result_fields() {
+        let schema = CalculateDurationTool.output_schema().unwrap();
+        let duration = &schema["properties"]["duration"];
+
+        assert_eq!(duration["properties"]["total_seconds"]["type"], "integer");
+        assert!(duration["required"]
+            .as_array()
+            .unwrap()
+            .contains(&json!("total_seconds")));
+        assert!(!duration["required"]
+            .as_array()
+            .unwrap()
+            .contains(&json!("days")));
+    }
+
+    #[tokio::test]
+    async fn test_tools_call_returns_structured_content() {
+        let dispatcher = Dispatcher::new();
+        let result = dispatcher
+            .dispatch(
+                "tools/call",
+                Some(json!({
+                    "name": "convert_timezone",
+                    "arguments": {
+                        "timestamp": "2023-01-01T12:00:00Z",
+                        "from_timezone": "UTC",
+                        "to_timezone": "Asia/Tokyo"
+                    }
+                })),
+            )
+            .await
+            .unwrap();
+
+        let structured = &result["structuredContent"];
+        assert_eq!(
+            structured["converted"]["timestamp"],
+            "2023-01-01T21:00:00+09:00"
+        );
+
+        let text = result["content"][0]["text"].as_str().unwrap();
+        let parsed: Value = serde_json::from_str(text).unwrap();
+        assert_eq!(&parsed, structured);
+    }
+
+    #[tokio::test]
+    async fn test_non_object_output_has_text_only() {
+        struct GreetingTool;
+
+        #[async_trait::async_trait]
+        impl Tool for GreetingTool {
+            fn name(&self) -> &str {
+                "greeting"
+            }
+
+            fn description(&self) -> &str {
+                "Say hello"
+            }
+
+            fn input_schema(&self) -> Value {
+                json!({"type": "object"})
+            }
+
+            async fn execute(&self, _arguments: Value) -> anyhow::Result<Value> {
+                Ok(json!("hello"))
+            }
+        }
+
+        let mut registry = ToolRegistry::new();
+        registry.register(GreetingTool);
+        let dispatcher = Dispatcher::with_tools(registry);
+
+        let result = dispatcher
+            .dispatch("tools/call", Some(json!({"name": "greeting"})))
+            .await
+            .unwrap();
+
+        assert_eq!(result["content"][0]["text"], "hello");
+        assert!(result.get("structuredContent").is_none());
+
+        let list = dispatcher.dispatch("tools/list", None).await.unwrap();
+        assert!(list["tools"][0].get("outputSchema").is_none());
+    }
+}
+
+#[cfg(test)]
+mod tool_error_tests {
+    use super::*;
+
+    async fn call_tool(name: &str, arguments: Value) -> Value {
+        Dispatcher::new()
+            .dispatch(
+                "tools/call",
+                Some(json!({"name": name, "arguments": arguments})),
+            )
+            .await
+            .expect("tool failures should not be protocol errors")
+    }
+
+    #[test]
+    fn test_time_error_codes() {
+        assert_eq!(TimeError::InvalidTimezone("X".to_string()).code(), -32000);
+        assert_eq!(TimeError::InvalidTimestamp("X".to_string()).code(), -32001);
+        assert_eq!(TimeError::Conversion("X".to_string()).code(), -32002);
+        assert_eq!(TimeError::InvalidArgument("X".to_string()).code(), -32602);
+    }
+
+    #[tokio::test]
+    async fn test_typed_api_returns_time_error() {
+        let error = TimeTools::get_timezone_info(GetTimezoneInfoArgs {
+            timezone: "Mars/Olympus_Mons".to_string(),
+        })
+        .await
+        .unwrap_err();
+
+        assert_eq!(
+            error,
+            TimeError::InvalidTimezone("Mars/Olympus_Mons".to_string())
+        );
+        assert_eq!(error.to_string(), "Invalid timezone: Mars/Olympus_Mons");
+    }
+
+    #[tokio::test]
+    async fn test_typed_calculate_duration_returns_time_error() {
+        let error = TimeTools::calculate_duration(CalculateDurationArgs {
+            start_time: "yesterday".to_string(),
+            end_time: "2024-01-01T00:00:00Z".to_string(),
+            units: DurationUnits::Seconds,
+        })
+        .await
+        .unwrap_err();
+
+        assert_eq!(error, TimeError::InvalidTimestamp("yesterday".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_timezone_is_error_result() {
+        let result = call_tool("get_timezone_info", json!({"timezone": "Invalid/Zone"})).await;
+
+        assert_eq!(result["isError"], true);
+        assert_eq!(result["structuredContent"]["error"]["code"], -32000);
+        assert_eq!(
+            result["content"][0]["text"],
+            "Invalid timezone: Invalid/Zone"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_timestamp_is_error_result() {
+        let result = call_tool(
+            "convert_timezone",
+            json!({
+                "timestamp": "yesterday",
+                "from_timezone": "UTC",
+                "to_timezone": "Europe/Paris"
+            }),
+        )
+        .await;
+
+        assert_eq!(result["isError"], true);
+        assert_eq!(result["structuredContent"]["error"]["code"], -32001);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_custom_format_is_conversion_error() {
+        let result = call_tool(
+            "format_time",
+            json!({
+                "timestamp": "2023-01-01T12:00:00Z",
+                "format": "custom",
+                "custom_format": "%Q"
+            }),
+        )
+        .await;
+
+        assert_eq!(result["isError"], true);
+        assert_eq!(result["structuredContent"]["error"]["code"], -32002);
+    }
+
+    #[tokio::test]
+    async fn test_missing_argument_is_error_result() {
+        let result = call_tool("convert_timezone", json!({"timestamp": "0"})).await;
+
+        assert_eq!(result["isError"], true);
+        assert_eq!(result["structuredContent"]["error"]["code"], -32602);
+        assert_eq!(result["content"][0]["text"], "from_timezone required");
+    }
+
+    #[tokio::test]
+    async fn test_successful_call_is_not_error() {
+        let result = call_tool("get_current_time", json!({})).await;
+
+        assert!(result.get("isError").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_malformed_call_is_protocol_error() {
+        let error = Dispatcher::new()
+            .dispatch("tools/call", Some(json!({"arguments": {}})))
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.code, -32602);
+        assert_eq!(error.message, "Missing tool name");
+    }
+}
+
+#[cfg(test)]
+mod stdio_tests {
+    use super::*;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, DuplexStream, Lines};
+
+    /// Sleeps for `ms` milliseconds, then returns its arguments.
+    struct SleepTool;
+
+    #[async_trait::async_trait]
+    impl Tool for SleepTool {
+        fn name(&self) -> &str {
+            "sleep"
+        }
+
+        fn description(&self) -> &str {
+            "Sleep before answering"
+        }
+
+        fn input_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+
+        async fn execute(&self, arguments: Value) -> anyhow::Result<Value> {
+            let ms = arguments["ms"].as_u64().unwrap_or(0);
+            tokio::time::sleep(Duration::from_millis(ms)).await;
+            Ok(arguments)
+        }
+    }
+
+    /// Reports `steps` progress updates before answering.
+    struct ProgressTool;
+
+    #[async_trait::async_trait]
+    impl Tool for ProgressTool {
+        fn name(&self) -> &str {
+            "progress"
+        }
+
+        fn description(&self) -> &str {
+            "Report progress before answering"
+        }
+
+        fn input_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+
+        async fn execute(&self, arguments: Value) -> anyhow::Result<Value> {
+            Ok(arguments)
+        }
+
+        async fn execute_with_context(
+            &self,
+            arguments: Value,
+            context: &ToolContext,
+        ) -> anyhow::Result<Value> {
+            let steps = arguments["steps"].as_u64().unwrap_or(0);
+            for step in 1..=steps {
+                context
+                    .progress
+                    .report(step as f64, Some(steps as f64), Some("working"));
+            }
+            Ok(arguments)
+        }
+    }
+
+    fn sleep_request(id: u64, ms: u64) -> String {
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "tools/call",
+            "params": {"name": "sleep", "arguments": {"ms": ms}}
+        })
+        .to_string()
+    }
+
+    async fn start(max_in_flight: usize) -> (DuplexStream, Lines<BufReader<DuplexStream>>) {
+        start_handler(StdioHandler::new().with_max_in_flight(max_in_flight)).await
+    }
+
+    /// Starts `handler` over in-memory pipes and initializes it, returning
+    /// the client's input stream and the lines the handler writes back.
+    async fn start_handler(
+        handler: StdioHandler,
+    ) -> (DuplexStream, Lines<BufReader<DuplexStream>>) {
+        let mut registry = ToolRegistry::new();
+        registry.register(SleepTool).register(ProgressTool);
+
+        let (client_in, server_in) = tokio::io::duplex(4096);
+        let (server_out, client_out) = tokio::io::duplex(4096);
+
+        let handler = handler.with_dispatcher(Dispatcher::with_tools(registry));
+        tokio::spawn(handler.serve(server_in, server_out));
+
+        let mut input = client_in;
+        let mut output = BufReader::new(client_out).lines();
+        let handshake = format!(
+            "{}\n{}\n",
+            json!({"jsonrpc": "2.0", "id": 0, "method": "initialize", "params": {}}),
+            json!({"jsonrpc": "2.0", "method": "notifications/initialized"})
+        );
+        input.write_all(handshake.as_bytes()).await.unwrap();
+        assert_eq!(next_id(&mut output).await, 0);
+
+        (input, output)
+    }
+
+    async fn next_id(lines: &mut Lines<BufReader<DuplexStream>>) -> Value {
+        let line = lines.next_line().await.unwrap().expect("stream closed");
+        serde_json::from_str::<Value>(&line).unwrap()["id"].clone()
+    }
+
+    #[tokio::test]
+    async fn test_slow_request_does_not_block_later_ones() {
+        let (mut input, mut output) = start(DEFAULT_MAX_IN_FLIGHT).await;
+
+        let requests = format!("{}\n{}\n", sleep_request(1, 300), sleep_request(2, 0));
+        input.write_all(requests.as_bytes()).await.unwrap();
+
+        assert_eq!(next_id(&mut output).await, 2);
+        assert_eq!(next_id(&mut output).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_max_in_flight_serializes_requests() {
+        let (mut input, mut output) = start(1).await;
+
+        let requests = format!("{}\n{}\n", sleep_request(1, 100), sleep_request(2, 0));
+        input.write_all(requests.as_bytes()).await.unwrap();
+
+        assert_eq!(next_id(&mut output).await, 1);
+        assert_eq!(next_id(&mut output).await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_pending_responses_are_flushed_on_eof() {
+        let (mut input, mut output) = start(DEFAULT_MAX_IN_FLIGHT).await;
+
+        let requests = format!("{}\n{}\n", sleep_request(1, 50), sleep_request(2, 50));
+        input.write_all(requests.as_bytes()).await.unwrap();
+        drop(input);
+
+        let mut ids = vec![next_id(&mut output).await, next_id(&mut output).await];
+        ids.sort_by_key(|id| id.as_u64());
+        assert_eq!(ids, vec![json!(1), json!(2)]);
+        assert!(output.next_line().await.unwrap().is_none());
+    }
+
+    fn cancel_notification(id: u64) -> String {
+        json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/cancelled",
+            "params": {"requestId": id, "reason": "User requested cancellation"}
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_request_gets_no_response() {
+        let (mut input, mut output) = start(DEFAULT_MAX_IN_FLIGHT).await;
+
+        let requests = format!(
+            "{}\n{}\n{}\n",
+            sleep_request(1, 5_000),
+            cancel_notification(1),
+            sleep_request(2, 0)
+        );
+        input.write_all(requests.as_bytes()).await.unwrap();
+        drop(input);
+
+        assert_eq!(next_id(&mut output).await, 2);
+        let remaining = tokio::time::timeout(Duration::from_secs(2), output.next_line())
+            .await
+            .expect("cancelled request was not aborted");
+        assert!(remaining.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_bypasses_in_flight_limit() {
+        let (mut input, mut output) = start(1).await;
+
+        let requests = format!(
+            "{}\n{}\n{}\n",
+            sleep_request(1, 5_000),
+            cancel_notification(1),
+            sleep_request(2, 0)
+        );
+        input.write_all(requests.as_bytes()).await.unwrap();
+
+        let id = tokio::time::timeout(Duration::from_secs(2), next_id(&mut output))
+            .await
+            .expect("cancellation was blocked by the in-flight limit");
+        assert_eq!(id, 2);
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_reaches_requests_while_at_limit() {
+        let (mut input, mut output) = start(1).await;
+
+        // Request 2 can't start until request 1 is gone, and the cancellation
+        // and ping behind it must still be read.
+        let requests = format!(
+            "{}\n{}\n{}\n{}\n",
+            sleep_request(1, 5_000),
+            sleep_request(2, 0),
+            cancel_notification(1),
+            json!({"jsonrpc": "2.0", "id": 3, "method": "ping"})
+        );
+        input.write_all(requests.as_bytes()).await.unwrap();
+
+        let answered = tokio::time::timeout(Duration::from_secs(2), async {
+            vec![next_id(&mut output).await, next_id(&mut output).await]
+        })
+        .await
+        .expect("cancellation was stuck behind the in-flight limit");
+        assert_eq!(answered, vec![json!(2), json!(3)]);
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_sent_right_after_request_is_honored() {
+        for _ in 0..5 {
+            let (mut input, mut output) = start(DEFAULT_MAX_IN_FLIGHT).await;
+            let requests = format!(
+                "{}\n{}\n{}\n",
+                sleep_request(1, 200),
+                cancel_notification(1),
+                json!({"jsonrpc": "2.0", "id": 2, "method": "ping"})
+            );
+            input.write_all(requests.as_bytes()).await.unwrap();
+
+            assert_eq!(next_id(&mut output).await, 2);
+            let late = tokio::time::timeout(Duration::from_millis(400), next_id(&mut output));
+            assert!(late.await.is_err(), "cancelled request was answered");
+        }
+
+        // Request 2 is still waiting for a slot when its cancellation arrives.
+        let (mut input, mut output) = start(1).await;
+        let requests = format!(
+            "{}\n{}\n{}\n",
+            sleep_request(1, 200),
+            sleep_request(2, 0),
+            cancel_notification(2)
+        );
+        input.write_all(requests.as_bytes()).await.unwrap();
+
+        assert_eq!(next_id(&mut output).await, 1);
+        let late = tokio::time::timeout(Duration::from_millis(300), next_id(&mut output));
+        assert!(late.await.is_err(), "cancelled request was answered");
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_is_observed_while_at_limit() {
+        let (trigger, shutdown) = shutdown::channel();
+        let (mut input, mut output) = start_handler(
+            StdioHandler::new()
+                .with_max_in_flight(1)
+                .with_shutdown(shutdown)
+                .with_shutdown_timeout(Duration::from_millis(50)),
+        )
+        .await;
+
+        let requests = format!("{}\n{}\n", sleep_request(1, 10_000), sleep_request(2, 0));
+        input.write_all(requests.as_bytes()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        trigger.trigger();
+
+        let closed = tokio::time::timeout(Duration::from_secs(2), async {
+            while output.next_line().await.unwrap().is_some() {}
+        })
+        .await;
+        assert!(
+            closed.is_ok(),
+            "shutdown was stuck behind the in-flight limit"
+        );
+    }
+
+    async fn next_message(lines: &mut Lines<BufReader<DuplexStream>>) -> Value {
+        let line = lines.next_line().await.unwrap().expect("stream closed");
+        serde_json::from_str(&line).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_progress_notifications_precede_response() {
+        let (mut input, mut output) = start(DEFAULT_MAX_IN_FLIGHT).await;
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {
+                "name": "progress",
+                "arguments": {"steps": 2},
+                "_meta": {"progressToken": "abc"}
+            }
+        });
+        input
+            .write_all(format!("{}\n", request).as_bytes())
+            .await
+            .unwrap();
+
+        for step in 1..=2 {
+            let notification = next_message(&mut output).await;
+            assert_eq!(notification["method"], "notifications/progress");
+            assert!(notification.get("id").is_none());
+            assert_eq!(notification["params"]["progressToken"], "abc");
+            assert_eq!(notification["params"]["progress"], step as f64);
+            assert_eq!(notification["params"]["total"], 2.0);
+            assert_eq!(notification["params"]["message"], "working");
+        }
+
+        let response = next_message(&mut output).await;
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["result"]["structuredContent"]["steps"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_no_progress_without_token() {
+        let (mut input, mut output) = start(DEFAULT_MAX_IN_FLIGHT).await;
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {"name": "progress", "arguments": {"steps": 2}}
+        });
+        input
+            .write_all(format!("{}\n", request).as_bytes())
+            .await
+            .unwrap();
+
+        let response = next_message(&mut output).await;
+        assert_eq!(response["id"], 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_resource_updates_over_stdio() {
+        let (mut input, mut output) = start(DEFAULT_MAX_IN_FLIGHT).await;
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "resources/subscribe",
+            "params": {"uri": "time://now/UTC"}
+        });
+        input
+            .write_all(format!("{}\n", request).as_bytes())
+            .await
+            .unwrap();
+
+        let response = next_message(&mut output).await;
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["result"], json!({}));
+
+        let notification = next_message(&mut output).await;
+        assert_eq!(notification["method"], "notifications/resources/updated");
+        assert_eq!(notification["params"]["uri"], "time://now/UTC");
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_drains_in_flight_requests() {
+        let (trigger, shutdown) = shutdown::channel();
+        let (mut input, mut output) = start_handler(
+            StdioHandler::new()
+                .with_shutdown(shutdown)
+                .with_shutdown_timeout(Duration::from_secs(5)),
+        )
+        .await;
+
+        input
+            .write_all(format!("{}\n", sleep_request(1, 100)).as_bytes())
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        trigger.trigger();
+
+        // Input is no longer read once shutdown has been triggered, but the
+        // request already in flight is still answered before output closes.
+        let _ = input
+            .write_all(format!("{}\n", sleep_request(2, 0)).as_bytes())
+            .await;
+        assert_eq!(next_id(&mut output).await, 1);
+        assert!(output.next_line().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_abandons_requests_past_deadline() {
+        let (trigger, shutdown) = shutdown::channel();
+        let (mut input, mut output) = start_handler(
+            StdioHandler::new()
+                .with_shutdown(shutdown)
+                .with_shutdown_timeout(Duration::from_millis(50)),
+        )
+        .await;
+
+        input
+            .write_all(format!("{}\n", sleep_request(1, 10_000)).as_bytes())
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        trigger.trigger();
+
+        let closed = tokio::time::timeout(Duration::from_secs(2), output.next_line())
+            .await
+            .expect("handler outlived the shutdown deadline");
+        assert!(closed.unwrap().is_none());
+    }
+}
+
+#[cfg(test)]
+mod shutdown_tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_default_shutdown_never_fires() {
+        let shutdown = Shutdown::default();
+        assert!(!shutdown.is_triggered());
+
+        let waited = tokio::time::timeout(Duration::from_millis(20), shutdown.wait()).await;
+        assert!(waited.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_trigger_fires_every_clone() {
+        let (trigger, shutdown) = shutdown::channel();
+        let clone = shutdown.clone();
+
+        trigger.trigger();
+
+        assert!(shutdown.is_triggered());
+        shutdown.wait().await;
+        clone.wait().await;
+    }
+
+    #[tokio::test]
+    async fn test_with_deadline_runs_to_completion_without_shutdown() {
+        let shutdown = Shutdown::default();
+
+        let output = shutdown
+            .with_deadline(Duration::from_millis(1), async {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                42
+            })
+            .await;
+
+        assert_eq!(output, Some(42));
+    }
+
+    #[tokio::test]
+    async fn test_with_deadline_cuts_off_work_after_shutdown() {
+        let (trigger, shutdown) = shutdown::channel();
+        trigger.trigger();
+
+        let output = shutdown
+            .with_deadline(Duration::from_millis(20), std::future::pending::<()>())
+            .await;
+
+        assert!(output.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_drain_waits_for_guards() {
+        let drain = Drain::new();
+        let guard = drain.guard();
+        let released = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+
+        let flag = released.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            flag.store(true, std::sync::atomic::Ordering::SeqCst);
+            drop(guard);
+        });
+
+        drain.wait().await;
+        assert!(released.load(std::sync::atomic::Ordering::SeqCst));
+    }
+}
+
+#[cfg(test)]
+mod session_tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cancel_unknown_request() {
+        let session = Session::new();
+
+        assert!(!session.cancel(&json!(42)));
+        assert_eq!(session.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_completed_requests_are_untracked() {
+        let dispatcher = Dispatcher::new();
+        let session = ready_session(&dispatcher).await;
+        let response = dispatcher
+            .handle_message(
+                &session,
+                None,
+                json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"}),
+            )
+            .await;
+
+        assert!(response.is_some());
+        assert_eq!(session.in_flight(), 0);
+        assert!(!session.cancel(&json!(1)));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_without_request_id_is_ignored() {
+        let response = Dispatcher::new()
+            .handle_message(
+                &Session::new(),
+                None,
+                json!({"jsonrpc": "2.0", "method": "notifications/cancelled", "params": {}}),
+            )
+            .await;
+
+        assert!(response.is_none());
+    }
+}
+
+#[cfg(test)]
+mod logging_tests {
+    use super::*;
+    use tokio::sync::mpsc;
+    use tracing_subscriber::prelude::*;
+
+    /// Drains every `notifications/message` data payload queued for a session.
+    fn received_messages(receiver: &mut mpsc::Receiver<Value>) -> Vec<Value> {
+        std::iter::from_fn(|| receiver.try_recv().ok())
+            .filter(|n| n["method"] == "notifications/message")
+            .map(|n| n["params"].clone())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_ping() {
+        let result = Dispatcher::new().dispatch("ping", None).await.unwrap();
+
+        assert_eq!(result, json!({}));
+    }
+
+    #[tokio::test]
+    async fn test_initialize_advertises_logging() {
+        let result = Dispatcher::new()
+            .dispatch("initialize", Some(json!({})))
+            .await
+            .unwrap();
+
+        assert!(result["capabilities"]["logging"].is_object());
+    }
+
+    #[tokio::test]
+    async fn test_set_level_validates_level() {
+        let dispatcher = Dispatcher::new();
+
+        let error = dispatcher
+            .dispatch("logging/setLevel", Some(json!({"level": "verbose"})))
+            .await
+            .unwrap_err();
+        assert_eq!(error.code, -32602);
+
+        let error = dispatcher
+            .dispatch("logging/setLevel", Some(json!({})))
+            .await
+            .unwrap_err();
+        assert_eq!(error.code, -32602);
+    }
+
+    #[tokio::test]
+    async fn test_set_level_records_session_level() {
+        let session = Session::new();
+        assert_eq!(session.log_level(), None);
+
+        Dispatcher::new()
+            .dispatch_with_session(
+                &session,
+                None,
+                "logging/setLevel",
+                Some(json!({"level": "error"})),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(session.log_level(), Some(LogLevel::Error));
+    }
+
+    #[test]
+    fn test_log_level_ordering() {
+        assert!(LogLevel::Debug < LogLevel::Info);
+        assert!(LogLevel::Warning < LogLevel::Error);
+        assert_eq!("critical".parse::<LogLevel>().unwrap(), LogLevel::Critical);
+        assert_eq!(LogLevel::from(&tracing::Level::WARN), LogLevel::Warning);
+        assert_eq!(LogLevel::from(&tracing::Level::TRACE), LogLevel::Debug);
+    }
+
+    #[test]
+    fn test_events_forwarded_at_or_above_level() {
+        let (outbound, mut receiver) = mpsc::channel(16);
+        let session = Session::with_outbound(outbound);
+        session.set_log_level(LogLevel::Warning);
+
+        let subscriber = tracing_subscriber::registry().with(McpLogLayer::new());
+        tracing::subscriber::with_default(subscriber, || {
+            session.span().in_scope(|| {
+                tracing::info!("logging-test: below threshold");
+                tracing::warn!(zone = "Mars/Olympus", "logging-test: structured");
+                tracing::error!("logging-test: plain");
+            })
+        });
+
+        let messages: Vec<Value> = received_messages(&mut receiver)
+            .into_iter()
+            .filter(|m| m["data"].to_string().contains("logging-test"))
+            .collect();
+
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0]["level"], "warning");
+        assert_eq!(messages[0]["logger"], "unit_tests::logging_tests");
+        assert_eq!(
+            messages[0]["data"],
+            json!({"message": "logging-test: structured", "zone": "Mars/Olympus"})
+        );
+        assert_eq!(messages[1]["level"], "error");
+        assert_eq!(messages[1]["data"], "logging-test: plain");
+    }
+
+    #[test]
+    fn test_sessions_without_level_receive_nothing() {
+        let (outbound, mut receiver) = mpsc::channel(16);
+        let session = Session::with_outbound(outbound);
+
+        let subscriber = tracing_subscriber::registry().with(McpLogLayer::new());
+        tracing::subscriber::with_default(subscriber, || {
+            session
+                .span()
+                .in_scope(|| tracing::error!("logging-test: unsubscribed"));
+        });
+
+        assert!(received_messages(&mut receiver).is_empty());
+    }
+
+    #[test]
+    fn test_events_only_reach_their_own_session() {
+        let (outbound, mut receiver) = mpsc::channel(16);
+        let session = Session::with_outbound(outbound);
+        session.set_log_level(LogLevel::Debug);
+        let (other_outbound, mut other_receiver) = mpsc::channel(16);
+        let other = Session::with_outbound(other_outbound);
+        other.set_log_level(LogLevel::Debug);
+
+        let subscriber = tracing_subscriber::registry().with(McpLogLayer::new());
+        tracing::subscriber::with_default(subscriber, || {
+            session.span().in_scope(|| {
+                let _nested = tracing::info_span!("nested").entered();
+                tracing::info!("logging-test: mine");
+            });
+            tracing::warn!("logging-test: rejected bearer token");
+        });
+
+        let data: Vec<Value> = received_messages(&mut receiver)
+            .into_iter()
+            .map(|m| m["data"].clone())
+            .collect();
+        assert_eq!(data, vec![json!("logging-test: mine")]);
+        assert!(received_messages(&mut other_receiver).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_dispatched_events_stay_in_session() {
+        let subscriber = tracing_subscriber::registry().with(McpLogLayer::new());
+        let _default = tracing::subscriber::set_default(subscriber);
+        let dispatcher = Dispatcher::new();
+
+        let (outbound, mut receiver) = mpsc::channel(16);
+        let watcher = Session::with_outbound(outbound);
+        for message in [
+            json!({"jsonrpc": "2.0", "id": 0, "method": "initialize", "params": {}}),
+            json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+            json!({"jsonrpc": "2.0", "id": 1, "method": "logging/setLevel", "params": {"level": "debug"}}),
+        ] {
+            dispatcher.handle_message(&watcher, None, message).await;
+        }
+        let own: Vec<Value> = received_messages(&mut receiver);
+        assert!(own
+            .iter()
+            .any(|m| m["data"] == "Client log level set to debug"));
+
+        // A second initialize is rejected with a warning, which only the
+        // session that sent it may see.
+        let (other_outbound, mut other_receiver) = mpsc::channel(16);
+        let other = Session::with_outbound(other_outbound);
+        other.set_log_level(LogLevel::Debug);
+        for id in [0, 1] {
+            dispatcher
+                .handle_message(
+                    &other,
+                    None,
+                    json!({"jsonrpc": "2.0", "id": id, "method": "initialize", "params": {}}),
+                )
+                .await;
+        }
+        assert!(received_messages(&mut other_receiver)
+            .iter()
+            .any(|m| m["level"] == "warning"));
+        assert!(received_messages(&mut receiver).is_empty());
+    }
+}
+
+#[cfg(test)]
+mod completion_tests {
+    use super::*;
+
+    async fn complete(reference: Value, name: &str, value: &str) -> McpResult<Value> {
+        Dispatcher::new()
+            .dispatch(
+                "completion/complete",
+                Some(json!({
+                    "ref": reference,
+                    "argument": {"name": name, "value": value}
+                })),
+            )
+            .await
+    }
+
+    #[test]
+    fn test_city_name_fuzzy_match() {
+        assert_eq!(
+            completion::complete_timezone("new york")[0],
+            "America/New_York"
+        );
+        assert_eq!(
+            completion::complete_timezone("New-York")[0],
+            "America/New_York"
+        );
+        assert_eq!(
+            completion::complete_timezone("newyork")[0],
+            "America/New_York"
+        );
+        assert_eq!(completion::complete_timezone("tokyo")[0], "Asia/Tokyo");
+    }
+
+    #[test]
+    fn test_prefix_matches_rank_first() {
+        let values = completion::complete_timezone("europe/l");
+
+        let prefix_matches = values.iter().filter(|v| v.starts_with("Europe/L")).count();
+        assert!(prefix_matches > 0);
+        assert!(values[..prefix_matches]
+            .iter()
+            .all(|v| v.starts_with("Europe/L")));
+    }
+
+    #[test]
+    fn test_shorter_names_rank_higher() {
+        assert_eq!(completion::complete_timezone("utc")[0], "UTC");
+    }
+
+    #[test]
+    fn test_no_match() {
+        assert!(completion::complete_timezone("zzzzqqq").is_empty());
+    }
+
+    #[test]
+    fn test_regions_and_formats() {
+        assert_eq!(completion::complete_region("eur"), vec!["Europe"]);
+        assert!(completion::complete_region("").contains(&"America".to_string()));
+        assert_eq!(
+            completion::complete("format", "iso"),
+            vec!["iso", "iso8601"]
+        );
+        assert_eq!(completion::complete("units", "h"), vec!["hours"]);
+        assert!(completion::complete("user_query", "what").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_prompt_argument_completion() {
+        let result = complete(
+            json!({"type": "ref/prompt", "name": "time_query_assistant"}),
+            "timezone",
+            "new york",
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result["completion"]["values"][0], "America/New_York");
+        assert_eq!(result["completion"]["hasMore"], false);
+    }
+
+    #[tokio::test]
+    async fn test_resource_variable_completion_is_capped() {
+        let result = complete(
+            json!({"type": "ref/resource", "uri": "time://zone/{tz}"}),
+            "tz",
+            "",
+        )
+        .await
+        .unwrap();
+
+        let values = result["completion"]["values"].as_array().unwrap();
+        assert_eq!(values.len(), completion::MAX_COMPLETIONS);
+        assert!(result["completion"]["total"].as_u64().unwrap() > 100);
+        assert_eq!(result["completion"]["hasMore"], true);
+    }
+
+    #[tokio::test]
+    async fn test_completion_errors() {
+        let error = complete(
+            json!({"type": "ref/prompt", "name": "nope"}),
+            "timezone",
+            "a",
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.code, -32602);
+
+        let error = complete(json!({"type": "ref/tool", "name": "x"}), "timezone", "a")
+            .await
+            .unwrap_err();
+        assert_eq!(error.code, -32602);
+    }
+
+    #[tokio::test]
+    async fn test_initialize_advertises_completions() {
+        let result = Dispatcher::new()
+            .dispatch("initialize", Some(json!({})))
+            .await
+            .unwrap();
+
+        assert!(result["capabilities"]["completions"].is_object());
+    }
+
+    #[tokio::test]
+    async fn test_prompt_uses_timezone_argument() {
+        let dispatcher = Dispatcher::new();
+        let result = dispatcher
+            .dispatch(
+                "prompts/get",
+                Some(json!({
+                    "name": "time_query_assistant",
+                    "arguments": {"user_query": "lunch?", "timezone": "Asia/Tokyo"}
+                })),
+            )
+            .await
+            .unwrap();
+        let text = result["messages"][0]["content"]["text"].as_str().unwrap();
+        assert!(text.contains("Asia/Tokyo"));
+
+        let error = dispatcher
+            .dispatch(
+                "prompts/get",
+                Some(json!({
+                    "name": "time_query_assistant",
+                    "arguments": {"timezone": "Nowhere/Special"}
+                })),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(error.code, -32602);
+    }
+}
+
+#[cfg(test)]
+mod resource_template_tests {
+    use super::*;
+    use time_mcp_server::resources::TimeResource;
+
+    async fn read(uri: &str) -> McpResult<Value> {
+        let result = Dispatcher::new()
+            .dispatch("resources/read", Some(json!({"uri": uri})))
+            .await?;
+        assert_eq!(result["contents"][0]["uri"], uri);
+        assert_eq!(result["contents"][0]["mimeType"], "application/json");
+        let text = result["contents"][0]["text"].as_str().unwrap();
+        Ok(serde_json::from_str(text).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_resources_use_time_scheme() {
+        let result = Dispatcher::new()
+            .dispatch("resources/list", None)
+            .await
+            .unwrap();
+
+        let uris: Vec<&str> = result["resources"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["uri"].as_str().unwrap())
+            .collect();
+        assert_eq!(uris, vec!["time://timezones", "time://formats"]);
+    }
+
+    #[tokio::test]
+    async fn test_templates_list() {
+        let result = Dispatcher::new()
+            .dispatch("resources/templates/list", None)
+            .await
+            .unwrap();
+
+        let templates: Vec<&str> = result["resourceTemplates"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["uriTemplate"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            templates,
+            vec![
+                "time://zone/{tz}",
+                "time://now/{tz}{?cadence}",
+                "time://zone/{tz}/transitions/{year}",
+                "time://calendar/{year}/{month}"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_static_resources_and_legacy_uris() {
+        assert!(
+            read("time://timezones").await.unwrap()["total_count"]
+                .as_u64()
+                .unwrap()
+                > 400
+        );
+        assert!(read("timezone_database").await.unwrap()["timezones"].is_array());
+        assert!(read("time://formats").await.unwrap()["iso8601_examples"].is_array());
+        assert!(read("time_formats").await.unwrap()["rfc3339_examples"].is_array());
+    }
+
+    #[tokio::test]
+    async fn test_read_zone() {
+        let zone = read("time://zone/Asia/Tokyo").await.unwrap();
+
+        assert_eq!(zone["timezone"], "Asia/Tokyo");
+        assert_eq!(zone["offset"], "+09:00");
+        assert_eq!(zone["dst_active"], false);
+        assert!(zone["current_time"].as_str().unwrap().ends_with("+09:00"));
+
+        let encoded = read("time://zone/Asia%2FTokyo").await.unwrap();
+        assert_eq!(encoded["timezone"], "Asia/Tokyo");
+    }
+
+    #[tokio::test]
+    async fn test_read_transitions() {
+        let result = read("time://zone/America/New_York/transitions/2024")
+            .await
+            .unwrap();
+        let transitions = result["transitions"].as_array().unwrap();
+
+        assert_eq!(result["timezone"], "America/New_York");
+        assert_eq!(transitions.len(), 2);
+        assert_eq!(transitions[0]["at"], "2024-03-10T07:00:00+00:00");
+        assert_eq!(transitions[0]["offset_before"], "-05:00");
+        assert_eq!(transitions[0]["offset_after"], "-04:00");
+        assert_eq!(transitions[0]["abbreviation_after"], "EDT");
+        assert_eq!(transitions[0]["dst_active"], true);
+        assert_eq!(transitions[1]["at"], "2024-11-03T06:00:00+00:00");
+        assert_eq!(transitions[1]["dst_active"], false);
+
+        let utc = read("time://zone/UTC/transitions/2024").await.unwrap();
+        assert!(utc["transitions"].as_array().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_read_calendar() {
+        let calendar = read("time://calendar/2024/2").await.unwrap();
+
+        assert_eq!(calendar["month_name"], "February");
+        assert_eq!(calendar["days_in_month"], 29);
+        assert_eq!(calendar["leap_year"], true);
+        assert_eq!(calendar["first_weekday"], "Thursday");
+        assert_eq!(calendar["days"][0]["date"], "2024-02-01");
+        assert_eq!(calendar["days"][0]["iso_week"], 5);
+    }
+
+    #[tokio::test]
+    async fn test_read_errors() {
+        assert_eq!(
+            read("time://zone/Mars/Base").await.unwrap_err().code,
+            -32000
+        );
+        assert_eq!(
+            read("time://calendar/2024/13").await.unwrap_err().code,
+            -32602
+        );
+        assert_eq!(
+            read("time://zone/UTC/transitions/soon")
+                .await
+                .unwrap_err()
+                .code,
+            -32602
+        );
+        assert_eq!(read("time://nowhere").await.unwrap_err().code, -32602);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            TimeResource::parse("time://calendar/2025/12").unwrap(),
+            TimeResource::Calendar(2025, 12)
+        );
+        assert_eq!(
+            TimeResource::parse("time://zone/Europe/Paris").unwrap(),
+            TimeResource::Zone(chrono_tz::Europe::Paris)
+        );
+    }
+
+    #[test]
+    fn test_uri_is_canonical() {
+        let uri = |uri: &str| TimeResource::parse(uri).unwrap().uri();
+
+        assert_eq!(uri("timezone_database"), "time://timezones");
+        assert_eq!(uri("time_formats"), "time://formats");
+        assert_eq!(
+            uri("time://zone/Europe%2FParis"),
+            "time://zone/Europe/Paris"
+        );
+        assert_eq!(
+            uri("time://zone/America%2FNew_York/transitions/2025"),
+            "time://zone/America/New_York/transitions/2025"
+        );
+        assert_eq!(uri("time://now/UTC?cadence=minute"), "time://now/UTC");
+        assert_eq!(
+            uri("time://now/UTC?cadence=hour"),
+            "time://now/UTC?cadence=hour"
+        );
+        assert_eq!(uri("time://calendar/2025/03"), "time://calendar/2025/3");
+
+        let page = format!(
+            "time://timezones?cursor={}",
+            time_mcp_server::pagination::encode_cursor(100)
+        );
+        assert_eq!(uri(&page), page);
+    }
+
+    #[tokio::test]
+    async fn test_completion_requires_known_template() {
+        let error = Dispatcher::new()
+            .dispatch(
+                "completion/complete",
+                Some(json!({
+                    "ref": {"type": "ref/resource", "uri": "time://unknown/{tz}"},
+                    "argument": {"name": "tz", "value": "a"}
+                })),
+            )
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.code, -32602);
+    }
+}
+
+#[cfg(test)]
+mod subscription_tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use time_mcp_server::resources::{Cadence, TimeResource};
+    use tokio::sync::mpsc;
+
+    async fn call(session: &Session, method: &str, uri: &str) -> McpResult<Value> {
+        Dispatcher::new()
+            .dispatch_with_session(session, None, method, Some(json!({"uri": uri})))
+            .await
+    }
+
+    #[test]
+    fn test_minute_and_hour_ticks() {
+        let at = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 30).unwrap();
+
+        assert_eq!(
+            Cadence::Minute.next_tick(chrono_tz::UTC, at),
+            Some(Utc.with_ymd_and_hms(2024, 6, 1, 12, 1, 0).unwrap())
+        );
+        assert_eq!(
+            Cadence::Hour.next_tick(chrono_tz::UTC, at),
+            Some(Utc.with_ymd_and_hms(2024, 6, 1, 13, 0, 0).unwrap())
+        );
+        // Kolkata is UTC+05:30, so its hours start at half past in UTC.
+        assert_eq!(
+            Cadence::Hour.next_tick(chrono_tz::Asia::Kolkata, at),
+            Some(Utc.with_ymd_and_hms(2024, 6, 1, 12, 30, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_transition_ticks() {
+        let at = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+
+        assert_eq!(
+            Cadence::Transition.next_tick(chrono_tz::America::New_York, at),
+            Some(Utc.with_ymd_and_hms(2024, 3, 10, 7, 0, 0).unwrap())
+        );
+        assert_eq!(Cadence::Transition.next_tick(chrono_tz::UTC, at), None);
+    }
+
+    #[test]
+    fn test_parse_now_uri() {
+        assert_eq!(
+            TimeResource::parse("time://now/Europe/Paris").unwrap(),
+            TimeResource::Now(chrono_tz::Europe::Paris, Cadence::Minute)
+        );
+        assert_eq!(
+            TimeResource::parse("time://now/Europe/Paris?cadence=transition").unwrap(),
+            TimeResource::Now(chrono_tz::Europe::Paris, Cadence::Transition)
+        );
+        assert!(TimeResource::parse("time://now/UTC?cadence=daily").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_now() {
+        let result = call(&Session::new(), "resources/read", "time://now/Asia/Tokyo")
+            .await
+            .unwrap();
+        let now: Value =
+            serde_json::from_str(result["contents"][0]["text"].as_str().unwrap()).unwrap();
+
+        assert_eq!(now["timezone"], "Asia/Tokyo");
+        assert_eq!(now["offset"], "+09:00");
+        assert!(now["unix"].is_i64());
+    }
+
+    #[tokio::test]
+    async fn test_initialize_advertises_subscribe() {
+        let result = Dispatcher::new()
+            .dispatch("initialize", Some(json!({})))
+            .await
+            .unwrap();
+
+        assert_eq!(result["capabilities"]["resources"]["subscribe"], true);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_subscribe_and_unsubscribe() {
+        let (outbound, mut receiver) = mpsc::channel(16);
+        let session = Session::with_outbound(outbound);
+
+        call(
+            &session,
+            "resources/subscribe",
+            "time://now/UTC?cadence=hour",
+        )
+        .await
+        .unwrap();
+        assert_eq!(session.subscriptions(), vec!["time://now/UTC?cadence=hour"]);
+
+        for _ in 0..2 {
+            let notification = receiver.recv().await.unwrap();
+            assert_eq!(notification["method"], "notifications/resources/updated");
+            assert_eq!(notification["params"]["uri"], "time://now/UTC?cadence=hour");
+        }
+
+        call(
+            &session,
+            "resources/unsubscribe",
+            "time://now/UTC?cadence=hour",
+        )
+        .await
+        .unwrap();
+        assert!(session.subscriptions().is_empty());
+        assert!(!session.unsubscribe("time://now/UTC?cadence=hour"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_dropping_session_stops_updates() {
+        let (outbound, mut receiver) = mpsc::channel(16);
+        let session = Session::with_outbound(outbound);
+
+        call(&session, "resources/subscribe", "time://now/UTC")
+            .await
+            .unwrap();
+        drop(session);
+
+        assert!(receiver.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_errors_and_static_resources() {
+        let session = Session::new();
+
+        let error = call(&session, "resources/subscribe", "time://now/Mars/Base")
+            .await
+            .unwrap_err();
+        assert_eq!(error.code, -32000);
+
+        call(&session, "resources/subscribe", "time://formats")
+            .await
+            .unwrap();
+        assert!(session.subscriptions().is_empty());
+
+        let error = Dispatcher::new()
+            .dispatch_with_session(&session, None, "resources/subscribe", None)
+            .await
+            .unwrap_err();
+        assert_eq!(error.code, -32602);
+    }
+}
+
+#[cfg(test)]
+mod pagination_tests {
+    use super::*;
+
+    async fn list(
+        dispatcher: &Dispatcher,
+        method: &str,
+        cursor: Option<Value>,
+    ) -> McpResult<Value> {
+        let params = cursor.map(|cursor| json!({ "cursor": cursor }));
+        dispatcher.dispatch(method, params).await
+    }
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = pagination::encode_cursor(150);
+
+        assert!(!cursor.contains("150"));
+        assert_eq!(pagination::decode_cursor(&cursor), Some(150));
+        assert_eq!(pagination::decode_cursor("not-a-cursor"), None);
+        assert_eq!(pagination::decode_cursor(""), None);
+    }
+
+    #[test]
+    fn test_paginate_pages() {
+        let first = pagination::paginate((0..5).collect(), 0, 2);
+        assert_eq!(first.items, vec![0, 1]);
+
+        let offset = pagination::decode_cursor(first.next_cursor.as_deref().unwrap()).unwrap();
+        let second = pagination::paginate((0..5).collect(), offset, 2);
+        assert_eq!(second.items, vec![2, 3]);
+
+        let last = pagination::paginate((0..5).collect(), 4, 2);
+        assert_eq!(last.items, vec![4]);
+        assert!(last.next_cursor.is_none());
+
+        let past_end = pagination::paginate((0..5).collect::<Vec<_>>(), 10, 2);
+        assert!(past_end.items.is_empty());
+        assert!(past_end.next_cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_tools_list_walks_all_pages() {
+        let dispatcher = Dispatcher::new().with_page_size(4);
+        let mut names = Vec::new();
+        let mut cursor = None;
+
+        loop {
+            let result = list(&dispatcher, "tools/list", cursor).await.unwrap();
+            for tool in result["tools"].as_array().unwrap() {
+                names.push(tool["name"].as_str().unwrap().to_string());
+            }
+            match result.get("nextCursor") {
+                Some(next) => cursor = Some(next.clone()),
+                None => break,
+            }
+        }
+
+        assert_eq!(names.len(), dispatcher.tools().len());
+        assert_eq!(names, dispatcher.tools().names().collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_default_page_size_returns_everything_without_cursor() {
+        let dispatcher = Dispatcher::new();
+        assert_eq!(dispatcher.page_size(), pagination::DEFAULT_PAGE_SIZE);
+
+        for method in [
+            "tools/list",
+            "resources/list",
+            "resources/templates/list",
+            "prompts/list",
+        ] {
+            let result = list(&dispatcher, method, None).await.unwrap();
+            assert!(result.get("nextCursor").is_none(), "{}", method);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resource_lists_paginate() {
+        let dispatcher = Dispatcher::new().with_page_size(1);
+
+        let first = list(&dispatcher, "resources/list", None).await.unwrap();
+        assert_eq!(first["resources"][0]["uri"], "time://timezones");
+
+        let second = list(
+            &dispatcher,
+            "resources/list",
+            Some(first["nextCursor"].clone()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(second["resources"][0]["uri"], "time://formats");
+        assert!(second.get("nextCursor").is_none());
+
+        let templates = list(&dispatcher, "resources/templates/list", None)
+            .await
+            .unwrap();
+        assert_eq!(templates["resourceTemplates"].as_array().unwrap().len(), 1);
+        assert!(templates["nextCursor"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_cursor_is_invalid_params() {
+        let dispatcher = Dispatcher::new();
+
+        for cursor in [json!("bogus"), json!(42)] {
+            for method in ["tools/list", "resources/list", "prompts/list"] {
+                let error = list(&dispatcher, method, Some(cursor.clone()))
+                    .await
+                    .unwrap_err();
+                assert_eq!(error.code, -32602);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_timezones_page_size_and_cursor() {
+        let first = TimeTools::list_timezones(ListTimezonesArgs {
+            page_size: Some(10),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        assert_eq!(first.count, 10);
+        assert!(first.total > 400);
+
+        let second = TimeTools::list_timezones(ListTimezonesArgs {
+            page_size: Some(10),
+            cursor: first.next_cursor.clone(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        assert_eq!(second.count, 10);
+        assert!(second
+            .timezones
+            .iter()
+            .all(|tz| !first.timezones.contains(tz)));
+    }
+
+    #[tokio::test]
+    async fn test_list_timezones_default_and_max_page_size() {
+        let default = TimeTools::list_timezones(ListTimezonesArgs::default())
+            .await
+            .unwrap();
+        assert_eq!(default.count, default.total);
+        assert!(default.next_cursor.is_none());
+
+        let first = TimeTools::list_timezones(ListTimezonesArgs {
+            page_size: Some(10),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        let continued = TimeTools::list_timezones(ListTimezonesArgs {
+            cursor: first.next_cursor,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        assert_eq!(continued.count, DEFAULT_TIMEZONE_PAGE_SIZE);
+        assert_eq!(continued.timezones[0], default.timezones[10]);
+
+        let everything = TimeTools::list_timezones(ListTimezonesArgs {
+            page_size: Some(usize::MAX),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        assert_eq!(everything.count, everything.total);
+        assert!(everything.next_cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_list_timezones_rejects_bad_page_args() {
+        let error = ListTimezonesTool
+            .execute(json!({"page_size": 0}))
+            .await
+            .unwrap_err();
+        assert_eq!(error.downcast_ref::<TimeError>().unwrap().code(), -32602);
+
+        let error = ListTimezonesTool
+            .execute(json!({"cursor": "bogus"}))
+            .await
+            .unwrap_err();
+        assert_eq!(error.downcast_ref::<TimeError>().unwrap().code(), -32602);
+    }
+
+    #[tokio::test]
+    async fn test_timezones_resource_pages() {
+        let dispatcher = Dispatcher::new();
+        let read = |uri: String| {
+            let dispatcher = dispatcher.clone();
+            async move {
+                let result = dispatcher
+                    .dispatch("resources/read", Some(json!({ "uri": uri })))
+                    .await?;
+                Ok::<Value, McpError>(
+                    serde_json::from_str(result["contents"][0]["text"].as_str().unwrap()).unwrap(),
+                )
+            }
+        };
+
+        let first = read("time://timezones".to_string()).await.unwrap();
+        assert_eq!(first["timezones"].as_array().unwrap().len(), 100);
+        let next_uri = first["next_uri"].as_str().unwrap().to_string();
+        assert!(next_uri.starts_with("time://timezones?cursor="));
+
+        let second = read(next_uri).await.unwrap();
+        assert_ne!(second["timezones"][0], first["timezones"][0]);
+        assert_eq!(second["total_count"], first["total_count"]);
+
+        let error = read("time://timezones?cursor=bogus".to_string())
+            .await
+            .unwrap_err();
+        assert_eq!(error.code, -32602);
+    }
+}
+
+#[cfg(test)]
+mod protocol_version_tests {
+    use super::*;
+
+    async fn initialize(session: &Session, version: Option<&str>) -> Value {
+        let params = match version {
+            Some(version) => json!({ "protocolVersion": version }),
+            None => json!({}),
+        };
+        Dispatcher::new()
+            .dispatch_with_session(session, None, "initialize", Some(params))
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn test_version_table() {
+        for version in ProtocolVersion::ALL {
+            assert_eq!(version.as_str().parse::<ProtocolVersion>(), Ok(*version));
+        }
+        assert_eq!(ProtocolVersion::ALL[0], ProtocolVersion::LATEST);
+        assert_eq!(ProtocolVersion::LATEST.as_str(), "2025-06-18");
+        assert!("1.0".parse::<ProtocolVersion>().is_err());
+        assert!(ProtocolVersion::V2024_11_05 < ProtocolVersion::V2025_03_26);
+    }
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(
+            ProtocolVersion::negotiate(Some("2024-11-05")),
+            ProtocolVersion::V2024_11_05
+        );
+        assert_eq!(
+            ProtocolVersion::negotiate(Some("2025-03-26")),
+            ProtocolVersion::V2025_03_26
+        );
+        assert_eq!(
+            ProtocolVersion::negotiate(Some("1999-01-01")),
+            ProtocolVersion::LATEST
+        );
+        assert_eq!(ProtocolVersion::negotiate(None), ProtocolVersion::LATEST);
+    }
+
+    #[tokio::test]
+    async fn test_initialize_records_negotiated_version() {
+        let session = Session::new();
+        assert!(!session.is_negotiated());
+        assert_eq!(session.protocol_version(), ProtocolVersion::LATEST);
+
+        let result = initialize(&session, Some("2025-03-26")).await;
+        assert_eq!(result["protocolVersion"], "2025-03-26");
+        assert!(session.is_negotiated());
+        assert_eq!(session.protocol_version(), ProtocolVersion::V2025_03_26);
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_version_gets_latest() {
+        for requested in ["1.0", "2099-01-01"] {
+            let result = initialize(&Session::new(), Some(requested)).await;
+            assert_eq!(result["protocolVersion"], ProtocolVersion::LATEST.as_str());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_completions_capability_is_gated() {
+        let result = initialize(&Session::new(), Some("2024-11-05")).await;
+        assert!(result["capabilities"].get("completions").is_none());
+        assert!(result["capabilities"]["tools"].is_object());
+
+        let result = initialize(&Session::new(), Some("2025-03-26")).await;
+        assert!(result["capabilities"]["completions"].is_object());
+    }
+
+    #[tokio::test]
+    async fn test_structured_output_is_gated() {
+        let dispatcher = Dispatcher::new();
+
+        for (version, structured) in [
+            ("2024-11-05", false),
+            ("2025-03-26", false),
+            ("2025-06-18", true),
+        ] {
+            let session = Session::new();
+            initialize(&session, Some(version)).await;
+
+            let tools = dispatcher
+                .dispatch_with_session(&session, None, "tools/list", None)
+                .await
+                .unwrap();
+            let list_timezones = tools["tools"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|tool| tool["name"] == "list_timezones")
+                .unwrap();
+            assert_eq!(list_timezones.get("outputSchema").is_some(), structured);
+
+            let result = dispatcher
+                .dispatch_with_session(
+                    &session,
+                    None,
+                    "tools/call",
+                    Some(json!({"name": "get_timezone_info", "arguments": {"timezone": "UTC"}})),
+                )
+                .await
+                .unwrap();
+            assert_eq!(result.get("structuredContent").is_some(), structured);
+            assert!(result["content"][0]["text"].is_string());
+
+            let error = dispatcher
+                .dispatch_with_session(
+                    &session, None,
+                    "tools/call",
+                    Some(json!({"name": "get_timezone_info", "arguments": {"timezone": "Mars/Base"}})),
+                )
+                .await
+                .unwrap();
+            assert_eq!(error["isError"], true);
+            assert_eq!(error.get("structuredContent").is_some(), structured);
+        }
+    }
+}
+
+#[cfg(test)]
+mod session_lifecycle_tests {
+    use super::*;
+
+    /// Returns the name of the client that called it.
+    struct WhoAmITool;
+
+    #[async_trait::async_trait]
+    impl Tool for WhoAmITool {
+        fn name(&self) -> &str {
+            "whoami"
+        }
+
+        fn description(&self) -> &str {
+            "Report the calling client"
+        }
+
+        fn input_schema(&self) -> Value {
+            json!({"type": "object"})
+        }
+
+        async fn execute(&self, _arguments: Value) -> anyhow::Result<Value> {
+            Ok(json!({"client": null}))
+        }
+
+        async fn execute_with_context(
+            &self,
+            _arguments: Value,
+            context: &ToolContext,
+        ) -> anyhow::Result<Value> {
+            Ok(json!({"client": context.client.as_ref().map(|c| c.name.clone())}))
+        }
+    }
+
+    fn request(id: u64, method: &str, params: Value) -> Value {
+        json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params})
+    }
+
+    fn initialized() -> Value {
+        json!({"jsonrpc": "2.0", "method": "notifications/initialized"})
+    }
+
+    #[tokio::test]
+    async fn test_state_transitions() {
+        let dispatcher = Dispatcher::new();
+        let session = Session::new();
+        assert_eq!(session.state(), SessionState::Uninitialized);
+
+        // initialized before initialize is ignored.
+        dispatcher
+            .handle_message(&session, None, initialized())
+            .await;
+        assert_eq!(session.state(), SessionState::Uninitialized);
+
+        dispatcher
+            .handle_message(&session, None, request(1, "initialize", json!({})))
+            .await;
+        assert_eq!(session.state(), SessionState::Initializing);
+
+        dispatcher
+            .handle_message(&session, None, initialized())
+            .await;
+        assert_eq!(session.state(), SessionState::Ready);
+
+        session.shut_down();
+        assert_eq!(session.state(), SessionState::ShuttingDown);
+    }
+
+    #[tokio::test]
+    async fn test_initialize_records_client_info_and_capabilities() {
+        let dispatcher = Dispatcher::new();
+        let session = Session::new();
+
+        dispatcher
+            .handle_message(
+                &session,
+                None,
+                request(
+                    1,
+                    "initialize",
+                    json!({
+                        "protocolVersion": "2025-06-18",
+                        "capabilities": {"roots": {"listChanged": true}},
+                        "clientInfo": {"name": "example-client", "version": "2.1.0"}
+                    }),
+                ),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            session.client_info(),
+            Some(ClientInfo {
+                name: "example-client".to_string(),
+                version: "2.1.0".to_string(),
+                title: None,
+            })
+        );
+        assert_eq!(session.client_capabilities()["roots"]["listChanged"], true);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_client_info_is_invalid_params() {
+        let dispatcher = Dispatcher::new();
+        let session = Session::new();
+
+        let response = dispatcher
+            .handle_message(
+                &session,
+                None,
+                request(1, "initialize", json!({"clientInfo": {"name": 5}})),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response["error"]["code"], -32602);
+        assert_eq!(session.state(), SessionState::Uninitialized);
+    }
+
+    #[tokio::test]
+    async fn test_requests_allowed_while_initializing() {
+        let dispatcher = Dispatcher::new();
+        let session = Session::new();
+
+        dispatcher
+            .handle_message(&session, None, request(1, "initialize", json!({})))
+            .await;
+        let response = dispatcher
+            .handle_message(&session, None, request(2, "tools/list", json!({})))
+            .await
+            .unwrap();
+
+        assert!(response["result"]["tools"].is_array());
+    }
+
+    #[tokio::test]
+    async fn test_shutting_down_rejects_all_but_ping() {
+        let dispatcher = Dispatcher::new();
+        let session = ready_session(&dispatcher).await;
+        session.shut_down();
+
+        let response = dispatcher
+            .handle_message(&session, None, request(1, "tools/list", json!({})))
+            .await
+            .unwrap();
+        assert_eq!(response["error"]["code"], -32600);
+        assert!(response["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("shutting down"));
+
+        let response = dispatcher
+            .handle_message(&session, None, request(2, "ping", json!({})))
+            .await
+            .unwrap();
+        assert_eq!(response["result"], json!({}));
+    }
+
+    #[tokio::test]
+    async fn test_tools_see_client_info() {
+        let mut registry = ToolRegistry::new();
+        registry.register(WhoAmITool);
+        let dispatcher = Dispatcher::with_tools(registry);
+        let session = Session::new();
+
+        dispatcher
+            .handle_message(
+                &session,
+                None,
+                request(
+                    1,
+                    "initialize",
+                    json!({"clientInfo": {"name": "example-client", "version": "1.0"}}),
+                ),
+            )
+            .await;
+
+        let response = dispatcher
+            .handle_message(
+                &session,
+                None,
+                request(2, "tools/call", json!({"name": "whoami"})),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            response["result"]["structuredContent"]["client"],
+            "example-client"
+        );
+
+        let result = dispatcher
+            .dispatch("tools/call", Some(json!({"name": "whoami"})))
+            .await
+            .unwrap();
+        assert_eq!(result["structuredContent"]["client"], Value::Null);
+    }
+}
@@ -0,0 +1,328 @@
+#![cfg(unix)]
+
+use serde_json::{json, Value};
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use time_mcp_server::config::DEFAULT_PROTOCOL_VERSION;
+use time_mcp_server::handlers::{dispatcher::Dispatcher, unix::UnixHandler};
+use time_mcp_server::registry::{Tool, ToolRegistry};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::UnixStream;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, timeout, Duration};
+
+struct Client {
+    writer: OwnedWriteHalf,
+    lines: Lines<BufReader<OwnedReadHalf>>,
+}
+
+impl Client {
+    async fn connect(path: &Path) -> Self {
+        let (reader, writer) = UnixStream::connect(path)
+            .await
+            .expect("Failed to connect")
+            .into_split();
+        Self {
+            writer,
+            lines: BufReader::new(reader).lines(),
+        }
+    }
+
+    async fn send(&mut self, message: Value) {
+        self.writer
+            .write_all(format!("{}\n", message).as_bytes())
+            .await
+            .unwrap();
+    }
+
+    async fn receive(&mut self) -> Value {
+        let line = timeout(Duration::from_secs(5), self.lines.next_line())
+            .await
+            .expect("Timed out waiting for a message")
+            .unwrap()
+            .expect("Connection closed");
+        serde_json::from_str(&line).unwrap()
+    }
+
+    async fn initialize(&mut self, name: &str) {
+        self.send(json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": DEFAULT_PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": {"name": name, "version": "1.0.0"}
+            }
+        }))
+        .await;
+        assert_eq!(self.receive().await["id"], 0);
+        self.send(json!({"jsonrpc": "2.0", "method": "notifications/initialized"}))
+            .await;
+    }
+}
+
+struct Server {
+    path: PathBuf,
+    shutdown: oneshot::Sender<()>,
+    task: JoinHandle<anyhow::Result<()>>,
+    _dir: tempfile::TempDir,
+}
+
+async fn spawn_server(handler: UnixHandler) -> Server {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("time-mcp.sock");
+    let (shutdown, stop) = oneshot::channel::<()>();
+
+    let socket_path = path.clone();
+    let task = tokio::spawn(async move {
+        handler
+            .run_until(&socket_path, async {
+                let _ = stop.await;
+            })
+            .await
+    });
+
+    for _ in 0..50 {
+        if path.exists() {
+            break;
+        }
+        sleep(Duration::from_millis(10)).await;
+    }
+
+    Server {
+        path,
+        shutdown,
+        task,
+        _dir: dir,
+    }
+}
+
+#[tokio::test]
+async fn test_unix_serves_concurrent_clients() {
+    let server = spawn_server(UnixHandler::default()).await;
+
+    let mut first = Client::connect(&server.path).await;
+    let mut second = Client::connect(&server.path).await;
+    first.initialize("first").await;
+    second.initialize("second").await;
+
+    first
+        .send(json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"}))
+        .await;
+    second
+        .send(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {"name": "get_timezone_info", "arguments": {"timezone": "Asia/Tokyo"}}
+        }))
+        .await;
+
+    assert!(first.receive().await["result"]["tools"].is_array());
+    assert_eq!(
+        second.receive().await["result"]["structuredContent"]["timezone"],
+        "Asia/Tokyo"
+    );
+}
+
+#[tokio::test]
+async fn test_unix_sessions_are_per_connection() {
+    let server = spawn_server(UnixHandler::default()).await;
+
+    let mut first = Client::connect(&server.path).await;
+    first.initialize("first").await;
+
+    let mut second = Client::connect(&server.path).await;
+    second
+        .send(json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"}))
+        .await;
+    assert_eq!(second.receive().await["error"]["code"], -32600);
+}
+
+#[tokio::test]
+async fn test_unix_parse_error_keeps_connection() {
+    let server = spawn_server(UnixHandler::default()).await;
+    let mut client = Client::connect(&server.path).await;
+
+    client.writer.write_all(b"not json\n").await.unwrap();
+    assert_eq!(client.receive().await["error"]["code"], -32700);
+
+    client
+        .send(json!({"jsonrpc": "2.0", "id": 1, "method": "ping"}))
+        .await;
+    assert_eq!(client.receive().await["result"], json!({}));
+}
+
+#[tokio::test]
+async fn test_unix_socket_permissions() {
+    let server = spawn_server(UnixHandler::default()).await;
+    let mode = std::fs::metadata(&server.path)
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    let server = spawn_server(UnixHandler::default().with_socket_mode(0o660)).await;
+    let mode = std::fs::metadata(&server.path)
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o660);
+
+    // The socket is staged elsewhere and moved in, leaving nothing behind.
+    let entries: Vec<_> = std::fs::read_dir(server.path.parent().unwrap())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(entries, vec!["time-mcp.sock"]);
+}
+
+#[tokio::test]
+async fn test_unix_socket_removed_on_shutdown() {
+    let server = spawn_server(UnixHandler::default()).await;
+    assert!(std::fs::metadata(&server.path)
+        .unwrap()
+        .file_type()
+        .is_socket());
+
+    server.shutdown.send(()).unwrap();
+    server.task.await.unwrap().unwrap();
+
+    assert!(!server.path.exists());
+}
+
+#[tokio::test]
+async fn test_unix_replaces_stale_socket() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("stale.sock");
+    drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+    assert!(path.exists());
+
+    let (shutdown, stop) = oneshot::channel::<()>();
+    let socket_path = path.clone();
+    let task = tokio::spawn(async move {
+        UnixHandler::default()
+            .run_until(&socket_path, async {
+                let _ = stop.await;
+            })
+            .await
+    });
+    sleep(Duration::from_millis(100)).await;
+
+    let mut client = Client::connect(&path).await;
+    client
+        .send(json!({"jsonrpc": "2.0", "id": 1, "method": "ping"}))
+        .await;
+    assert_eq!(client.receive().await["id"], 1);
+
+    shutdown.send(()).unwrap();
+    task.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn test_unix_refuses_live_socket_and_regular_file() {
+    let server = spawn_server(UnixHandler::default()).await;
+    let error = UnixHandler::default()
+        .run_until(&server.path, async {})
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("already in use"));
+    assert!(server.path.exists());
+
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("not-a-socket");
+    std::fs::write(&file, "data").unwrap();
+    let error = UnixHandler::default()
+        .run_until(&file, async {})
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("not a socket"));
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "data");
+}
+
+/// Sleeps for `ms` milliseconds, then returns its arguments.
+struct SleepTool;
+
+#[async_trait::async_trait]
+impl Tool for SleepTool {
+    fn name(&self) -> &str {
+        "sleep"
+    }
+
+    fn description(&self) -> &str {
+        "Sleep before answering"
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({"type": "object"})
+    }
+
+    async fn execute(&self, arguments: Value) -> anyhow::Result<Value> {
+        let ms = arguments["ms"].as_u64().unwrap_or(0);
+        sleep(Duration::from_millis(ms)).await;
+        Ok(arguments)
+    }
+}
+
+#[tokio::test]
+async fn test_unix_shutdown_drains_in_flight_requests() {
+    let mut registry = ToolRegistry::new();
+    registry.register(SleepTool);
+    let server =
+        spawn_server(UnixHandler::default().with_dispatcher(Dispatcher::with_tools(registry)))
+            .await;
+    let mut client = Client::connect(&server.path).await;
+    client.initialize("draining").await;
+
+    client
+        .send(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {"name": "sleep", "arguments": {"ms": 300}}
+        }))
+        .await;
+    sleep(Duration::from_millis(50)).await;
+    server.shutdown.send(()).unwrap();
+
+    let response = client.receive().await;
+    assert_eq!(response["id"], 1);
+    assert_eq!(response["result"]["structuredContent"]["ms"], 300);
+    assert!(client.lines.next_line().await.unwrap().is_none());
+
+    server.task.await.unwrap().unwrap();
+    assert!(!server.path.exists());
+}
+
+#[tokio::test]
+async fn test_unix_shutdown_deadline() {
+    let mut registry = ToolRegistry::new();
+    registry.register(SleepTool);
+    let handler = UnixHandler::default()
+        .with_dispatcher(Dispatcher::with_tools(registry))
+        .with_shutdown_timeout(Duration::from_millis(100));
+    let server = spawn_server(handler).await;
+    let mut client = Client::connect(&server.path).await;
+    client.initialize("stuck").await;
+
+    client
+        .send(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {"name": "sleep", "arguments": {"ms": 60_000}}
+        }))
+        .await;
+    sleep(Duration::from_millis(50)).await;
+    server.shutdown.send(()).unwrap();
+
+    timeout(Duration::from_secs(5), server.task)
+        .await
+        .expect("Server outlived the shutdown deadline")
+        .unwrap()
+        .unwrap();
+    assert!(!server.path.exists());
+}
@@ -0,0 +1,437 @@
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::net::TcpListener;
+use time_mcp_server::config::{ServerConfig, TransportType, DEFAULT_PROTOCOL_VERSION};
+use time_mcp_server::handlers::{dispatcher::Dispatcher, origin::OriginPolicy, ws::WsHandler};
+use time_mcp_server::logging::McpLogLayer;
+use time_mcp_server::registry::{Tool, ToolContext, ToolRegistry};
+use time_mcp_server::shutdown;
+use tokio::net::TcpStream;
+use tokio::time::{sleep, timeout, Duration};
+use tokio_tungstenite::{
+    connect_async, tungstenite::client::IntoClientRequest, tungstenite::Message, MaybeTlsStream,
+    WebSocketStream,
+};
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+fn get_available_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
+
+async fn spawn_ws_server(handler: WsHandler) -> u16 {
+    let port = get_available_port();
+
+    tokio::spawn(async move {
+        handler.run("127.0.0.1", port).await.unwrap();
+    });
+
+    sleep(Duration::from_millis(100)).await;
+    port
+}
+
+async fn spawn_test_server(auth_enabled: bool) -> u16 {
+    let config = ServerConfig {
+        transport: TransportType::WebSocket {
+            host: "127.0.0.1".to_string(),
+            port: 0,
+        },
+        auth_enabled,
+        ..ServerConfig::default()
+    };
+    spawn_ws_server(WsHandler::new(config)).await
+}
+
+async fn connect(port: u16) -> Socket {
+    let (socket, _) = connect_async(format!("ws://127.0.0.1:{}/mcp", port))
+        .await
+        .expect("Failed to connect");
+    socket
+}
+
+async fn send(socket: &mut Socket, message: Value) {
+    socket
+        .send(Message::Text(message.to_string()))
+        .await
+        .expect("Failed to send message");
+}
+
+async fn receive(socket: &mut Socket) -> Value {
+    loop {
+        let frame = timeout(Duration::from_secs(5), socket.next())
+            .await
+            .expect("Timed out waiting for a message")
+            .expect("Socket closed")
+            .expect("Failed to read message");
+
+        if let Message::Text(text) = frame {
+            return serde_json::from_str(&text).expect("Server sent invalid JSON");
+        }
+    }
+}
+
+/// Connects and completes the initialize handshake.
+async fn connect_initialized(port: u16) -> Socket {
+    let mut socket = connect(port).await;
+
+    send(
+        &mut socket,
+        json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": DEFAULT_PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": {"name": "ws-test-client", "version": "1.0.0"}
+            }
+        }),
+    )
+    .await;
+    let response = receive(&mut socket).await;
+    assert_eq!(response["id"], 0);
+    assert_eq!(
+        response["result"]["protocolVersion"],
+        DEFAULT_PROTOCOL_VERSION
+    );
+
+    send(
+        &mut socket,
+        json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+    )
+    .await;
+    socket
+}
+
+#[tokio::test]
+async fn test_ws_tools_list_and_call() {
+    let port = spawn_test_server(false).await;
+    let mut socket = connect_initialized(port).await;
+
+    send(
+        &mut socket,
+        json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"}),
+    )
+    .await;
+    let response = receive(&mut socket).await;
+    assert_eq!(response["id"], 1);
+    assert!(response["result"]["tools"].is_array());
+
+    send(
+        &mut socket,
+        json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "tools/call",
+            "params": {"name": "get_timezone_info", "arguments": {"timezone": "Europe/Paris"}}
+        }),
+    )
+    .await;
+    let response = receive(&mut socket).await;
+    assert_eq!(response["id"], 2);
+    assert_eq!(
+        response["result"]["structuredContent"]["timezone"],
+        "Europe/Paris"
+    );
+}
+
+#[tokio::test]
+async fn test_ws_rejects_requests_before_initialize() {
+    let port = spawn_test_server(false).await;
+    let mut socket = connect(port).await;
+
+    send(
+        &mut socket,
+        json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"}),
+    )
+    .await;
+    let response = receive(&mut socket).await;
+    assert_eq!(response["error"]["code"], -32600);
+}
+
+#[tokio::test]
+async fn test_ws_parse_error_and_batch() {
+    let port = spawn_test_server(false).await;
+    let mut socket = connect_initialized(port).await;
+
+    socket
+        .send(Message::Text("not json".to_string()))
+        .await
+        .unwrap();
+    let response = receive(&mut socket).await;
+    assert_eq!(response["error"]["code"], -32700);
+
+    send(
+        &mut socket,
+        json!([
+            {"jsonrpc": "2.0", "id": 1, "method": "ping"},
+            {"jsonrpc": "2.0", "id": 2, "method": "prompts/list"}
+        ]),
+    )
+    .await;
+    let response = receive(&mut socket).await;
+    assert_eq!(response.as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_ws_sessions_are_per_connection() {
+    let port = spawn_test_server(false).await;
+    let _first = connect_initialized(port).await;
+    let mut second = connect(port).await;
+
+    send(
+        &mut second,
+        json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"}),
+    )
+    .await;
+    let response = receive(&mut second).await;
+    assert_eq!(response["error"]["code"], -32600);
+}
+
+#[tokio::test]
+async fn test_ws_log_notifications() {
+    use tracing_subscriber::prelude::*;
+    let _ = tracing_subscriber::registry()
+        .with(McpLogLayer::new())
+        .try_init();
+
+    let port = spawn_test_server(false).await;
+    let mut socket = connect_initialized(port).await;
+
+    send(
+        &mut socket,
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "logging/setLevel",
+            "params": {"level": "info"}
+        }),
+    )
+    .await;
+
+    let mut saw_response = false;
+    let mut saw_log = false;
+    while !(saw_response && saw_log) {
+        let message = receive(&mut socket).await;
+        saw_response |= message["id"] == 1;
+        saw_log |= message["method"] == "notifications/message";
+    }
+}
+
+/// Reports `steps` progress updates before answering.
+struct ProgressTool;
+
+#[async_trait::async_trait]
+impl Tool for ProgressTool {
+    fn name(&self) -> &str {
+        "progress"
+    }
+
+    fn description(&self) -> &str {
+        "Report progress before answering"
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({"type": "object"})
+    }
+
+    async fn execute(&self, arguments: Value) -> anyhow::Result<Value> {
+        Ok(arguments)
+    }
+
+    async fn execute_with_context(
+        &self,
+        arguments: Value,
+        context: &ToolContext,
+    ) -> anyhow::Result<Value> {
+        let steps = arguments["steps"].as_u64().unwrap_or(0);
+        for step in 1..=steps {
+            context
+                .progress
+                .report(step as f64, Some(steps as f64), None);
+        }
+        Ok(arguments)
+    }
+}
+
+#[tokio::test]
+async fn test_ws_progress_notifications() {
+    let mut registry = ToolRegistry::new();
+    registry.register(ProgressTool);
+    let handler =
+        WsHandler::new(ServerConfig::default()).with_dispatcher(Dispatcher::with_tools(registry));
+    let port = spawn_ws_server(handler).await;
+    let mut socket = connect_initialized(port).await;
+
+    send(
+        &mut socket,
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {
+                "name": "progress",
+                "arguments": {"steps": 3},
+                "_meta": {"progressToken": 7}
+            }
+        }),
+    )
+    .await;
+
+    for step in 1..=3 {
+        let notification = receive(&mut socket).await;
+        assert_eq!(notification["method"], "notifications/progress");
+        assert_eq!(notification["params"]["progressToken"], 7);
+        assert_eq!(notification["params"]["progress"], step as f64);
+    }
+    let response = receive(&mut socket).await;
+    assert_eq!(response["id"], 1);
+}
+
+/// Sleeps for `ms` milliseconds, then returns its arguments.
+struct SleepTool;
+
+#[async_trait::async_trait]
+impl Tool for SleepTool {
+    fn name(&self) -> &str {
+        "sleep"
+    }
+
+    fn description(&self) -> &str {
+        "Sleep before answering"
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({"type": "object"})
+    }
+
+    async fn execute(&self, arguments: Value) -> anyhow::Result<Value> {
+        let ms = arguments["ms"].as_u64().unwrap_or(0);
+        sleep(Duration::from_millis(ms)).await;
+        Ok(arguments)
+    }
+}
+
+#[tokio::test]
+async fn test_ws_graceful_shutdown() {
+    let port = get_available_port();
+    let mut registry = ToolRegistry::new();
+    registry.register(SleepTool);
+    let (trigger, shutdown) = shutdown::channel();
+    let handler = WsHandler::new(ServerConfig::default())
+        .with_dispatcher(Dispatcher::with_tools(registry))
+        .with_shutdown(shutdown);
+    let server = tokio::spawn(async move { handler.run("127.0.0.1", port).await });
+    sleep(Duration::from_millis(100)).await;
+
+    let mut socket = connect_initialized(port).await;
+    send(
+        &mut socket,
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {"name": "sleep", "arguments": {"ms": 300}}
+        }),
+    )
+    .await;
+    sleep(Duration::from_millis(50)).await;
+    trigger.trigger();
+
+    let response = receive(&mut socket).await;
+    assert_eq!(response["id"], 1);
+    assert_eq!(response["result"]["structuredContent"]["ms"], 300);
+
+    // The server closes the socket once the in-flight request is answered.
+    let closed = timeout(Duration::from_secs(5), async {
+        while let Some(Ok(frame)) = socket.next().await {
+            if frame.is_close() {
+                break;
+            }
+        }
+    })
+    .await;
+    assert!(closed.is_ok(), "Socket stayed open after shutdown");
+
+    timeout(Duration::from_secs(5), server)
+        .await
+        .expect("Server did not stop")
+        .unwrap()
+        .unwrap();
+    assert!(connect_async(format!("ws://127.0.0.1:{}/mcp", port))
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_ws_upgrade_requires_auth() {
+    let port = spawn_test_server(true).await;
+
+    let error = connect_async(format!("ws://127.0.0.1:{}/mcp", port))
+        .await
+        .expect_err("Unauthenticated upgrade should fail");
+    match error {
+        tokio_tungstenite::tungstenite::Error::Http(response) => {
+            assert_eq!(response.status(), 401);
+        }
+        other => panic!("Unexpected error: {}", other),
+    }
+
+    let mut request = format!("ws://127.0.0.1:{}/mcp", port)
+        .into_client_request()
+        .unwrap();
+    request
+        .headers_mut()
+        .insert("authorization", "Bearer bogus".parse().unwrap());
+    assert!(connect_async(request).await.is_err());
+}
+
+#[tokio::test]
+async fn test_ws_rejects_untrusted_origin() {
+    let config = ServerConfig {
+        allowed_origins: OriginPolicy::new().with_origin("https://app.example.com"),
+        ..ServerConfig::default()
+    };
+    let port = spawn_ws_server(WsHandler::new(config)).await;
+    let upgrade = |origin: &str| {
+        let mut request = format!("ws://127.0.0.1:{}/mcp", port)
+            .into_client_request()
+            .unwrap();
+        request
+            .headers_mut()
+            .insert("origin", origin.parse().unwrap());
+        connect_async(request)
+    };
+
+    match upgrade("https://evil.example").await {
+        Err(tokio_tungstenite::tungstenite::Error::Http(response)) => {
+            assert_eq!(response.status(), 403)
+        }
+        other => panic!("expected a 403 rejection, got {:?}", other.map(|_| ())),
+    }
+
+    for origin in ["https://app.example.com", "http://localhost:5173"] {
+        let (mut socket, _) = upgrade(origin).await.expect("Failed to connect");
+        send(
+            &mut socket,
+            json!({"jsonrpc": "2.0", "id": 1, "method": "ping"}),
+        )
+        .await;
+        assert_eq!(receive(&mut socket).await["id"], 1);
+    }
+}
+
+#[tokio::test]
+async fn test_ws_health_check() {
+    let port = spawn_test_server(false).await;
+
+    let res = reqwest::get(format!("http://127.0.0.1:{}/health", port))
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+}